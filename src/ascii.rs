@@ -0,0 +1,320 @@
+use crate::error::GDSIIErrorKind;
+use crate::model::*;
+use byteorder::{BigEndian, ByteOrder};
+use std::fmt::Write;
+
+/// Stream Format data type names, indexed by data type
+const DATA_TYPES: [&str; 7] = [
+    "NODATA", "BITARRAY", "INT2", "INT4", "REAL4", "REAL8", "ASCII",
+];
+
+fn invalid(line: usize, reason: impl Into<String>) -> GDSIIErrorKind {
+    GDSIIErrorKind::InvalidAscii {
+        line,
+        reason: reason.into(),
+    }
+}
+
+// `"..."` with `\"`, `\\` and `\xNN` for anything but printable ASCII
+fn quote(out: &mut String, bytes: &[u8]) {
+    out.push('"');
+    for b in bytes {
+        match b {
+            b'"' => out.push_str("\\\""),
+            b'\\' => out.push_str("\\\\"),
+            0x20..=0x7E => out.push(*b as char),
+            _ => write!(out, "\\x{:02X}", b).unwrap(),
+        }
+    }
+    out.push('"');
+}
+
+fn hex(out: &mut String, bytes: &[u8]) {
+    for b in bytes {
+        write!(out, "{:02X}", b).unwrap();
+    }
+}
+
+// the decoded value, `raw` and the bytes when the data does not fit its type
+fn value(out: &mut String, dtype: u8, data: &[u8]) {
+    let fits = match dtype {
+        0x00 => data.is_empty(),
+        0x01 => data.len() == 2,
+        0x02 => data.len().is_multiple_of(2),
+        0x03 => data.len().is_multiple_of(4),
+        0x05 => data.len().is_multiple_of(8),
+        0x06 => true,
+        _ => false,
+    };
+    if !fits {
+        out.push_str(" raw ");
+        hex(out, data);
+        return;
+    }
+    match dtype {
+        0x01 => write!(out, " 0x{:04X}", BigEndian::read_u16(data)).unwrap(),
+        0x02 => data
+            .chunks(2)
+            .for_each(|c| write!(out, " {}", BigEndian::read_i16(c)).unwrap()),
+        0x03 => data
+            .chunks(4)
+            .for_each(|c| write!(out, " {}", BigEndian::read_i32(c)).unwrap()),
+        0x05 => {
+            for c in data.chunks(8) {
+                let real = Real8::from_bits(BigEndian::read_u64(c));
+                // the raw bits when the decimal would not read back the same
+                if Real8::from_f64(real.to_f64()) == real {
+                    write!(out, " {:?}", real.to_f64()).unwrap();
+                } else {
+                    write!(out, " 0x{:016X}", real.to_bits()).unwrap();
+                }
+            }
+        }
+        0x06 => {
+            // the NUL padding an odd length string gets is implied
+            let text = data.strip_suffix(&[0]).unwrap_or(data);
+            out.push(' ');
+            quote(out, text);
+        }
+        _ => {}
+    }
+}
+
+/// Text dump of a gds2 stream, one line per record.
+///
+/// Each line holds the byte offset in hex, the record name, the data type
+/// and the decoded values, indented by nesting:
+///
+/// ```text
+/// 00000000: HEADER INT2 600
+/// 00000066: BGNSTR INT2 0 0 0 0 0 0 0 0 0 0 0 0
+/// 0000007E:   STRNAME ASCII "TOP"
+/// 00000084:   BOUNDARY NODATA
+/// 00000088:     LAYER INT2 1
+/// ```
+///
+/// Strings are quoted with `\"`, `\\` and `\xNN` escapes, reals that an
+/// `f64` cannot carry exactly are given as their raw bits (`0x...`). Record
+/// types outside the Stream Format table are written as `0xNN`, data that
+/// does not fit its data type as `raw` and hex bytes. Bytes after ENDLIB
+/// end the dump as a `TAIL` line. [`ascii2gds`] reads the text back to the
+/// same bytes.
+pub fn gds2ascii(data: &[u8]) -> Result<String, GDSIIErrorKind> {
+    let mut out = String::new();
+    let mut offset = 0;
+    let mut depth: usize = 0;
+    let mut structure = None;
+    loop {
+        let Some(head) = data.get(offset..offset + 4) else {
+            return Err(GDSIIErrorKind::UnexpectedEof { offset, structure });
+        };
+        let size = BigEndian::read_u16(head) as usize;
+        if size < 4 || !size.is_multiple_of(2) {
+            return Err(GDSIIErrorKind::InvalidRecordSize {
+                offset,
+                size,
+                structure,
+            });
+        }
+        let Some(record) = data.get(offset..offset + size) else {
+            return Err(GDSIIErrorKind::UnexpectedEof { offset, structure });
+        };
+        let (rtype, dtype) = (record[2], record[3]);
+        // ENDEL and ENDSTR close the nesting they are written in
+        if matches!(rtype, 0x07 | 0x11) {
+            depth = depth.saturating_sub(1);
+        }
+        write!(out, "{:08X}: {:width$}", offset, "", width = 2 * depth).unwrap();
+        match record_name(rtype) {
+            "UNKNOWN" => write!(out, "0x{:02X}", rtype).unwrap(),
+            name => out.push_str(name),
+        }
+        match DATA_TYPES.get(dtype as usize) {
+            Some(name) => write!(out, " {}", name).unwrap(),
+            None => write!(out, " 0x{:02X}", dtype).unwrap(),
+        }
+        value(&mut out, dtype, &record[4..]);
+        out.push('\n');
+        offset += size;
+        match rtype {
+            0x05 => depth = 1,
+            0x06 => structure = Some(String::from_utf8_lossy(&record[4..]).into()),
+            0x07 => structure = None,
+            0x08..=0x0C | 0x14 | 0x15 | 0x2D => depth += 1,
+            0x04 => break,
+            _ => {}
+        }
+    }
+    let tail = &data[offset..];
+    if tail.iter().all(|b| *b == 0) && !tail.is_empty() {
+        writeln!(out, "{:08X}: TAIL zeros {}", offset, tail.len()).unwrap();
+    } else if !tail.is_empty() {
+        write!(out, "{:08X}: TAIL raw ", offset).unwrap();
+        hex(&mut out, tail);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+fn code(token: &str) -> Option<u8> {
+    u8::from_str_radix(token.strip_prefix("0x")?, 16).ok()
+}
+
+fn unhex(text: &str, line: usize) -> Result<Vec<u8>, GDSIIErrorKind> {
+    let digits: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+    if !digits.len().is_multiple_of(2) {
+        return Err(invalid(line, "odd number of hex digits"));
+    }
+    digits
+        .chunks(2)
+        .map(|pair| {
+            let pair: String = pair.iter().collect();
+            u8::from_str_radix(&pair, 16)
+                .map_err(|_| invalid(line, format!("`{}` is not a hex byte", pair)))
+        })
+        .collect()
+}
+
+fn unquote(text: &str, line: usize) -> Result<Vec<u8>, GDSIIErrorKind> {
+    let inner = text
+        .strip_prefix('"')
+        .and_then(|t| t.strip_suffix('"'))
+        .ok_or_else(|| invalid(line, "string is not in double quotes"))?;
+    let mut bytes = vec![];
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0; 4];
+            bytes.extend(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+        match chars.next() {
+            Some('"') => bytes.push(b'"'),
+            Some('\\') => bytes.push(b'\\'),
+            Some('x') => {
+                let digits: String = chars.by_ref().take(2).collect();
+                bytes.extend(unhex(&digits, line)?);
+            }
+            _ => return Err(invalid(line, "unknown escape in string")),
+        }
+    }
+    Ok(bytes)
+}
+
+fn numbers<T: std::str::FromStr>(text: &str, line: usize) -> Result<Vec<T>, GDSIIErrorKind> {
+    text.split_whitespace()
+        .map(|t| {
+            t.parse()
+                .map_err(|_| invalid(line, format!("`{}` is not a number of the data type", t)))
+        })
+        .collect()
+}
+
+/// Stream read back from a [`gds2ascii`] dump.
+///
+/// Offsets and indentation are ignored, so lines may be added, removed or
+/// edited freely; record sizes are recomputed. Empty lines and lines
+/// starting with `#` are skipped.
+pub fn ascii2gds(text: &str) -> Result<Vec<u8>, GDSIIErrorKind> {
+    let mut data = vec![];
+    for (i, line) in text.lines().enumerate() {
+        let line_no = i + 1;
+        let mut rest = line.trim();
+        if rest.is_empty() || rest.starts_with('#') {
+            continue;
+        }
+        // the offset, if any
+        if let Some((first, after)) = rest.split_once(char::is_whitespace) {
+            if first.ends_with(':') {
+                rest = after.trim_start();
+            }
+        }
+        let (name, rest) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        let rest = rest.trim_start();
+        if name == "TAIL" {
+            match rest.split_once(char::is_whitespace) {
+                Some(("zeros", n)) => {
+                    let n: usize = n
+                        .trim()
+                        .parse()
+                        .map_err(|_| invalid(line_no, "TAIL zeros needs a byte count"))?;
+                    data.resize(data.len() + n, 0);
+                }
+                Some(("raw", bytes)) => data.extend(unhex(bytes, line_no)?),
+                _ => return Err(invalid(line_no, "TAIL needs `zeros` or `raw`")),
+            }
+            continue;
+        }
+        let rtype = match (0..=u8::MAX).find(|t| record_name(*t) == name) {
+            Some(t) if name != "UNKNOWN" => t,
+            _ => {
+                code(name).ok_or_else(|| invalid(line_no, format!("unknown record `{}`", name)))?
+            }
+        };
+        let (dtype_name, values) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        let dtype = match DATA_TYPES.iter().position(|d| *d == dtype_name) {
+            Some(t) => t as u8,
+            None => code(dtype_name)
+                .ok_or_else(|| invalid(line_no, format!("unknown data type `{}`", dtype_name)))?,
+        };
+        let values = values.trim();
+        let payload = match values.split_once(char::is_whitespace) {
+            Some(("raw", bytes)) => unhex(bytes, line_no)?,
+            _ if values == "raw" => vec![],
+            _ => match dtype {
+                0x00 if values.is_empty() => vec![],
+                0x01 => {
+                    let bits = values
+                        .strip_prefix("0x")
+                        .and_then(|v| u16::from_str_radix(v, 16).ok())
+                        .ok_or_else(|| invalid(line_no, "bit array needs a 0xNNNN value"))?;
+                    bits.to_be_bytes().to_vec()
+                }
+                0x02 => numbers::<i16>(values, line_no)?
+                    .iter()
+                    .flat_map(|v| v.to_be_bytes())
+                    .collect(),
+                0x03 => numbers::<i32>(values, line_no)?
+                    .iter()
+                    .flat_map(|v| v.to_be_bytes())
+                    .collect(),
+                0x05 => {
+                    let mut bytes = vec![];
+                    for token in values.split_whitespace() {
+                        let real = match token.strip_prefix("0x") {
+                            Some(bits) => u64::from_str_radix(bits, 16).map(Real8::from_bits).ok(),
+                            None => token.parse().map(Real8::from_f64).ok(),
+                        };
+                        let real = real.ok_or_else(|| {
+                            invalid(line_no, format!("`{}` is not a real", token))
+                        })?;
+                        bytes.extend(real.to_be_bytes());
+                    }
+                    bytes
+                }
+                0x06 => {
+                    let mut bytes = unquote(values, line_no)?;
+                    if !bytes.len().is_multiple_of(2) {
+                        bytes.push(0);
+                    }
+                    bytes
+                }
+                _ => return Err(invalid(line_no, "data of this type must be given as `raw`")),
+            },
+        };
+        if payload.len() > u16::MAX as usize - 4 {
+            return Err(invalid(line_no, "record is longer than 65535 bytes"));
+        }
+        data.extend(((payload.len() + 4) as u16).to_be_bytes());
+        data.extend([rtype, dtype]);
+        data.extend(payload);
+    }
+    Ok(data)
+}
+
+impl Library {
+    /// [`gds2ascii`] dump of the library as [`Library::to_bytes`] writes it
+    pub fn to_ascii(&self) -> Result<String, GDSIIErrorKind> {
+        gds2ascii(&self.to_bytes()?)
+    }
+}
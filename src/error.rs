@@ -1,5 +1,41 @@
 use thiserror::Error;
 
+/// Data length a record type requires
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpectedLength {
+    Exact(usize),
+    MultipleOf(usize),
+    AtMost(usize),
+}
+
+impl std::fmt::Display for ExpectedLength {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExpectedLength::Exact(n) => write!(f, "{} bytes", n),
+            ExpectedLength::MultipleOf(n) => write!(f, "a multiple of {} bytes", n),
+            ExpectedLength::AtMost(n) => write!(f, "at most {} bytes", n),
+        }
+    }
+}
+
+impl ExpectedLength {
+    pub fn accepts(&self, len: usize) -> bool {
+        match self {
+            ExpectedLength::Exact(n) => len == *n,
+            ExpectedLength::MultipleOf(n) => len.is_multiple_of(*n),
+            ExpectedLength::AtMost(n) => len <= *n,
+        }
+    }
+}
+
+// " in structure `name`" when the error happened inside BGNSTR/ENDSTR
+fn context(structure: &Option<String>) -> String {
+    match structure {
+        Some(name) => format!(" in structure `{}`", name),
+        None => String::new(),
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum GDSIIErrorKind {
     #[error("Cannot parse the given *.gds")]
@@ -9,4 +45,141 @@ pub enum GDSIIErrorKind {
         #[from]
         source: std::io::Error,
     },
+    #[error(
+        "{record} record at offset {offset:#x}{}: expected {expected} of data, found {actual}",
+        context(structure)
+    )]
+    InvalidLength {
+        offset: usize,
+        record: &'static str,
+        expected: ExpectedLength,
+        actual: usize,
+        structure: Option<String>,
+    },
+    #[error(
+        "record size {size} at offset {offset:#x}{} is odd or smaller than the record header",
+        context(structure)
+    )]
+    InvalidRecordSize {
+        offset: usize,
+        size: usize,
+        structure: Option<String>,
+    },
+    #[error(
+        "unknown record type {rtype:#04x} (data type {dtype:#04x}) at offset {offset:#x}{}",
+        context(structure)
+    )]
+    UnknownRecord {
+        offset: usize,
+        rtype: u8,
+        dtype: u8,
+        structure: Option<String>,
+    },
+    #[error(
+        "{record} record at offset {offset:#x}{} is not valid UTF-8 text",
+        context(structure)
+    )]
+    InvalidString {
+        offset: usize,
+        record: &'static str,
+        structure: Option<String>,
+    },
+    #[error(
+        "unexpected {record} record at offset {offset:#x}{}",
+        context(structure)
+    )]
+    UnexpectedRecord {
+        offset: usize,
+        record: &'static str,
+        structure: Option<String>,
+    },
+    #[error(
+        "{element} at offset {offset:#x}{} has no {missing} record",
+        context(structure)
+    )]
+    MissingRecord {
+        offset: usize,
+        element: &'static str,
+        missing: &'static str,
+        structure: Option<String>,
+    },
+    #[error(
+        "{element} at offset {offset:#x}{} has {actual} XY points, expected {expected}",
+        context(structure)
+    )]
+    InvalidPointCount {
+        offset: usize,
+        element: &'static str,
+        expected: usize,
+        actual: usize,
+        structure: Option<String>,
+    },
+    #[error(
+        "stream ends at offset {offset:#x}{} before ENDLIB",
+        context(structure)
+    )]
+    UnexpectedEof {
+        offset: usize,
+        structure: Option<String>,
+    },
+    #[error(
+        "structure `{name}` is not defined{}",
+        referenced_from.as_ref().map_or(String::new(), |s| format!(", referenced from `{}`", s))
+    )]
+    UndefinedStructure {
+        name: String,
+        /// structure holding the SREF/AREF, `None` for a requested top cell
+        referenced_from: Option<String>,
+    },
+    #[error("structures reference each other in a cycle: {}", cycle.join(" -> "))]
+    ReferenceCycle {
+        /// structure names along the cycle, the first one repeated at the end
+        cycle: Vec<String>,
+    },
+    #[error("invalid OASIS stream at offset {offset:#x}: {reason}")]
+    InvalidOasis {
+        /// start of the record, or of the CBLOCK holding it
+        offset: usize,
+        reason: String,
+    },
+    #[error("text dump line {line}: {reason}")]
+    InvalidAscii {
+        /// 1-based
+        line: usize,
+        reason: String,
+    },
+    #[cfg(feature = "serde")]
+    #[error("invalid JSON library: {source}")]
+    InvalidJson {
+        #[from]
+        source: serde_json::Error,
+    },
+    #[error("layer map line {line}: {reason}")]
+    InvalidLayerMap {
+        /// 1-based
+        line: usize,
+        reason: String,
+    },
+    #[error("layer {layer}/{datatype} in structure `{structure}` has no mapping")]
+    UnmappedLayer {
+        layer: i16,
+        datatype: i16,
+        structure: String,
+    },
+    #[error("cannot write {record} {state}")]
+    InvalidNesting {
+        record: &'static str,
+        state: &'static str,
+    },
+    #[error("{record} record holds {length} bytes of data, a record holds at most 65530")]
+    RecordTooLong { record: &'static str, length: usize },
+    #[error("saved stream differs from the input at the {record} record at offset {offset:#x}")]
+    RoundtripMismatch {
+        offset: usize,
+        record: &'static str,
+        /// the record as read
+        original: Vec<u8>,
+        /// the record as written back
+        saved: Vec<u8>,
+    },
 }
@@ -0,0 +1,61 @@
+use super::BBox;
+use crate::model::{Element, Path, Point};
+
+/// What counts towards a bounding box besides the drawn shapes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BBoxOptions {
+    /// include TEXT origins, texts have no extent of their own
+    pub include_text: bool,
+}
+
+// smallest box holding every (x, y), widened to whole database units
+fn bbox_f64(points: impl IntoIterator<Item = (f64, f64)>) -> Option<BBox> {
+    let mut points = points.into_iter();
+    let first = points.next()?;
+    let (mut min, mut max) = (first, first);
+    for (x, y) in points {
+        min = (min.0.min(x), min.1.min(y));
+        max = (max.0.max(x), max.1.max(y));
+    }
+    Some(BBox {
+        min: (min.0.floor() as i32, min.1.floor() as i32),
+        max: (max.0.ceil() as i32, max.1.ceil() as i32),
+    })
+}
+
+impl Path {
+    /// Extent of the drawn outline, see [`Path::outline`], or of the centre
+    /// line for a path without width.
+    pub fn bbox(&self) -> Option<BBox> {
+        let half = self.width.unwrap_or(0).unsigned_abs() as f64 / 2.0;
+        let to_f64 = |p: &Point| (p.0 as f64, p.1 as f64);
+        let mut outline = self.outline();
+        if outline.is_empty() {
+            outline.extend(self.points.iter().map(to_f64));
+        }
+        // the outline only approximates round ends, they reach half the
+        // width around the end points
+        if self.pathtype == Some(1) {
+            for p in self.points.first().into_iter().chain(self.points.last()) {
+                let (x, y) = to_f64(p);
+                outline.extend([(x - half, y - half), (x + half, y + half)]);
+            }
+        }
+        bbox_f64(outline)
+    }
+}
+
+impl Element {
+    /// Extent of the element itself; SREF and AREF need the structures they
+    /// place and give `None`, see [`crate::Library::structure_bbox`].
+    pub fn bbox(&self, options: &BBoxOptions) -> Option<BBox> {
+        match self {
+            Element::Boundary(e) => BBox::from_points(&e.points),
+            Element::Path(e) => e.bbox(),
+            Element::Node(e) => BBox::from_points(&e.points),
+            Element::Box(e) => BBox::from_points(&e.points),
+            Element::Text(e) if options.include_text => Some(BBox::new(e.origin, e.origin)),
+            Element::Text(_) | Element::SRef(_) | Element::ARef(_) => None,
+        }
+    }
+}
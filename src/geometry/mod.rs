@@ -0,0 +1,108 @@
+mod extent;
+mod outline;
+mod rtree;
+mod transform;
+
+pub use extent::BBoxOptions;
+pub use rtree::RTree;
+pub use transform::Transform;
+
+use crate::model::Point;
+
+/// Axis aligned box in database units, `min` and `max` are both inside.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BBox {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl BBox {
+    /// box spanning two opposite corners given in any order
+    pub fn new(a: Point, b: Point) -> Self {
+        BBox {
+            min: (a.0.min(b.0), a.1.min(b.1)),
+            max: (a.0.max(b.0), a.1.max(b.1)),
+        }
+    }
+
+    /// smallest box holding every point, `None` for no points
+    pub fn from_points(points: &[Point]) -> Option<Self> {
+        let (first, rest) = points.split_first()?;
+        let mut bbox = BBox::new(*first, *first);
+        for p in rest {
+            bbox = bbox.include(*p);
+        }
+        Some(bbox)
+    }
+
+    /// the box grown to hold `p`
+    pub fn include(self, p: Point) -> Self {
+        BBox {
+            min: (self.min.0.min(p.0), self.min.1.min(p.1)),
+            max: (self.max.0.max(p.0), self.max.1.max(p.1)),
+        }
+    }
+
+    pub fn union(self, other: BBox) -> Self {
+        self.include(other.min).include(other.max)
+    }
+
+    pub fn width(&self) -> i64 {
+        self.max.0 as i64 - self.min.0 as i64
+    }
+
+    pub fn height(&self) -> i64 {
+        self.max.1 as i64 - self.min.1 as i64
+    }
+
+    /// the four corners, counterclockwise from `min`
+    pub fn corners(&self) -> [Point; 4] {
+        [
+            self.min,
+            (self.max.0, self.min.1),
+            self.max,
+            (self.min.0, self.max.1),
+        ]
+    }
+
+    pub fn contains(&self, p: Point) -> bool {
+        (self.min.0..=self.max.0).contains(&p.0) && (self.min.1..=self.max.1).contains(&p.1)
+    }
+
+    /// whether the boxes share at least one point, edges included
+    pub fn intersects(&self, other: &BBox) -> bool {
+        self.min.0 <= other.max.0
+            && other.min.0 <= self.max.0
+            && self.min.1 <= other.max.1
+            && other.min.1 <= self.max.1
+    }
+}
+
+/// Whether `p` lies inside or on the edge of the closed polygon `points`,
+/// by the even-odd rule.
+pub fn polygon_contains(points: &[Point], p: Point) -> bool {
+    let (px, py) = (p.0 as i64, p.1 as i64);
+    let mut inside = false;
+    for (i, a) in points.iter().enumerate() {
+        let b = points[(i + 1) % points.len()];
+        let (ax, ay, bx, by) = (a.0 as i64, a.1 as i64, b.0 as i64, b.1 as i64);
+        let cross = (bx - ax) * (py - ay) - (by - ay) * (px - ax);
+        if cross == 0
+            && px >= ax.min(bx)
+            && px <= ax.max(bx)
+            && py >= ay.min(by)
+            && py <= ay.max(by)
+        {
+            return true;
+        }
+        if (ay > py) != (by > py) {
+            // x where the edge crosses the horizontal through p, compared
+            // without dividing
+            let right = if by > ay { cross > 0 } else { cross < 0 };
+            if right {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
@@ -0,0 +1,131 @@
+use crate::model::Path;
+
+// segments of a half circle in a round path end
+const ROUND_STEPS: usize = 16;
+
+// joins sharper than this many half widths are bevelled
+const MITER_LIMIT: f64 = 4.0;
+
+type Vector = (f64, f64);
+
+fn unit(a: Vector, b: Vector) -> Vector {
+    let length = (b.0 - a.0).hypot(b.1 - a.1);
+    ((b.0 - a.0) / length, (b.1 - a.1) / length)
+}
+
+// `p` moved by `v` scaled by `s`
+fn offset(p: Vector, v: Vector, s: f64) -> Vector {
+    (p.0 + v.0 * s, p.1 + v.1 * s)
+}
+
+// points of the half circle of radius `r` around `c` from `c + r * normal`
+// through `c + r * dir`, both ends left out
+fn half_circle(out: &mut Vec<Vector>, c: Vector, dir: Vector, normal: Vector, r: f64) {
+    for i in 1..ROUND_STEPS {
+        let (sin, cos) = (std::f64::consts::PI * i as f64 / ROUND_STEPS as f64).sin_cos();
+        out.push((
+            c.0 + r * (normal.0 * cos + dir.0 * sin),
+            c.1 + r * (normal.1 * cos + dir.1 * sin),
+        ));
+    }
+}
+
+impl Path {
+    /// Polygon covering the drawn path: the centre line widened by WIDTH,
+    /// lengthened or rounded at the ends as PATHTYPE asks, with mitred
+    /// joins bevelled when sharper than four half widths.
+    ///
+    /// The first vertex is not repeated at the end. A path of zero width
+    /// or without length gives no vertices, except for the square or
+    /// circle of pathtypes 1 and 2 around a single point.
+    pub fn outline(&self) -> Vec<(f64, f64)> {
+        let half = self.width.unwrap_or(0).unsigned_abs() as f64 / 2.0;
+        let pathtype = self.pathtype.unwrap_or(0);
+        let mut points: Vec<Vector> = self
+            .points
+            .iter()
+            .map(|p| (p.0 as f64, p.1 as f64))
+            .collect();
+        points.dedup();
+        if half == 0.0 || points.is_empty() {
+            return vec![];
+        }
+        if points.len() == 1 {
+            let (x, y) = points[0];
+            let mut out = vec![];
+            match pathtype {
+                1 => {
+                    out.push((x + half, y));
+                    half_circle(&mut out, (x, y), (0.0, 1.0), (1.0, 0.0), half);
+                    out.push((x - half, y));
+                    half_circle(&mut out, (x, y), (0.0, -1.0), (-1.0, 0.0), half);
+                }
+                2 => out.extend([
+                    (x - half, y - half),
+                    (x + half, y - half),
+                    (x + half, y + half),
+                    (x - half, y + half),
+                ]),
+                _ => {}
+            }
+            return out;
+        }
+
+        let n = points.len();
+        let (begin, end) = match pathtype {
+            2 => (half, half),
+            4 => (
+                self.bgnextn.unwrap_or(0) as f64,
+                self.endextn.unwrap_or(0) as f64,
+            ),
+            _ => (0.0, 0.0),
+        };
+        let first = unit(points[0], points[1]);
+        let last = unit(points[n - 2], points[n - 1]);
+        points[0] = offset(points[0], first, -begin);
+        points[n - 1] = offset(points[n - 1], last, end);
+
+        // left side forward, then the right side backward
+        let mut left = vec![];
+        let mut right = vec![];
+        for i in 0..n {
+            let before = (i > 0).then(|| unit(points[i - 1], points[i]));
+            let after = (i + 1 < n).then(|| unit(points[i], points[i + 1]));
+            let normal = |d: Vector| (-d.1, d.0);
+            match (before, after) {
+                (Some(d), None) | (None, Some(d)) => {
+                    left.push(offset(points[i], normal(d), half));
+                    right.push(offset(points[i], normal(d), -half));
+                }
+                (Some(a), Some(b)) => {
+                    let (na, nb) = (normal(a), normal(b));
+                    let sum = (na.0 + nb.0, na.1 + nb.1);
+                    let length = sum.0.hypot(sum.1);
+                    // the miter reaches half / cos of half the turn
+                    let cos = length / 2.0;
+                    if cos * MITER_LIMIT < 1.0 {
+                        left.extend([offset(points[i], na, half), offset(points[i], nb, half)]);
+                        right.extend([offset(points[i], na, -half), offset(points[i], nb, -half)]);
+                    } else {
+                        let miter = (sum.0 / length, sum.1 / length);
+                        left.push(offset(points[i], miter, half / cos));
+                        right.push(offset(points[i], miter, -half / cos));
+                    }
+                }
+                (None, None) => unreachable!("a path of two or more points"),
+            }
+        }
+
+        let mut out = left;
+        if pathtype == 1 {
+            let normal = (-last.1, last.0);
+            half_circle(&mut out, points[n - 1], last, normal, half);
+        }
+        out.extend(right.into_iter().rev());
+        if pathtype == 1 {
+            let back = (-first.0, -first.1);
+            half_circle(&mut out, points[0], back, (first.1, -first.0), half);
+        }
+        out
+    }
+}
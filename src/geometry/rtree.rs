@@ -0,0 +1,345 @@
+use super::BBox;
+use crate::model::Point;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+// most entries a node holds before it splits
+const MAX_ENTRIES: usize = 16;
+
+#[derive(Debug, Clone)]
+enum Node<T> {
+    Leaf(Vec<(BBox, T)>),
+    Inner(Vec<(BBox, Node<T>)>),
+}
+
+impl<T> Node<T> {
+    fn bbox(&self) -> Option<BBox> {
+        match self {
+            Node::Leaf(entries) => entries.iter().map(|e| e.0).reduce(BBox::union),
+            Node::Inner(children) => children.iter().map(|c| c.0).reduce(BBox::union),
+        }
+    }
+}
+
+fn area(b: &BBox) -> f64 {
+    b.width() as f64 * b.height() as f64
+}
+
+fn center(b: &BBox) -> (i64, i64) {
+    (
+        b.min.0 as i64 + b.max.0 as i64,
+        b.min.1 as i64 + b.max.1 as i64,
+    )
+}
+
+// squared distance from `p` to the nearest point of `b`, 0 inside
+fn distance2(b: &BBox, p: Point) -> f64 {
+    let dx = (b.min.0 as f64 - p.0 as f64)
+        .max(p.0 as f64 - b.max.0 as f64)
+        .max(0.0);
+    let dy = (b.min.1 as f64 - p.1 as f64)
+        .max(p.1 as f64 - b.max.1 as f64)
+        .max(0.0);
+    dx * dx + dy * dy
+}
+
+// halve `entries` along the axis their centres spread most, returning the
+// upper half
+fn split<E>(entries: &mut Vec<(BBox, E)>) -> Vec<(BBox, E)> {
+    let spread = |axis: fn((i64, i64)) -> i64| {
+        let values = entries.iter().map(|e| axis(center(&e.0)));
+        values.clone().max().unwrap_or(0) - values.min().unwrap_or(0)
+    };
+    if spread(|c| c.0) >= spread(|c| c.1) {
+        entries.sort_by_key(|e| center(&e.0).0);
+    } else {
+        entries.sort_by_key(|e| center(&e.0).1);
+    }
+    entries.split_off(entries.len() / 2)
+}
+
+// sort-tile-recursive grouping of `entries` into nodes of at most
+// MAX_ENTRIES
+fn tile<E>(mut entries: Vec<(BBox, E)>) -> Vec<Vec<(BBox, E)>> {
+    let nodes = entries.len().div_ceil(MAX_ENTRIES);
+    let slices = (nodes as f64).sqrt().ceil() as usize;
+    let per_slice = slices * MAX_ENTRIES;
+    entries.sort_by_key(|e| center(&e.0).0);
+    let mut groups = vec![];
+    while !entries.is_empty() {
+        let rest = entries.split_off(per_slice.min(entries.len()));
+        let mut slice = std::mem::replace(&mut entries, rest);
+        slice.sort_by_key(|e| center(&e.0).1);
+        while !slice.is_empty() {
+            let rest = slice.split_off(MAX_ENTRIES.min(slice.len()));
+            groups.push(std::mem::replace(&mut slice, rest));
+        }
+    }
+    groups
+}
+
+/// R-tree of items keyed by their bounding boxes.
+///
+/// [`RTree::bulk_load`] packs a whole set at once; [`RTree::insert`] and
+/// [`RTree::remove`] keep the tree balanced under edits, though a tree
+/// built by many edits answers queries slower than a packed one.
+#[derive(Debug, Clone)]
+pub struct RTree<T> {
+    root: Node<T>,
+    len: usize,
+}
+
+impl<T> Default for RTree<T> {
+    fn default() -> Self {
+        RTree {
+            root: Node::Leaf(vec![]),
+            len: 0,
+        }
+    }
+}
+
+impl<T> RTree<T> {
+    pub fn new() -> Self {
+        RTree::default()
+    }
+
+    pub fn bulk_load(entries: Vec<(BBox, T)>) -> Self {
+        let len = entries.len();
+        let mut nodes: Vec<(BBox, Node<T>)> = tile(entries)
+            .into_iter()
+            .map(|leaf| {
+                let node = Node::Leaf(leaf);
+                (node.bbox().unwrap(), node)
+            })
+            .collect();
+        while nodes.len() > 1 {
+            nodes = tile(nodes)
+                .into_iter()
+                .map(|children| {
+                    let node = Node::Inner(children);
+                    (node.bbox().unwrap(), node)
+                })
+                .collect();
+        }
+        RTree {
+            root: nodes.pop().map_or(Node::Leaf(vec![]), |n| n.1),
+            len,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// box of every item together
+    pub fn bbox(&self) -> Option<BBox> {
+        self.root.bbox()
+    }
+
+    pub fn insert(&mut self, bbox: BBox, item: T) {
+        self.len += 1;
+        if let Some(sibling) = Self::insert_into(&mut self.root, bbox, item) {
+            let old = std::mem::replace(&mut self.root, Node::Inner(vec![]));
+            let old = (old.bbox().unwrap(), old);
+            self.root = Node::Inner(vec![old, sibling]);
+        }
+    }
+
+    // insert below `node`, returning the new sibling when `node` split
+    fn insert_into(node: &mut Node<T>, bbox: BBox, item: T) -> Option<(BBox, Node<T>)> {
+        match node {
+            Node::Leaf(entries) => {
+                entries.push((bbox, item));
+                if entries.len() <= MAX_ENTRIES {
+                    return None;
+                }
+                let sibling = Node::Leaf(split(entries));
+                Some((sibling.bbox().unwrap(), sibling))
+            }
+            Node::Inner(children) => {
+                // the child growing least, then the smallest
+                let growth = |b: &BBox| {
+                    let grown = area(&b.union(bbox));
+                    (grown - area(b), grown)
+                };
+                let best = (0..children.len())
+                    .min_by(|a, b| {
+                        growth(&children[*a].0)
+                            .partial_cmp(&growth(&children[*b].0))
+                            .unwrap_or(Ordering::Equal)
+                    })
+                    .unwrap();
+                let child = &mut children[best];
+                child.0 = child.0.union(bbox);
+                let sibling = Self::insert_into(&mut child.1, bbox, item);
+                if let Some(sibling) = sibling {
+                    child.0 = child.1.bbox().unwrap();
+                    children.push(sibling);
+                }
+                if children.len() <= MAX_ENTRIES {
+                    return None;
+                }
+                let sibling = Node::Inner(split(children));
+                Some((sibling.bbox().unwrap(), sibling))
+            }
+        }
+    }
+
+    /// remove one item equal to `item` stored under `bbox`
+    pub fn remove(&mut self, bbox: &BBox, item: &T) -> Option<T>
+    where
+        T: PartialEq,
+    {
+        let removed = Self::remove_from(&mut self.root, bbox, item)?;
+        self.len -= 1;
+        // a root with a single child is one level too many
+        while let Node::Inner(children) = &mut self.root {
+            match children.len() {
+                0 => self.root = Node::Leaf(vec![]),
+                1 => self.root = children.pop().unwrap().1,
+                _ => break,
+            }
+        }
+        Some(removed)
+    }
+
+    fn remove_from(node: &mut Node<T>, bbox: &BBox, item: &T) -> Option<T>
+    where
+        T: PartialEq,
+    {
+        match node {
+            Node::Leaf(entries) => {
+                let i = entries.iter().position(|e| e.0 == *bbox && e.1 == *item)?;
+                Some(entries.swap_remove(i).1)
+            }
+            Node::Inner(children) => {
+                for i in 0..children.len() {
+                    if !children[i].0.intersects(bbox) {
+                        continue;
+                    }
+                    if let Some(removed) = Self::remove_from(&mut children[i].1, bbox, item) {
+                        match children[i].1.bbox() {
+                            Some(b) => children[i].0 = b,
+                            None => {
+                                children.swap_remove(i);
+                            }
+                        }
+                        return Some(removed);
+                    }
+                }
+                None
+            }
+        }
+    }
+
+    /// items whose boxes share at least one point with `window`
+    pub fn search(&self, window: &BBox) -> Vec<&T> {
+        let mut found = vec![];
+        let mut stack = vec![&self.root];
+        while let Some(node) = stack.pop() {
+            match node {
+                Node::Leaf(entries) => found.extend(
+                    entries
+                        .iter()
+                        .filter(|e| e.0.intersects(window))
+                        .map(|e| &e.1),
+                ),
+                Node::Inner(children) => stack.extend(
+                    children
+                        .iter()
+                        .filter(|c| c.0.intersects(window))
+                        .map(|c| &c.1),
+                ),
+            }
+        }
+        found
+    }
+
+    /// items whose boxes hold `p`, edges included
+    pub fn at_point(&self, p: Point) -> Vec<&T> {
+        self.search(&BBox::new(p, p))
+    }
+
+    /// Item whose box is closest to `p`, 0 when `p` is inside it, with that
+    /// distance.
+    pub fn nearest(&self, p: Point) -> Option<(&T, f64)> {
+        // nodes and items by distance, nearest first
+        struct Candidate<'a, T> {
+            distance: f64,
+            node: Option<&'a Node<T>>,
+            item: Option<&'a T>,
+        }
+        impl<T> PartialEq for Candidate<'_, T> {
+            fn eq(&self, other: &Self) -> bool {
+                self.distance == other.distance
+            }
+        }
+        impl<T> Eq for Candidate<'_, T> {}
+        impl<T> PartialOrd for Candidate<'_, T> {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+        impl<T> Ord for Candidate<'_, T> {
+            fn cmp(&self, other: &Self) -> Ordering {
+                other.distance.total_cmp(&self.distance)
+            }
+        }
+
+        let mut heap = BinaryHeap::new();
+        heap.push(Candidate {
+            distance: 0.0,
+            node: Some(&self.root),
+            item: None,
+        });
+        while let Some(candidate) = heap.pop() {
+            if let Some(item) = candidate.item {
+                return Some((item, candidate.distance.sqrt()));
+            }
+            match candidate.node? {
+                Node::Leaf(entries) => heap.extend(entries.iter().map(|e| Candidate {
+                    distance: distance2(&e.0, p),
+                    node: None,
+                    item: Some(&e.1),
+                })),
+                Node::Inner(children) => heap.extend(children.iter().map(|c| Candidate {
+                    distance: distance2(&c.0, p),
+                    node: Some(&c.1),
+                    item: None,
+                })),
+            }
+        }
+        None
+    }
+
+    /// every item with its box, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (&BBox, &T)> {
+        let mut entries = vec![];
+        let mut stack = vec![&self.root];
+        while let Some(node) = stack.pop() {
+            match node {
+                Node::Leaf(leaf) => entries.extend(leaf.iter().map(|e| (&e.0, &e.1))),
+                Node::Inner(children) => stack.extend(children.iter().map(|c| &c.1)),
+            }
+        }
+        entries.into_iter()
+    }
+
+    /// number of levels, 1 for a single leaf
+    pub fn height(&self) -> usize {
+        let mut height = 1;
+        let mut node = &self.root;
+        while let Node::Inner(children) = node {
+            match children.first() {
+                Some(c) => node = &c.1,
+                None => break,
+            }
+            height += 1;
+        }
+        height
+    }
+}
@@ -0,0 +1,287 @@
+use super::BBox;
+use crate::model::{ARef, Element, Point, SRef, Strans, Text};
+
+/// Placement of a structure or text in its parent's coordinates.
+///
+/// A point is mirrored about the x axis when `reflect_x` is set, scaled by
+/// `mag`, rotated counterclockwise by `angle` degrees and finally moved by
+/// `offset`, the order Stream Format defines for STRANS, MAG, ANGLE and XY.
+/// Rotations by multiples of 90 degrees are applied without trigonometry,
+/// so they stay exact on integer coordinates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    pub reflect_x: bool,
+    /// counterclockwise rotation in degrees, kept in `[0, 360)`
+    pub angle: f64,
+    pub mag: f64,
+    pub offset: (f64, f64),
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform::identity()
+    }
+}
+
+// (x, y) rotated by `turns` quarter turns counterclockwise
+fn quarter_turn<T: std::ops::Neg<Output = T>>(turns: u8, x: T, y: T) -> (T, T) {
+    match turns {
+        0 => (x, y),
+        1 => (-y, x),
+        2 => (-x, -y),
+        _ => (y, -x),
+    }
+}
+
+// nearest database unit, saturating at the i32 range
+fn round(v: f64) -> i32 {
+    v.round() as i32
+}
+
+impl Transform {
+    pub fn identity() -> Self {
+        Transform {
+            reflect_x: false,
+            angle: 0.0,
+            mag: 1.0,
+            offset: (0.0, 0.0),
+        }
+    }
+
+    pub fn new(reflect_x: bool, angle: f64, mag: f64, offset: (f64, f64)) -> Self {
+        Transform {
+            reflect_x,
+            angle: angle.rem_euclid(360.0),
+            mag,
+            offset,
+        }
+    }
+
+    pub fn translate(dx: f64, dy: f64) -> Self {
+        Transform::new(false, 0.0, 1.0, (dx, dy))
+    }
+
+    pub fn rotate(angle: f64) -> Self {
+        Transform::new(false, angle, 1.0, (0.0, 0.0))
+    }
+
+    pub fn scale(mag: f64) -> Self {
+        Transform::new(false, 0.0, mag, (0.0, 0.0))
+    }
+
+    pub fn reflect_x() -> Self {
+        Transform::new(true, 0.0, 1.0, (0.0, 0.0))
+    }
+
+    /// placement given by an optional STRANS at `origin`
+    pub fn from_strans(strans: Option<&Strans>, origin: Point) -> Self {
+        let offset = (origin.0 as f64, origin.1 as f64);
+        match strans {
+            Some(s) => Transform::new(s.reflect_x, s.angle_degrees(), s.magnification(), offset),
+            None => Transform::new(false, 0.0, 1.0, offset),
+        }
+    }
+
+    /// number of quarter turns when the angle is a multiple of 90 degrees
+    pub fn quarter_turns(&self) -> Option<u8> {
+        let angle = self.angle.rem_euclid(360.0);
+        if angle % 90.0 == 0.0 {
+            Some((angle / 90.0) as u8 % 4)
+        } else {
+            None
+        }
+    }
+
+    /// whether axis aligned edges stay axis aligned
+    pub fn is_manhattan(&self) -> bool {
+        self.quarter_turns().is_some()
+    }
+
+    /// `self` followed by `next`
+    pub fn then(&self, next: &Transform) -> Transform {
+        // mirroring about x negates every rotation applied before it
+        let angle = if next.reflect_x {
+            next.angle - self.angle
+        } else {
+            next.angle + self.angle
+        };
+        Transform::new(
+            self.reflect_x != next.reflect_x,
+            angle,
+            self.mag * next.mag,
+            next.apply_f64(self.offset),
+        )
+    }
+
+    pub fn inverse(&self) -> Transform {
+        let angle = if self.reflect_x {
+            self.angle
+        } else {
+            -self.angle
+        };
+        let linear = Transform::new(self.reflect_x, angle, 1.0 / self.mag, (0.0, 0.0));
+        let (x, y) = linear.apply_f64(self.offset);
+        Transform {
+            offset: (-x, -y),
+            ..linear
+        }
+    }
+
+    pub fn apply_f64(&self, p: (f64, f64)) -> (f64, f64) {
+        let (x, y) = if self.reflect_x { (p.0, -p.1) } else { p };
+        let (x, y) = (x * self.mag, y * self.mag);
+        let (x, y) = match self.quarter_turns() {
+            Some(turns) => quarter_turn(turns, x, y),
+            None => {
+                let (sin, cos) = self.angle.to_radians().sin_cos();
+                (x * cos - y * sin, x * sin + y * cos)
+            }
+        };
+        (x + self.offset.0, y + self.offset.1)
+    }
+
+    /// the point rounded to the nearest database unit
+    pub fn apply(&self, p: Point) -> Point {
+        let exact = self.mag == 1.0 && self.offset.0.fract() == 0.0 && self.offset.1.fract() == 0.0;
+        match self.quarter_turns() {
+            Some(turns) if exact => {
+                let y = if self.reflect_x {
+                    -(p.1 as i64)
+                } else {
+                    p.1 as i64
+                };
+                let (x, y) = quarter_turn(turns, p.0 as i64, y);
+                let x = x + self.offset.0 as i64;
+                let y = y + self.offset.1 as i64;
+                (
+                    x.clamp(i32::MIN as i64, i32::MAX as i64) as i32,
+                    y.clamp(i32::MIN as i64, i32::MAX as i64) as i32,
+                )
+            }
+            _ => {
+                let (x, y) = self.apply_f64((p.0 as f64, p.1 as f64));
+                (round(x), round(y))
+            }
+        }
+    }
+
+    /// every vertex of a polygon or path
+    pub fn apply_points(&self, points: &[Point]) -> Vec<Point> {
+        points.iter().map(|p| self.apply(*p)).collect()
+    }
+
+    /// smallest box holding the transformed corners of `bbox`
+    pub fn apply_box(&self, bbox: &BBox) -> BBox {
+        let corners = bbox.corners().map(|p| self.apply(p));
+        let mut out = BBox::new(corners[0], corners[0]);
+        for p in &corners[1..] {
+            out = out.include(*p);
+        }
+        out
+    }
+}
+
+impl SRef {
+    pub fn transform(&self) -> Transform {
+        Transform::from_strans(self.strans.as_ref(), self.origin)
+    }
+}
+
+impl Text {
+    pub fn transform(&self) -> Transform {
+        Transform::from_strans(self.strans.as_ref(), self.origin)
+    }
+}
+
+impl ARef {
+    /// placement of the instance at `col`, `row`, counted from the origin
+    pub fn instance_transform(&self, col: i16, row: i16) -> Transform {
+        let cols = (self.cols as f64).max(1.0);
+        let rows = (self.rows as f64).max(1.0);
+        let (ox, oy) = (self.origin.0 as f64, self.origin.1 as f64);
+        let col_step = (
+            (self.col_point.0 as f64 - ox) / cols,
+            (self.col_point.1 as f64 - oy) / cols,
+        );
+        let row_step = (
+            (self.row_point.0 as f64 - ox) / rows,
+            (self.row_point.1 as f64 - oy) / rows,
+        );
+        let (c, r) = (col as f64, row as f64);
+        Transform {
+            offset: (
+                ox + c * col_step.0 + r * row_step.0,
+                oy + c * col_step.1 + r * row_step.1,
+            ),
+            ..Transform::from_strans(self.strans.as_ref(), self.origin)
+        }
+    }
+
+    /// placements of every instance, row by row
+    pub fn transforms(&self) -> impl Iterator<Item = Transform> + '_ {
+        (0..self.rows.max(0))
+            .flat_map(move |r| (0..self.cols.max(0)).map(move |c| self.instance_transform(c, r)))
+    }
+}
+
+// the STRANS of a placement `t`, keeping the flags and the MAG/ANGLE records
+// that `original` already had
+fn placement(t: &Transform, original: Option<&Strans>) -> (Option<Strans>, Point) {
+    let origin = (round(t.offset.0), round(t.offset.1));
+    let base = original.copied().unwrap_or_default();
+    let mag = (t.mag != 1.0 || base.mag.is_some()).then(|| t.mag.into());
+    let angle = (t.angle != 0.0 || base.angle.is_some()).then(|| t.angle.into());
+    if original.is_none() && !t.reflect_x && mag.is_none() && angle.is_none() {
+        return (None, origin);
+    }
+    let strans = Strans {
+        reflect_x: t.reflect_x,
+        mag,
+        angle,
+        ..base
+    };
+    (Some(strans), origin)
+}
+
+// positive widths scale with the placement, negative ones are absolute
+fn scale_width(width: Option<i32>, mag: f64) -> Option<i32> {
+    width.map(|w| if w > 0 { round(w as f64 * mag) } else { w })
+}
+
+impl Element {
+    /// The element as seen through the placement `t`.
+    ///
+    /// References and texts get their placement composed with `t`; the
+    /// absolute magnification and angle flags are treated as relative.
+    pub fn transformed(&self, t: &Transform) -> Element {
+        if *t == Transform::identity() {
+            return self.clone();
+        }
+        let mut element = self.clone();
+        match &mut element {
+            Element::Boundary(e) => e.points = t.apply_points(&e.points),
+            Element::Path(e) => {
+                e.points = t.apply_points(&e.points);
+                e.width = scale_width(e.width, t.mag);
+                e.bgnextn = e.bgnextn.map(|v| round(v as f64 * t.mag));
+                e.endextn = e.endextn.map(|v| round(v as f64 * t.mag));
+            }
+            Element::SRef(e) => {
+                (e.strans, e.origin) = placement(&e.transform().then(t), e.strans.as_ref())
+            }
+            Element::ARef(e) => {
+                let base = Transform::from_strans(e.strans.as_ref(), e.origin).then(t);
+                (e.strans, e.origin) = placement(&base, e.strans.as_ref());
+                e.col_point = t.apply(e.col_point);
+                e.row_point = t.apply(e.row_point);
+            }
+            Element::Text(e) => {
+                (e.strans, e.origin) = placement(&e.transform().then(t), e.strans.as_ref());
+                e.width = scale_width(e.width, t.mag);
+            }
+            Element::Node(e) => e.points = t.apply_points(&e.points),
+            Element::Box(e) => e.points = t.apply_points(&e.points),
+        }
+        element
+    }
+}
@@ -0,0 +1,140 @@
+use crate::error::GDSIIErrorKind;
+use crate::geometry::{BBox, BBoxOptions};
+use crate::model::*;
+use std::collections::HashMap;
+
+fn union(a: Option<BBox>, b: Option<BBox>) -> Option<BBox> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.union(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Bounding boxes of the structures of a library, each structure computed
+/// once and its box transformed at every placement.
+///
+/// A structure with nothing in it, or only empty placements, has `None`.
+pub struct BBoxCache<'a> {
+    structures: HashMap<&'a str, &'a Structure>,
+    options: BBoxOptions,
+    memo: HashMap<&'a str, Option<BBox>>,
+    // structures being computed, outermost first
+    open: Vec<&'a str>,
+}
+
+impl<'a> BBoxCache<'a> {
+    pub fn new(library: &'a Library, options: &BBoxOptions) -> Self {
+        let mut structures = HashMap::new();
+        for s in &library.structures {
+            structures.entry(s.name.as_str()).or_insert(s);
+        }
+        BBoxCache {
+            structures,
+            options: *options,
+            memo: HashMap::new(),
+            open: vec![],
+        }
+    }
+
+    /// Box of the structure `name` in its own coordinates.
+    ///
+    /// Fails when a structure it places is undefined or places itself.
+    pub fn structure(&mut self, name: &str) -> Result<Option<BBox>, GDSIIErrorKind> {
+        let bbox = self.compute(name, None);
+        if bbox.is_err() {
+            self.open.clear();
+        }
+        bbox
+    }
+
+    fn compute(
+        &mut self,
+        name: &str,
+        parent: Option<&str>,
+    ) -> Result<Option<BBox>, GDSIIErrorKind> {
+        let Some(&structure) = self.structures.get(name) else {
+            return Err(GDSIIErrorKind::UndefinedStructure {
+                name: name.to_string(),
+                referenced_from: parent.map(|p| p.to_string()),
+            });
+        };
+        let name = structure.name.as_str();
+        if let Some(bbox) = self.memo.get(name) {
+            return Ok(*bbox);
+        }
+        if let Some(start) = self.open.iter().position(|n| *n == name) {
+            let mut cycle: Vec<String> = self.open[start..].iter().map(|n| n.to_string()).collect();
+            cycle.push(name.to_string());
+            return Err(GDSIIErrorKind::ReferenceCycle { cycle });
+        }
+        self.open.push(name);
+        let mut bbox = None;
+        for element in &structure.elements {
+            let placed = self.placed(element, name)?;
+            bbox = union(bbox, placed);
+        }
+        self.open.pop();
+        self.memo.insert(name, bbox);
+        Ok(bbox)
+    }
+
+    // box of `element` of the structure `parent`, placements included
+    pub(crate) fn placed(
+        &mut self,
+        element: &Element,
+        parent: &str,
+    ) -> Result<Option<BBox>, GDSIIErrorKind> {
+        let bbox = match element {
+            Element::SRef(e) => self
+                .compute(&e.sname, Some(parent))?
+                .map(|child| e.transform().apply_box(&child)),
+            Element::ARef(e) if e.cols > 0 && e.rows > 0 => {
+                // instances are evenly spaced, the corner ones hold the
+                // rest between them
+                self.compute(&e.sname, Some(parent))?.map(|child| {
+                    let (c, r) = (e.cols - 1, e.rows - 1);
+                    [(0, 0), (c, 0), (0, r), (c, r)]
+                        .iter()
+                        .map(|(c, r)| e.instance_transform(*c, *r).apply_box(&child))
+                        .reduce(BBox::union)
+                        .unwrap()
+                })
+            }
+            Element::ARef(_) => None,
+            e => e.bbox(&self.options),
+        };
+        Ok(bbox)
+    }
+}
+
+impl Library {
+    /// a [`BBoxCache`] to ask for the boxes of several structures
+    pub fn bboxes(&self, options: &BBoxOptions) -> BBoxCache<'_> {
+        BBoxCache::new(self, options)
+    }
+
+    /// box of the structure `name` with everything it places
+    pub fn structure_bbox(
+        &self,
+        name: &str,
+        options: &BBoxOptions,
+    ) -> Result<Option<BBox>, GDSIIErrorKind> {
+        self.bboxes(options).structure(name)
+    }
+
+    /// Box of every top structure together.
+    ///
+    /// Every structure is computed, so an undefined reference or a cycle
+    /// anywhere in the library is an error.
+    pub fn bbox(&self, options: &BBoxOptions) -> Result<Option<BBox>, GDSIIErrorKind> {
+        let mut cache = self.bboxes(options);
+        for name in self.structure_names() {
+            cache.structure(name)?;
+        }
+        let mut bbox = None;
+        for top in self.hierarchy().tops() {
+            bbox = union(bbox, cache.structure(top)?);
+        }
+        Ok(bbox)
+    }
+}
@@ -0,0 +1,221 @@
+use crate::error::GDSIIErrorKind;
+use crate::geometry::Transform;
+use crate::model::*;
+use std::collections::HashMap;
+
+/// Options of [`Library::flatten`] and [`Library::flat_elements`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FlattenOptions {
+    /// levels of SREF/AREF to expand, deeper references are kept as
+    /// references placed in top cell coordinates; `None` expands all
+    pub max_depth: Option<usize>,
+    pub keep_text: bool,
+    pub keep_node: bool,
+}
+
+enum Frame<'a> {
+    // elements of one structure, placed by `transform`
+    Elements {
+        name: &'a str,
+        elements: &'a [Element],
+        next: usize,
+        transform: Transform,
+        depth: usize,
+    },
+    // instances of an AREF still to expand, row by row
+    Array {
+        aref: &'a ARef,
+        child: &'a Structure,
+        next: u32,
+        transform: Transform,
+        depth: usize,
+    },
+}
+
+/// Every element below a top structure in top cell coordinates, see
+/// [`Library::flat_elements`].
+///
+/// Yields an error and stops at the first reference to an undefined
+/// structure or the first reference cycle.
+pub struct FlatElements<'a> {
+    structures: HashMap<&'a str, &'a Structure>,
+    options: FlattenOptions,
+    stack: Vec<Frame<'a>>,
+}
+
+impl<'a> FlatElements<'a> {
+    fn expands(&self, depth: usize) -> bool {
+        self.options.max_depth.is_none_or(|max| depth < max)
+    }
+
+    // the structure `name` referenced from `parent`, checked against the
+    // structures being expanded
+    fn child(&self, name: &str, parent: &str) -> Result<&'a Structure, GDSIIErrorKind> {
+        let child = match self.structures.get(name) {
+            Some(child) => *child,
+            None => {
+                return Err(GDSIIErrorKind::UndefinedStructure {
+                    name: name.to_string(),
+                    referenced_from: Some(parent.to_string()),
+                })
+            }
+        };
+        let path: Vec<&str> = self
+            .stack
+            .iter()
+            .filter_map(|f| match f {
+                Frame::Elements { name, .. } => Some(*name),
+                Frame::Array { .. } => None,
+            })
+            .collect();
+        if let Some(start) = path.iter().position(|n| *n == name) {
+            let mut cycle: Vec<String> = path[start..].iter().map(|n| n.to_string()).collect();
+            cycle.push(name.to_string());
+            return Err(GDSIIErrorKind::ReferenceCycle { cycle });
+        }
+        Ok(child)
+    }
+
+    fn fail(&mut self, e: GDSIIErrorKind) -> Option<Result<Element, GDSIIErrorKind>> {
+        self.stack.clear();
+        Some(Err(e))
+    }
+}
+
+impl Iterator for FlatElements<'_> {
+    type Item = Result<Element, GDSIIErrorKind>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let frame = match self.stack.last_mut()? {
+                Frame::Elements {
+                    name,
+                    elements,
+                    next,
+                    transform,
+                    depth,
+                } => match elements.get(*next) {
+                    Some(element) => {
+                        *next += 1;
+                        (*name, element, *transform, *depth)
+                    }
+                    None => {
+                        self.stack.pop();
+                        continue;
+                    }
+                },
+                Frame::Array {
+                    aref,
+                    child,
+                    next,
+                    transform,
+                    depth,
+                } => {
+                    let cols = aref.cols.max(0) as u32;
+                    let total = cols * aref.rows.max(0) as u32;
+                    if *next >= total {
+                        self.stack.pop();
+                        continue;
+                    }
+                    let (c, r) = ((*next % cols) as i16, (*next / cols) as i16);
+                    *next += 1;
+                    let instance = Frame::Elements {
+                        name: &child.name,
+                        elements: &child.elements,
+                        next: 0,
+                        transform: aref.instance_transform(c, r).then(transform),
+                        depth: *depth,
+                    };
+                    self.stack.push(instance);
+                    continue;
+                }
+            };
+            let (name, element, transform, depth) = frame;
+            match element {
+                Element::SRef(e) if self.expands(depth) => match self.child(&e.sname, name) {
+                    Ok(child) => self.stack.push(Frame::Elements {
+                        name: &child.name,
+                        elements: &child.elements,
+                        next: 0,
+                        transform: e.transform().then(&transform),
+                        depth: depth + 1,
+                    }),
+                    Err(err) => return self.fail(err),
+                },
+                Element::ARef(e) if self.expands(depth) => match self.child(&e.sname, name) {
+                    Ok(child) => self.stack.push(Frame::Array {
+                        aref: e,
+                        child,
+                        next: 0,
+                        transform,
+                        depth: depth + 1,
+                    }),
+                    Err(err) => return self.fail(err),
+                },
+                Element::Text(_) if !self.options.keep_text => {}
+                Element::Node(_) if !self.options.keep_node => {}
+                e => return Some(Ok(e.transformed(&transform))),
+            }
+        }
+    }
+}
+
+impl Library {
+    /// Iterate every element below `top` with SREF and AREF instances
+    /// expanded and placed in `top` coordinates, in file order depth first.
+    pub fn flat_elements(
+        &self,
+        top: &str,
+        options: &FlattenOptions,
+    ) -> Result<FlatElements<'_>, GDSIIErrorKind> {
+        let mut structures: HashMap<&str, &Structure> = HashMap::new();
+        for s in &self.structures {
+            structures.entry(s.name.as_str()).or_insert(s);
+        }
+        let top = match structures.get(top) {
+            Some(top) => *top,
+            None => {
+                return Err(GDSIIErrorKind::UndefinedStructure {
+                    name: top.to_string(),
+                    referenced_from: None,
+                })
+            }
+        };
+        let stack = vec![Frame::Elements {
+            name: &top.name,
+            elements: &top.elements,
+            next: 0,
+            transform: Transform::identity(),
+            depth: 0,
+        }];
+        Ok(FlatElements {
+            structures,
+            options: *options,
+            stack,
+        })
+    }
+
+    /// A library holding only `top`, with every reference below it expanded
+    /// as [`Library::flat_elements`] does.
+    pub fn flatten(&self, top: &str, options: &FlattenOptions) -> Result<Library, GDSIIErrorKind> {
+        let elements = self
+            .flat_elements(top, options)?
+            .collect::<Result<Vec<_>, _>>()?;
+        let top = self.structure(top).expect("flat_elements checked `top`");
+        let structure = Structure {
+            name: top.name.clone(),
+            created: top.created,
+            modified: top.modified,
+            strclass: top.strclass,
+            strtype: top.strtype,
+            elements,
+            raw: vec![],
+        };
+        Ok(Library {
+            header: self.header.clone(),
+            structures: vec![structure],
+            raw: vec![],
+            tail: self.tail.clone(),
+        })
+    }
+}
@@ -0,0 +1,211 @@
+use crate::error::GDSIIErrorKind;
+use crate::model::*;
+use std::collections::{HashMap, VecDeque};
+
+/// Which structures place which, built from the SREF and AREF elements of a
+/// library.
+///
+/// Structures are numbered in library order; when two share a name the
+/// first one is used. References to names no structure has are collected
+/// by [`HierarchyGraph::dangling`] instead of becoming edges.
+#[derive(Debug, Clone, Default)]
+pub struct HierarchyGraph {
+    names: Vec<String>,
+    index: HashMap<String, usize>,
+    // per structure, each child once with its number of placements, in
+    // order of first reference
+    children: Vec<Vec<(usize, u64)>>,
+    parents: Vec<Vec<usize>>,
+    // (referencing structure, undefined name)
+    dangling: Vec<(String, String)>,
+}
+
+impl HierarchyGraph {
+    pub fn new(library: &Library) -> Self {
+        let names: Vec<String> = library.structures.iter().map(|s| s.name.clone()).collect();
+        let mut index = HashMap::new();
+        for (i, name) in names.iter().enumerate() {
+            index.entry(name.clone()).or_insert(i);
+        }
+        let mut children: Vec<Vec<(usize, u64)>> = vec![vec![]; names.len()];
+        let mut parents: Vec<Vec<usize>> = vec![vec![]; names.len()];
+        let mut dangling = vec![];
+        for (parent, structure) in library.structures.iter().enumerate() {
+            for element in &structure.elements {
+                let (sname, count) = match element {
+                    Element::SRef(e) => (&e.sname, 1),
+                    Element::ARef(e) => (&e.sname, e.cols.max(0) as u64 * e.rows.max(0) as u64),
+                    _ => continue,
+                };
+                let Some(&child) = index.get(sname) else {
+                    dangling.push((structure.name.clone(), sname.clone()));
+                    continue;
+                };
+                match children[parent].iter_mut().find(|(c, _)| *c == child) {
+                    Some((_, n)) => *n += count,
+                    None => {
+                        children[parent].push((child, count));
+                        parents[child].push(parent);
+                    }
+                }
+            }
+        }
+        HierarchyGraph {
+            names,
+            index,
+            children,
+            parents,
+            dangling,
+        }
+    }
+
+    fn name(&self, i: usize) -> &str {
+        &self.names[i]
+    }
+
+    // whether structure `i` is the one its name stands for
+    fn is_node(&self, i: usize) -> bool {
+        self.index[&self.names[i]] == i
+    }
+
+    // whether a structure `name` stands for places `i`
+    fn is_placed(&self, i: usize) -> bool {
+        self.parents[i].iter().any(|p| self.is_node(*p))
+    }
+
+    /// structure names in library order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.names.iter().map(|n| n.as_str())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.index.contains_key(name)
+    }
+
+    /// structures no other structure places, in library order
+    pub fn tops(&self) -> Vec<&str> {
+        (0..self.names.len())
+            .filter(|i| self.is_node(*i) && !self.is_placed(*i))
+            .map(|i| self.name(i))
+            .collect()
+    }
+
+    /// structures placed directly by `name`, each once
+    pub fn children(&self, name: &str) -> Vec<&str> {
+        self.index.get(name).map_or(vec![], |i| {
+            self.children[*i]
+                .iter()
+                .map(|(c, _)| self.name(*c))
+                .collect()
+        })
+    }
+
+    /// structures placing `name` directly, each once
+    pub fn parents(&self, name: &str) -> Vec<&str> {
+        self.index.get(name).map_or(vec![], |i| {
+            self.parents[*i].iter().map(|p| self.name(*p)).collect()
+        })
+    }
+
+    /// placements of `child` directly in `parent`, AREF counting each
+    /// instance
+    pub fn placements(&self, parent: &str, child: &str) -> u64 {
+        match (self.index.get(parent), self.index.get(child)) {
+            (Some(p), Some(c)) => self.children[*p]
+                .iter()
+                .find(|(i, _)| i == c)
+                .map_or(0, |(_, n)| *n),
+            _ => 0,
+        }
+    }
+
+    /// SREF/AREF targets no structure defines, as (referencing structure,
+    /// undefined name) in file order
+    pub fn dangling(&self) -> &[(String, String)] {
+        &self.dangling
+    }
+
+    /// Every structure after all the structures placing it.
+    ///
+    /// Fails with [`GDSIIErrorKind::ReferenceCycle`] when structures place
+    /// each other.
+    pub fn topological_order(&self) -> Result<Vec<&str>, GDSIIErrorKind> {
+        self.order()
+            .map(|order| order.into_iter().map(|i| self.name(i)).collect())
+    }
+
+    fn order(&self) -> Result<Vec<usize>, GDSIIErrorKind> {
+        let nodes: Vec<usize> = (0..self.names.len()).filter(|i| self.is_node(*i)).collect();
+        // placements by shadowed duplicates do not count
+        let mut pending: Vec<usize> = self
+            .parents
+            .iter()
+            .map(|p| p.iter().filter(|p| self.is_node(**p)).count())
+            .collect();
+        let mut queue: VecDeque<usize> =
+            nodes.iter().copied().filter(|i| pending[*i] == 0).collect();
+        let mut order = Vec::with_capacity(nodes.len());
+        while let Some(i) = queue.pop_front() {
+            order.push(i);
+            for (child, _) in &self.children[i] {
+                pending[*child] -= 1;
+                if pending[*child] == 0 {
+                    queue.push_back(*child);
+                }
+            }
+        }
+        if order.len() == nodes.len() {
+            return Ok(order);
+        }
+        // every structure left has a parent left, walking up from any of
+        // them must come back to one already seen
+        let mut walk: Vec<usize> = vec![];
+        let mut next = nodes.iter().copied().find(|i| pending[*i] > 0);
+        while let Some(i) = next {
+            if let Some(pos) = walk.iter().position(|w| *w == i) {
+                // the walk went from child to parent, the cycle reads the
+                // other way
+                let mut cycle = vec![self.names[i].clone()];
+                cycle.extend(walk[pos + 1..].iter().rev().map(|w| self.names[*w].clone()));
+                cycle.push(self.names[i].clone());
+                return Err(GDSIIErrorKind::ReferenceCycle { cycle });
+            }
+            walk.push(i);
+            next = self.parents[i]
+                .iter()
+                .copied()
+                .find(|p| self.is_node(*p) && pending[*p] > 0);
+        }
+        let cycle = walk.iter().rev().map(|w| self.names[*w].clone()).collect();
+        Err(GDSIIErrorKind::ReferenceCycle { cycle })
+    }
+
+    /// How often each structure appears once every top is flattened, tops
+    /// counting once themselves.
+    ///
+    /// Fails with [`GDSIIErrorKind::ReferenceCycle`] when structures place
+    /// each other.
+    pub fn instance_counts(&self) -> Result<HashMap<&str, u64>, GDSIIErrorKind> {
+        let order = self.order()?;
+        let mut counts = vec![0u64; self.names.len()];
+        for i in &order {
+            if !self.is_placed(*i) {
+                counts[*i] = 1;
+            }
+            for (child, n) in &self.children[*i] {
+                counts[*child] = counts[*child].saturating_add(counts[*i].saturating_mul(*n));
+            }
+        }
+        Ok(order
+            .into_iter()
+            .map(|i| (self.name(i), counts[i]))
+            .collect())
+    }
+}
+
+impl Library {
+    /// the [`HierarchyGraph`] of the structures as they are now
+    pub fn hierarchy(&self) -> HierarchyGraph {
+        HierarchyGraph::new(self)
+    }
+}
@@ -0,0 +1,184 @@
+use crate::error::GDSIIErrorKind;
+use crate::geometry::Transform;
+use crate::model::*;
+use std::collections::{BTreeMap, HashMap};
+
+// elements of one structure by (layer, datatype), and its references
+#[derive(Debug, Default)]
+struct StructureLayers {
+    shapes: BTreeMap<(i16, i16), Vec<usize>>,
+    references: Vec<usize>,
+}
+
+/// Elements of every structure grouped by layer and datatype, built once
+/// and queried many times.
+///
+/// TEXT, NODE and BOX are indexed under their TEXTTYPE, NODETYPE and
+/// BOXTYPE. The index borrows the library, so it cannot go stale.
+pub struct LayerIndex<'a> {
+    structures: HashMap<&'a str, (&'a Structure, StructureLayers)>,
+    // library order, for listing
+    names: Vec<&'a str>,
+}
+
+impl<'a> LayerIndex<'a> {
+    pub fn new(library: &'a Library) -> Self {
+        let mut structures = HashMap::new();
+        let mut names = vec![];
+        for structure in &library.structures {
+            if structures.contains_key(structure.name.as_str()) {
+                continue;
+            }
+            let mut layers = StructureLayers::default();
+            for (i, element) in structure.elements.iter().enumerate() {
+                match (element.layer(), element.datatype()) {
+                    (Some(layer), Some(datatype)) => {
+                        layers.shapes.entry((layer, datatype)).or_default().push(i)
+                    }
+                    _ => layers.references.push(i),
+                }
+            }
+            names.push(structure.name.as_str());
+            structures.insert(structure.name.as_str(), (structure, layers));
+        }
+        LayerIndex { structures, names }
+    }
+
+    /// every (layer, datatype) in use with its number of elements, summed
+    /// over all structures and not multiplied by placements
+    pub fn layers(&self) -> BTreeMap<(i16, i16), usize> {
+        let mut layers = BTreeMap::new();
+        for name in &self.names {
+            for (key, count) in self.structure_layers(name) {
+                *layers.entry(key).or_insert(0) += count;
+            }
+        }
+        layers
+    }
+
+    /// (layer, datatype) pairs drawn directly in `structure` with counts
+    pub fn structure_layers(&self, structure: &str) -> BTreeMap<(i16, i16), usize> {
+        self.structures
+            .get(structure)
+            .map(|(_, layers)| {
+                layers
+                    .shapes
+                    .iter()
+                    .map(|(key, elements)| (*key, elements.len()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// elements of `structure` itself on `layer`/`datatype`, in file order
+    pub fn shapes_on(
+        &self,
+        structure: &str,
+        layer: i16,
+        datatype: i16,
+    ) -> impl Iterator<Item = &'a Element> + '_ {
+        let (elements, indices): (&[Element], &[usize]) = match self.structures.get(structure) {
+            Some((s, layers)) => (
+                &s.elements,
+                layers
+                    .shapes
+                    .get(&(layer, datatype))
+                    .map_or(&[], |v| v.as_slice()),
+            ),
+            None => (&[], &[]),
+        };
+        indices.iter().map(move |i| &elements[*i])
+    }
+
+    /// Elements on `layer`/`datatype` below `top` with every reference
+    /// expanded, in `top` coordinates.
+    ///
+    /// Structures with nothing on the layer anywhere below them are skipped
+    /// without being expanded. Fails like [`Library::flatten`] on undefined
+    /// structures and cycles.
+    pub fn flat_shapes_on(
+        &self,
+        top: &str,
+        layer: i16,
+        datatype: i16,
+    ) -> Result<Vec<Element>, GDSIIErrorKind> {
+        let key = (layer, datatype);
+        let mut reaches = HashMap::new();
+        self.reaches(top, None, key, &mut reaches, &mut vec![])?;
+        let mut shapes = vec![];
+        self.collect(top, key, &Transform::identity(), &reaches, &mut shapes);
+        Ok(shapes)
+    }
+
+    // whether `name` or anything it places draws on `key`, checking every
+    // structure below it on the way
+    fn reaches(
+        &self,
+        name: &str,
+        parent: Option<&str>,
+        key: (i16, i16),
+        memo: &mut HashMap<&'a str, bool>,
+        open: &mut Vec<&'a str>,
+    ) -> Result<bool, GDSIIErrorKind> {
+        let Some((structure, layers)) = self.structures.get(name) else {
+            return Err(GDSIIErrorKind::UndefinedStructure {
+                name: name.to_string(),
+                referenced_from: parent.map(|p| p.to_string()),
+            });
+        };
+        let name = structure.name.as_str();
+        if let Some(reaches) = memo.get(name) {
+            return Ok(*reaches);
+        }
+        if let Some(start) = open.iter().position(|n| *n == name) {
+            let mut cycle: Vec<String> = open[start..].iter().map(|n| n.to_string()).collect();
+            cycle.push(name.to_string());
+            return Err(GDSIIErrorKind::ReferenceCycle { cycle });
+        }
+        open.push(name);
+        let mut reaches = layers.shapes.contains_key(&key);
+        for i in &layers.references {
+            if let Some(sname) = structure.elements[*i].sname() {
+                reaches |= self.reaches(sname, Some(name), key, memo, open)?;
+            }
+        }
+        open.pop();
+        memo.insert(name, reaches);
+        Ok(reaches)
+    }
+
+    fn collect(
+        &self,
+        name: &str,
+        key: (i16, i16),
+        t: &Transform,
+        reaches: &HashMap<&'a str, bool>,
+        shapes: &mut Vec<Element>,
+    ) {
+        let (structure, layers) = &self.structures[name];
+        let mut order: Vec<usize> = layers.shapes.get(&key).cloned().unwrap_or_default();
+        order.extend(&layers.references);
+        order.sort_unstable();
+        for i in order {
+            match &structure.elements[i] {
+                Element::SRef(e) if reaches[e.sname.as_str()] => {
+                    self.collect(&e.sname, key, &e.transform().then(t), reaches, shapes)
+                }
+                Element::ARef(e) if reaches[e.sname.as_str()] => {
+                    for instance in e.transforms() {
+                        self.collect(&e.sname, key, &instance.then(t), reaches, shapes)
+                    }
+                }
+                Element::SRef(_) | Element::ARef(_) => {}
+                element => shapes.push(element.transformed(t)),
+            }
+        }
+    }
+}
+
+impl Library {
+    /// a [`LayerIndex`] over the structures as they are now
+    pub fn layer_index(&self) -> LayerIndex<'_> {
+        LayerIndex::new(self)
+    }
+}
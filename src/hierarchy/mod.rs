@@ -0,0 +1,11 @@
+mod bbox;
+mod flatten;
+mod graph;
+mod layers;
+mod spatial;
+
+pub use bbox::BBoxCache;
+pub use flatten::{FlatElements, FlattenOptions};
+pub use graph::HierarchyGraph;
+pub use layers::LayerIndex;
+pub use spatial::{SpatialIndex, SpatialOptions};
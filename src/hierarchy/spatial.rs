@@ -0,0 +1,159 @@
+use super::BBoxCache;
+use crate::error::GDSIIErrorKind;
+use crate::geometry::{polygon_contains, BBox, BBoxOptions, RTree};
+use crate::model::*;
+
+/// What a [`SpatialIndex`] holds.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SpatialOptions {
+    pub bbox: BBoxOptions,
+    /// index SREF and AREF by the box of everything they place
+    pub references: bool,
+}
+
+/// R-tree over the elements of one structure, answering with element
+/// positions in [`Structure::elements`].
+///
+/// The index keeps no borrow of the library: edit the structure, then
+/// [`SpatialIndex::refresh`] to re-index only the elements whose boxes
+/// changed.
+#[derive(Debug, Clone)]
+pub struct SpatialIndex {
+    structure: String,
+    options: SpatialOptions,
+    // box of each element by position, `None` when not indexed
+    boxes: Vec<Option<BBox>>,
+    tree: RTree<usize>,
+}
+
+impl SpatialIndex {
+    pub fn build(
+        library: &Library,
+        structure: &str,
+        options: &SpatialOptions,
+    ) -> Result<Self, GDSIIErrorKind> {
+        let boxes = Self::element_boxes(library, structure, options)?;
+        let tree = RTree::bulk_load(
+            boxes
+                .iter()
+                .enumerate()
+                .filter_map(|(i, b)| b.map(|b| (b, i)))
+                .collect(),
+        );
+        Ok(SpatialIndex {
+            structure: structure.to_string(),
+            options: *options,
+            boxes,
+            tree,
+        })
+    }
+
+    fn element_boxes(
+        library: &Library,
+        name: &str,
+        options: &SpatialOptions,
+    ) -> Result<Vec<Option<BBox>>, GDSIIErrorKind> {
+        let Some(structure) = library.structure(name) else {
+            return Err(GDSIIErrorKind::UndefinedStructure {
+                name: name.to_string(),
+                referenced_from: None,
+            });
+        };
+        let mut cache = BBoxCache::new(library, &options.bbox);
+        structure
+            .elements
+            .iter()
+            .map(|element| match element {
+                Element::SRef(_) | Element::ARef(_) if !options.references => Ok(None),
+                element => cache.placed(element, name),
+            })
+            .collect()
+    }
+
+    /// Bring the index up to date with the structure as it is in `library`,
+    /// returning how many elements were re-indexed.
+    ///
+    /// Every element's box is recomputed, only those that changed touch
+    /// the tree.
+    pub fn refresh(&mut self, library: &Library) -> Result<usize, GDSIIErrorKind> {
+        let boxes = Self::element_boxes(library, &self.structure, &self.options)?;
+        let mut changed = 0;
+        for i in 0..boxes.len().max(self.boxes.len()) {
+            let old = self.boxes.get(i).copied().flatten();
+            let new = boxes.get(i).copied().flatten();
+            if old == new {
+                continue;
+            }
+            if let Some(old) = old {
+                self.tree.remove(&old, &i);
+            }
+            if let Some(new) = new {
+                self.tree.insert(new, i);
+            }
+            changed += 1;
+        }
+        self.boxes = boxes;
+        Ok(changed)
+    }
+
+    /// name of the indexed structure
+    pub fn structure(&self) -> &str {
+        &self.structure
+    }
+
+    /// number of indexed elements
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// box of the element at `index`, `None` when it is not indexed
+    pub fn element_bbox(&self, index: usize) -> Option<BBox> {
+        self.boxes.get(index).copied().flatten()
+    }
+
+    /// elements whose boxes intersect `window`, in file order
+    pub fn window(&self, window: &BBox) -> Vec<usize> {
+        let mut found: Vec<usize> = self.tree.search(window).into_iter().copied().collect();
+        found.sort_unstable();
+        found
+    }
+
+    /// elements whose boxes hold `p`, in file order
+    pub fn at_point(&self, p: Point) -> Vec<usize> {
+        self.window(&BBox::new(p, p))
+    }
+
+    /// Elements of `structure` covering `p`: BOUNDARY and BOX by their
+    /// outline, everything else by its box.
+    pub fn containing(&self, structure: &Structure, p: Point) -> Vec<usize> {
+        self.at_point(p)
+            .into_iter()
+            .filter(|i| match structure.elements.get(*i) {
+                Some(Element::Boundary(e)) => polygon_contains(&e.points, p),
+                Some(Element::Box(e)) => polygon_contains(&e.points, p),
+                Some(_) => true,
+                None => false,
+            })
+            .collect()
+    }
+
+    /// element whose box is closest to `p` with that distance, 0 inside
+    pub fn nearest(&self, p: Point) -> Option<(usize, f64)> {
+        self.tree.nearest(p).map(|(i, d)| (*i, d))
+    }
+}
+
+impl Library {
+    /// a [`SpatialIndex`] over the elements of the structure `name`
+    pub fn spatial_index(
+        &self,
+        name: &str,
+        options: &SpatialOptions,
+    ) -> Result<SpatialIndex, GDSIIErrorKind> {
+        SpatialIndex::build(self, name, options)
+    }
+}
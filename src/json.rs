@@ -0,0 +1,42 @@
+use crate::error::GDSIIErrorKind;
+use crate::model::Library;
+
+impl Library {
+    /// The library as JSON.
+    ///
+    /// The schema follows the model field by field and stays stable between
+    /// releases:
+    ///
+    /// - the library is `{"header": [...], "structures": [...], "raw": [...],
+    ///   "tail": [...]}`, `raw` left out when empty and `tail` the bytes
+    ///   after ENDLIB
+    /// - header records are `{"record": "UNITS", "value": [0.001, 1e-9]}`,
+    ///   named as in the Stream Format, `value` left out for ENDMASKS
+    /// - a structure has `name`, `created` and `modified` timestamps
+    ///   (`year` ... `second`), `strclass`, `strtype` and `elements`
+    /// - an element is an object with `"type"` one of `boundary`, `path`,
+    ///   `sref`, `aref`, `text`, `node` and `box` next to the fields of
+    ///   [`Boundary`](crate::Boundary), [`Path`](crate::Path), ...; points
+    ///   are `[x, y]` pairs, absent optional records `null`
+    /// - `extra` element records use the header record layout, unknown
+    ///   records are `[index, {"rtype", "dtype", "bytes"}]`
+    /// - reals are numbers, or `"0x..."` strings of their bits when an
+    ///   `f64` cannot hold them exactly
+    ///
+    /// [`Library::from_json`] reads it back to a library that saves to the
+    /// same bytes. Missing fields take their default when reading.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("the model serializes to JSON")
+    }
+
+    /// [`Library::to_json`] indented for reading
+    pub fn to_json_pretty(&self) -> String {
+        serde_json::to_string_pretty(self).expect("the model serializes to JSON")
+    }
+
+    /// library read from [`Library::to_json`] output, fails with
+    /// [`GDSIIErrorKind::InvalidJson`]
+    pub fn from_json(json: &str) -> Result<Library, GDSIIErrorKind> {
+        Ok(serde_json::from_str(json)?)
+    }
+}
@@ -0,0 +1,275 @@
+use crate::error::GDSIIErrorKind;
+use crate::model::*;
+use std::collections::HashMap;
+
+/// What [`Library::remap_layers`] does with an element whose layer the
+/// map does not name.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Unmapped {
+    /// leave its numbers as they are
+    #[default]
+    Keep,
+    /// remove the element
+    Drop,
+    /// fail with [`GDSIIErrorKind::UnmappedLayer`] and change nothing
+    Error,
+}
+
+/// Renumbering of (layer, datatype) pairs.
+///
+/// The text format has one mapping per line, `#` starting a comment:
+///
+/// ```text
+/// 31 0 -> 12 5    # one pair
+/// 32/1 -> 13/0    # the same with slashes
+/// 40 * -> 41 *    # every datatype of layer 40, datatypes kept
+/// 50 * -> 51 0    # every datatype of layer 50 merged into 51/0
+/// ```
+///
+/// An exact pair wins over a whole layer mapping. TEXTTYPE, NODETYPE and
+/// BOXTYPE are looked up as the datatype.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LayerMap {
+    pairs: HashMap<(i16, i16), (i16, i16)>,
+    // layer -> (layer, datatype or `None` to keep it)
+    layers: HashMap<i16, (i16, Option<i16>)>,
+}
+
+fn invalid(line: usize, reason: impl Into<String>) -> GDSIIErrorKind {
+    GDSIIErrorKind::InvalidLayerMap {
+        line,
+        reason: reason.into(),
+    }
+}
+
+// a layer or datatype number, `None` for `*`
+fn number(token: &str, line: usize, wildcard: bool) -> Result<Option<i16>, GDSIIErrorKind> {
+    if wildcard && token == "*" {
+        return Ok(None);
+    }
+    token.parse().map(Some).map_err(|_| {
+        invalid(
+            line,
+            format!("`{}` is not a layer or datatype number", token),
+        )
+    })
+}
+
+// "31 0" or "31/0"
+fn pair(text: &str, line: usize) -> Result<(i16, Option<i16>), GDSIIErrorKind> {
+    let tokens: Vec<&str> = text
+        .split(|c: char| c == '/' || c.is_whitespace())
+        .filter(|t| !t.is_empty())
+        .collect();
+    match tokens.as_slice() {
+        [layer, datatype] => Ok((
+            number(layer, line, false)?.unwrap(),
+            number(datatype, line, true)?,
+        )),
+        _ => Err(invalid(
+            line,
+            format!("expected `layer datatype`, found `{}`", text.trim()),
+        )),
+    }
+}
+
+impl LayerMap {
+    pub fn new() -> Self {
+        LayerMap::default()
+    }
+
+    /// map one pair, replacing an earlier mapping of it
+    pub fn insert(&mut self, from: (i16, i16), to: (i16, i16)) {
+        self.pairs.insert(from, to);
+    }
+
+    /// map every datatype of `layer`, to `datatype` or to itself when
+    /// `None`
+    pub fn insert_layer(&mut self, layer: i16, to_layer: i16, to_datatype: Option<i16>) {
+        self.layers.insert(layer, (to_layer, to_datatype));
+    }
+
+    pub fn len(&self) -> usize {
+        self.pairs.len() + self.layers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// where `layer`/`datatype` goes, `None` when unmapped
+    pub fn get(&self, layer: i16, datatype: i16) -> Option<(i16, i16)> {
+        if let Some(to) = self.pairs.get(&(layer, datatype)) {
+            return Some(*to);
+        }
+        self.layers
+            .get(&layer)
+            .map(|(l, d)| (*l, d.unwrap_or(datatype)))
+    }
+
+    /// a map in the `layer datatype -> layer datatype` text format
+    pub fn parse(text: &str) -> Result<Self, GDSIIErrorKind> {
+        let mut map = LayerMap::new();
+        for (i, line) in text.lines().enumerate() {
+            let line_no = i + 1;
+            let line = line.split('#').next().unwrap_or("");
+            if line.trim().is_empty() {
+                continue;
+            }
+            let Some((from, to)) = line.split_once("->") else {
+                return Err(invalid(line_no, "expected `->`"));
+            };
+            match (pair(from, line_no)?, pair(to, line_no)?) {
+                ((layer, Some(datatype)), (to_layer, Some(to_datatype))) => {
+                    map.insert((layer, datatype), (to_layer, to_datatype))
+                }
+                ((layer, None), (to_layer, to_datatype)) => {
+                    map.insert_layer(layer, to_layer, to_datatype)
+                }
+                ((_, Some(_)), (_, None)) => {
+                    return Err(invalid(line_no, "`*` target needs a `*` source datatype"))
+                }
+            }
+        }
+        Ok(map)
+    }
+
+    /// [`LayerMap::parse`] on the contents of a file
+    pub fn from_file<P: AsRef<std::path::Path>>(path: P) -> Result<Self, GDSIIErrorKind> {
+        LayerMap::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Renumber every layer `from` names to the numbers `to` gives the same
+    /// layer and purpose names; names `to` lacks stay unmapped.
+    pub fn between(from: &CadenceLayerMap, to: &CadenceLayerMap) -> Self {
+        let mut map = LayerMap::new();
+        for entry in &from.entries {
+            if let Some(target) = to.get(&entry.name, &entry.purpose) {
+                map.insert(
+                    (entry.layer, entry.datatype),
+                    (target.layer, target.datatype),
+                );
+            }
+        }
+        map
+    }
+}
+
+/// One line of a Cadence layer map.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CadenceLayer {
+    pub name: String,
+    pub purpose: String,
+    pub layer: i16,
+    pub datatype: i16,
+}
+
+/// Cadence style layer map file, naming the stream numbers of each layer
+/// and purpose:
+///
+/// ```text
+/// # name  purpose  layer  datatype
+/// M1      drawing  31     0
+/// M1      pin      31     2
+/// ```
+///
+/// Columns after the datatype are ignored. Two of them, one per PDK, give a
+/// [`LayerMap`] through [`LayerMap::between`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CadenceLayerMap {
+    pub entries: Vec<CadenceLayer>,
+}
+
+impl CadenceLayerMap {
+    pub fn parse(text: &str) -> Result<Self, GDSIIErrorKind> {
+        let mut entries = vec![];
+        for (i, line) in text.lines().enumerate() {
+            let line_no = i + 1;
+            let line = line.split('#').next().unwrap_or("");
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            match tokens.as_slice() {
+                [] => {}
+                [name, purpose, layer, datatype, ..] => entries.push(CadenceLayer {
+                    name: name.to_string(),
+                    purpose: purpose.to_string(),
+                    layer: number(layer, line_no, false)?.unwrap(),
+                    datatype: number(datatype, line_no, false)?.unwrap(),
+                }),
+                _ => return Err(invalid(line_no, "expected `name purpose layer datatype`")),
+            }
+        }
+        Ok(CadenceLayerMap { entries })
+    }
+
+    pub fn from_file<P: AsRef<std::path::Path>>(path: P) -> Result<Self, GDSIIErrorKind> {
+        CadenceLayerMap::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// first entry for `name` and `purpose`
+    pub fn get(&self, name: &str, purpose: &str) -> Option<&CadenceLayer> {
+        self.entries
+            .iter()
+            .find(|e| e.name == name && e.purpose == purpose)
+    }
+}
+
+// LAYER and DATATYPE/TEXTTYPE/NODETYPE/BOXTYPE of a drawn element
+fn numbers(element: &mut Element) -> Option<(&mut i16, &mut i16)> {
+    match element {
+        Element::Boundary(e) => Some((&mut e.layer, &mut e.datatype)),
+        Element::Path(e) => Some((&mut e.layer, &mut e.datatype)),
+        Element::Text(e) => Some((&mut e.layer, &mut e.texttype)),
+        Element::Node(e) => Some((&mut e.layer, &mut e.nodetype)),
+        Element::Box(e) => Some((&mut e.layer, &mut e.boxtype)),
+        Element::SRef(_) | Element::ARef(_) => None,
+    }
+}
+
+impl Library {
+    /// Renumber the layers of every drawn element through `map`.
+    ///
+    /// With [`Unmapped::Error`] the whole library is checked before
+    /// anything changes.
+    pub fn remap_layers(
+        &mut self,
+        map: &LayerMap,
+        unmapped: Unmapped,
+    ) -> Result<(), GDSIIErrorKind> {
+        if unmapped == Unmapped::Error {
+            for structure in &self.structures {
+                for element in &structure.elements {
+                    if let (Some(layer), Some(datatype)) = (element.layer(), element.datatype()) {
+                        if map.get(layer, datatype).is_none() {
+                            return Err(GDSIIErrorKind::UnmappedLayer {
+                                layer,
+                                datatype,
+                                structure: structure.name.clone(),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        for structure in &mut self.structures {
+            let mut keep = Vec::with_capacity(structure.elements.len());
+            for element in &mut structure.elements {
+                let mapped = match numbers(element) {
+                    Some((layer, datatype)) => match map.get(*layer, *datatype) {
+                        Some(to) => {
+                            (*layer, *datatype) = to;
+                            true
+                        }
+                        None => false,
+                    },
+                    None => true,
+                };
+                keep.push(mapped || unmapped != Unmapped::Drop);
+            }
+            if keep.contains(&false) {
+                let mut keep = keep.into_iter();
+                structure.retain_elements(|_| keep.next().unwrap_or(true));
+            }
+        }
+        Ok(())
+    }
+}
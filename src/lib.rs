@@ -1,20 +1,150 @@
-#![allow(dead_code)]
+//! GDSII Stream Format parser and saver.
+//!
+//! [`parse_gds2`], [`parse_bytes`] and [`from_reader`] build a [`Library`];
+//! [`Library::save_gds2`], [`Library::to_bytes`] and [`Library::to_writer`]
+//! write it back byte for byte. [`RecordReader`] and [`GdsWriter`] work
+//! record by record for streams too large to hold in memory, [`gds2ascii`]
+//! and [`ascii2gds`] turn a stream into an editable text dump and back.
+//! With the `serde` feature the model implements `Serialize` and
+//! `Deserialize`, see `Library::to_json` for the JSON schema.
+//!
+//! ```no_run
+//! use gds2_io::prelude::*;
+//!
+//! let lib = parse_gds2("chip.gds")?;
+//! for s in &lib.structures {
+//!     println!("{}: {} elements", s.name, s.elements.len());
+//! }
+//! lib.save_gds2("copy.gds")?;
+//! # Ok::<(), GDSIIErrorKind>(())
+//! ```
 
-use crate::model::GDSIIModel;
-use crate::parser::gds2_parser;
+use crate::oasis::oasis_parser;
+use crate::parser::{gds2_parser, gds2_parser_with, gds2_read};
+use crate::saver::first_difference;
 
-use crate::error::GDSIIErrorKind;
+pub use crate::ascii::{ascii2gds, gds2ascii};
+pub use crate::error::{ExpectedLength, GDSIIErrorKind};
+pub use crate::geometry::{polygon_contains, BBox, BBoxOptions, RTree, Transform};
+pub use crate::hierarchy::{
+    BBoxCache, FlatElements, FlattenOptions, HierarchyGraph, LayerIndex, SpatialIndex,
+    SpatialOptions,
+};
+pub use crate::layermap::{CadenceLayer, CadenceLayerMap, LayerMap, Unmapped};
+pub use crate::model::{
+    record_name, ARef, Boundary, Element, FileHeader, GDSIIModel, GDSIIVariant, GdsBox, Library,
+    ModuleHeader, Node, Path, Point, Property, RawRecord, Real8, SRef, Strans, Structure, Text,
+    Timestamp, Tuctosin, TuctosinHeader,
+};
+pub use crate::oasis::OasisOptions;
+pub use crate::parser::{ParseOptions, RecordReader};
+pub use crate::render::{
+    Color, Hatch, Image, LayerStyle, LayerStyles, RasterOptions, SvgOptions, Viewport,
+};
+pub use crate::saver::GdsWriter;
+pub use crate::summary::{ElementCounts, LargestPolygon, LayerCounts, Summary};
 
+mod ascii;
 mod error;
+mod geometry;
+mod hierarchy;
+#[cfg(feature = "serde")]
+mod json;
+mod layermap;
 mod model;
+mod oasis;
 mod parser;
+mod render;
 mod saver;
+mod summary;
+
+/// The types and entry points most programs need, `use gds2_io::prelude::*;`
+pub mod prelude {
+    pub use crate::{
+        from_reader, parse_bytes, parse_gds2, ARef, BBox, BBoxOptions, Boundary, Element,
+        FlattenOptions, GDSIIErrorKind, GdsBox, GdsWriter, HierarchyGraph, Library, Node,
+        ParseOptions, Path, Point, Property, RecordReader, SRef, Strans, Structure, Text,
+        Transform,
+    };
+}
 
 /// gds2 file path
 pub fn parse_gds2<P: AsRef<std::path::Path>>(
     file: P,
-) -> std::result::Result<GDSIIModel, GDSIIErrorKind> {
+) -> std::result::Result<Library, GDSIIErrorKind> {
+    parse_gds2_with(file, &ParseOptions::default())
+}
+
+/// gds2 file path, parsed with the given options
+pub fn parse_gds2_with<P: AsRef<std::path::Path>>(
+    file: P,
+    options: &ParseOptions,
+) -> std::result::Result<Library, GDSIIErrorKind> {
+    let file = std::io::BufReader::new(std::fs::File::open(file)?);
+    from_reader_with(file, options)
+}
+
+/// gds2 stream already in memory
+pub fn parse_bytes(data: &[u8]) -> std::result::Result<Library, GDSIIErrorKind> {
+    gds2_parser(data)
+}
+
+/// gds2 stream already in memory, parsed with the given options
+pub fn parse_bytes_with(
+    data: &[u8],
+    options: &ParseOptions,
+) -> std::result::Result<Library, GDSIIErrorKind> {
+    gds2_parser_with(data, options)
+}
+
+/// gds2 stream read to its end from `reader`, e.g. stdin or a decompressor;
+/// wrap unbuffered readers in a [`std::io::BufReader`]
+pub fn from_reader<R: std::io::Read>(reader: R) -> std::result::Result<Library, GDSIIErrorKind> {
+    from_reader_with(reader, &ParseOptions::default())
+}
+
+/// gds2 stream read from `reader`, parsed with the given options
+pub fn from_reader_with<R: std::io::Read>(
+    reader: R,
+    options: &ParseOptions,
+) -> std::result::Result<Library, GDSIIErrorKind> {
+    gds2_read(reader, options)
+}
+
+/// gds2 stream given as a [`gds2ascii`] text dump
+pub fn parse_ascii(text: &str) -> std::result::Result<Library, GDSIIErrorKind> {
+    gds2_parser(&ascii2gds(text)?)
+}
+
+/// OASIS file path, read into the same model as a gds2 file
+pub fn parse_oasis<P: AsRef<std::path::Path>>(
+    file: P,
+) -> std::result::Result<Library, GDSIIErrorKind> {
+    parse_oasis_bytes(&std::fs::read(file)?)
+}
+
+/// OASIS stream already in memory.
+///
+/// Shapes become BOUNDARY and PATH elements, repeated placements on a
+/// lattice become AREFs and other repetitions one element per instance.
+/// `S_GDS_PROPERTY` properties become element properties, other properties
+/// and XNAME, XELEMENT and XGEOMETRY records are read and dropped. The
+/// library gets the name `LIB` and units from the START record.
+pub fn parse_oasis_bytes(data: &[u8]) -> std::result::Result<Library, GDSIIErrorKind> {
+    oasis_parser(data)
+}
+
+/// Parse a gds2 file, keeping unknown records, write it back in memory and
+/// compare the result with the file, reporting the first record that differs
+/// as [`GDSIIErrorKind::RoundtripMismatch`].
+pub fn verify_roundtrip<P: AsRef<std::path::Path>>(
+    file: P,
+) -> std::result::Result<(), GDSIIErrorKind> {
     let buff = std::fs::read(file)?;
-    let gds2: GDSIIModel = gds2_parser(&buff)?;
-    Ok(gds2)
+    let gds2 = gds2_parser_with(&buff, &ParseOptions { keep_unknown: true })?;
+    let saved = gds2.to_bytes()?;
+    match first_difference(&buff, &saved) {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
 }
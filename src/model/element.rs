@@ -0,0 +1,351 @@
+use super::{RawRecord, Strans, Tuctosin, TuctosinHeader};
+
+/// A coordinate pair in database units
+pub type Point = (i32, i32);
+
+/// One element of a structure, everything from its header record up to ENDEL.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename_all = "lowercase"))]
+pub enum Element {
+    Boundary(Boundary),
+    Path(Path),
+    SRef(SRef),
+    ARef(ARef),
+    Text(Text),
+    Node(Node),
+    Box(GdsBox),
+}
+
+impl Element {
+    /// the record that opens this element
+    pub fn header(&self) -> TuctosinHeader {
+        match self {
+            Element::Boundary(_) => TuctosinHeader::Boundary,
+            Element::Path(_) => TuctosinHeader::Path,
+            Element::SRef(_) => TuctosinHeader::Sref,
+            Element::ARef(_) => TuctosinHeader::Aref,
+            Element::Text(e) if e.textnode => TuctosinHeader::TextNode,
+            Element::Text(_) => TuctosinHeader::Text,
+            Element::Node(_) => TuctosinHeader::Node,
+            Element::Box(_) => TuctosinHeader::Box,
+        }
+    }
+
+    /// layer of a drawn element, `None` for references
+    pub fn layer(&self) -> Option<i16> {
+        match self {
+            Element::Boundary(e) => Some(e.layer),
+            Element::Path(e) => Some(e.layer),
+            Element::Text(e) => Some(e.layer),
+            Element::Node(e) => Some(e.layer),
+            Element::Box(e) => Some(e.layer),
+            Element::SRef(_) | Element::ARef(_) => None,
+        }
+    }
+
+    /// DATATYPE, TEXTTYPE, NODETYPE or BOXTYPE depending on the kind
+    pub fn datatype(&self) -> Option<i16> {
+        match self {
+            Element::Boundary(e) => Some(e.datatype),
+            Element::Path(e) => Some(e.datatype),
+            Element::Text(e) => Some(e.texttype),
+            Element::Node(e) => Some(e.nodetype),
+            Element::Box(e) => Some(e.boxtype),
+            Element::SRef(_) | Element::ARef(_) => None,
+        }
+    }
+
+    /// name of the referenced structure for SREF/AREF
+    pub fn sname(&self) -> Option<&str> {
+        match self {
+            Element::SRef(e) => Some(&e.sname),
+            Element::ARef(e) => Some(&e.sname),
+            _ => None,
+        }
+    }
+
+    /// obsolete, unreleased and extension records (UINTEGER, USTRING,
+    /// SPACING, ELKEY, LINKTYPE, LINKKEYS, BORDER, CONTACT, ...) in file order
+    pub fn extra(&self) -> &[Tuctosin] {
+        match self {
+            Element::Boundary(e) => &e.extra,
+            Element::Path(e) => &e.extra,
+            Element::SRef(e) => &e.extra,
+            Element::ARef(e) => &e.extra,
+            Element::Text(e) => &e.extra,
+            Element::Node(e) => &e.extra,
+            Element::Box(e) => &e.extra,
+        }
+    }
+
+    /// records kept by [`ParseOptions::keep_unknown`], each with the index of
+    /// the body record it precedes in [`Element::records`] order
+    ///
+    /// [`ParseOptions::keep_unknown`]: crate::ParseOptions::keep_unknown
+    pub fn raw(&self) -> &[(usize, RawRecord)] {
+        match self {
+            Element::Boundary(e) => &e.raw,
+            Element::Path(e) => &e.raw,
+            Element::SRef(e) => &e.raw,
+            Element::ARef(e) => &e.raw,
+            Element::Text(e) => &e.raw,
+            Element::Node(e) => &e.raw,
+            Element::Box(e) => &e.raw,
+        }
+    }
+
+    /// record types of the body records in file order, kept only when the
+    /// file did not follow Stream Format order so that
+    /// [`Element::records`] writes them back as read
+    pub fn order(&self) -> &[[u8; 2]] {
+        match self {
+            Element::Boundary(e) => &e.order,
+            Element::Path(e) => &e.order,
+            Element::SRef(e) => &e.order,
+            Element::ARef(e) => &e.order,
+            Element::Text(e) => &e.order,
+            Element::Node(e) => &e.order,
+            Element::Box(e) => &e.order,
+        }
+    }
+
+    pub(crate) fn set_order(&mut self, order: Vec<[u8; 2]>) {
+        match self {
+            Element::Boundary(e) => e.order = order,
+            Element::Path(e) => e.order = order,
+            Element::SRef(e) => e.order = order,
+            Element::ARef(e) => e.order = order,
+            Element::Text(e) => e.order = order,
+            Element::Node(e) => e.order = order,
+            Element::Box(e) => e.order = order,
+        }
+    }
+
+    /// STRANS, MAG and ANGLE of references and texts
+    pub fn strans(&self) -> Option<&Strans> {
+        match self {
+            Element::SRef(e) => e.strans.as_ref(),
+            Element::ARef(e) => e.strans.as_ref(),
+            Element::Text(e) => e.strans.as_ref(),
+            _ => None,
+        }
+    }
+
+    pub fn props(&self) -> &[Property] {
+        match self {
+            Element::Boundary(e) => &e.props,
+            Element::Path(e) => &e.props,
+            Element::SRef(e) => &e.props,
+            Element::ARef(e) => &e.props,
+            Element::Text(e) => &e.props,
+            Element::Node(e) => &e.props,
+            Element::Box(e) => &e.props,
+        }
+    }
+}
+
+/// PROPATTR/PROPVALUE pair attached to an element
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Property {
+    pub attr: i16,
+    pub value: String,
+}
+
+/// Filled polygon, the first and last point are identical.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Boundary {
+    pub elflags: Option<i16>,
+    pub plex: Option<i32>,
+    pub layer: i16,
+    pub datatype: i16,
+    pub points: Vec<Point>,
+    pub props: Vec<Property>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub extra: Vec<Tuctosin>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub raw: Vec<(usize, RawRecord)>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub order: Vec<[u8; 2]>,
+}
+
+/// Wire of a given width along its center line.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Path {
+    pub elflags: Option<i16>,
+    pub plex: Option<i32>,
+    pub layer: i16,
+    pub datatype: i16,
+    pub pathtype: Option<i16>,
+    pub width: Option<i32>,
+    pub bgnextn: Option<i32>,
+    pub endextn: Option<i32>,
+    pub points: Vec<Point>,
+    pub props: Vec<Property>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub extra: Vec<Tuctosin>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub raw: Vec<(usize, RawRecord)>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub order: Vec<[u8; 2]>,
+}
+
+/// Single placement of another structure.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct SRef {
+    pub elflags: Option<i16>,
+    pub plex: Option<i32>,
+    pub sname: String,
+    pub strans: Option<Strans>,
+    pub origin: Point,
+    pub props: Vec<Property>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub extra: Vec<Tuctosin>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub raw: Vec<(usize, RawRecord)>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub order: Vec<[u8; 2]>,
+}
+
+/// Array placement of another structure.
+///
+/// The XY record holds three points: the origin, the origin displaced by
+/// `cols` column pitches and the origin displaced by `rows` row pitches.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct ARef {
+    pub elflags: Option<i16>,
+    pub plex: Option<i32>,
+    pub sname: String,
+    pub strans: Option<Strans>,
+    pub cols: i16,
+    pub rows: i16,
+    pub origin: Point,
+    pub col_point: Point,
+    pub row_point: Point,
+    pub props: Vec<Property>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub extra: Vec<Tuctosin>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub raw: Vec<(usize, RawRecord)>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub order: Vec<[u8; 2]>,
+}
+
+impl ARef {
+    /// Build an array from its origin and per-instance pitch vectors.
+    pub fn new<S: Into<String>>(
+        sname: S,
+        origin: Point,
+        cols: i16,
+        rows: i16,
+        col_pitch: Point,
+        row_pitch: Point,
+    ) -> Self {
+        ARef {
+            sname: sname.into(),
+            cols,
+            rows,
+            origin,
+            col_point: (
+                origin.0 + col_pitch.0 * cols as i32,
+                origin.1 + col_pitch.1 * cols as i32,
+            ),
+            row_point: (
+                origin.0 + row_pitch.0 * rows as i32,
+                origin.1 + row_pitch.1 * rows as i32,
+            ),
+            ..Default::default()
+        }
+    }
+
+    /// displacement between two neighbouring columns
+    pub fn col_pitch(&self) -> Point {
+        let cols = (self.cols as i32).max(1);
+        (
+            (self.col_point.0 - self.origin.0) / cols,
+            (self.col_point.1 - self.origin.1) / cols,
+        )
+    }
+
+    /// displacement between two neighbouring rows
+    pub fn row_pitch(&self) -> Point {
+        let rows = (self.rows as i32).max(1);
+        (
+            (self.row_point.0 - self.origin.0) / rows,
+            (self.row_point.1 - self.origin.1) / rows,
+        )
+    }
+}
+
+/// Text label.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Text {
+    pub elflags: Option<i16>,
+    pub plex: Option<i32>,
+    pub layer: i16,
+    pub texttype: i16,
+    pub presentation: Option<i16>,
+    pub pathtype: Option<i16>,
+    pub width: Option<i32>,
+    pub strans: Option<Strans>,
+    pub origin: Point,
+    pub string: String,
+    pub props: Vec<Property>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub extra: Vec<Tuctosin>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub raw: Vec<(usize, RawRecord)>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub order: Vec<[u8; 2]>,
+    /// read from, and written back as, the obsolete TEXTNODE header
+    pub textnode: bool,
+}
+
+/// Electrical net node.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Node {
+    pub elflags: Option<i16>,
+    pub plex: Option<i32>,
+    pub layer: i16,
+    pub nodetype: i16,
+    pub points: Vec<Point>,
+    pub props: Vec<Property>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub extra: Vec<Tuctosin>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub raw: Vec<(usize, RawRecord)>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub order: Vec<[u8; 2]>,
+}
+
+/// BOX element, five points describing a closed rectangle outline.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct GdsBox {
+    pub elflags: Option<i16>,
+    pub plex: Option<i32>,
+    pub layer: i16,
+    pub boxtype: i16,
+    pub points: Vec<Point>,
+    pub props: Vec<Property>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub extra: Vec<Tuctosin>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub raw: Vec<(usize, RawRecord)>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub order: Vec<[u8; 2]>,
+}
@@ -1,26 +1,191 @@
-use std::collections::HashMap;
-
-// assume there is single module in the gds2 file
-#[derive(Default)]
-pub struct GDSIIModel {
-    pub header: HashMap<String, FileHeader>,
-    pub structure_time: [i16; 12],
-    pub structure_name: String,
-    pub s_boundary: Vec<Tuctosin>,
-    pub s_path: Vec<Tuctosin>,
-    pub s_sref: Vec<Tuctosin>,
-    pub s_aref: Vec<Tuctosin>,
-    pub s_text: Vec<Tuctosin>,
-    pub s_node: Vec<Tuctosin>,
-    pub s_box: Vec<Tuctosin>,
+mod element;
+mod real8;
+mod record;
+mod strans;
+
+pub use element::*;
+pub use real8::Real8;
+pub use record::record_name;
+pub use strans::Strans;
+
+/// A GDSII library: the file level header records and every structure
+/// (cell) of the stream, kept in file order.
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Library {
+    /// library records from HEADER to UNITS in file order
+    pub header: Vec<FileHeader>,
+    pub structures: Vec<Structure>,
+    /// unknown records outside any structure, each with the index of the
+    /// header record or structure it precedes (header records first, then
+    /// structures)
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub raw: Vec<(usize, RawRecord)>,
+    /// bytes after ENDLIB, usually zero padding up to a 2048-byte tape block
+    pub tail: Vec<u8>,
 }
 
-impl GDSIIModel {
-    pub fn summerize(&self) {
-        unimplemented!()
+/// Former name of [`Library`], kept so existing callers keep compiling.
+pub type GDSIIModel = Library;
+
+impl Library {
+    /// Find a structure by its STRNAME.
+    pub fn structure(&self, name: &str) -> Option<&Structure> {
+        self.structures.iter().find(|s| s.name == name)
+    }
+
+    pub fn structure_mut(&mut self, name: &str) -> Option<&mut Structure> {
+        self.structures.iter_mut().find(|s| s.name == name)
+    }
+
+    /// Structure names in file order.
+    pub fn structure_names(&self) -> impl Iterator<Item = &str> {
+        self.structures.iter().map(|s| s.name.as_str())
+    }
+
+    /// First header record with the given [`FileHeader::get_tag`] tag.
+    pub fn header_record(&self, tag: &str) -> Option<&FileHeader> {
+        self.header.iter().find(|h| h.get_tag() == tag)
+    }
+
+    /// UNITS record as (user units per database unit, meters per database unit)
+    pub fn units(&self) -> Option<(f64, f64)> {
+        match self.header_record("unit") {
+            Some(FileHeader::Units([user, meters])) => Some((user.to_f64(), meters.to_f64())),
+            _ => None,
+        }
     }
 }
 
+/// A single structure (cell) delimited by BGNSTR/ENDSTR.
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Structure {
+    pub name: String,
+    pub created: Timestamp,
+    pub modified: Timestamp,
+    pub strclass: Option<i16>,
+    pub strtype: Option<i16>,
+    /// elements of every kind in file order
+    pub elements: Vec<Element>,
+    /// unknown records between elements, each with the index of the element
+    /// it precedes
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub raw: Vec<(usize, RawRecord)>,
+}
+
+impl Structure {
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        Structure {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    /// BGNSTR payload: creation time followed by modification time.
+    pub fn bgn_str(&self) -> [i16; 12] {
+        let mut d = [0i16; 12];
+        d[..6].copy_from_slice(&self.created.to_array());
+        d[6..].copy_from_slice(&self.modified.to_array());
+        d
+    }
+
+    /// Remove the element at `index`; unknown records placed before it
+    /// move to the element after it.
+    pub fn remove_element(&mut self, index: usize) -> Element {
+        for (at, _) in &mut self.raw {
+            if *at > index {
+                *at -= 1;
+            }
+        }
+        self.elements.remove(index)
+    }
+
+    /// Keep only the elements `keep` returns true for, called once per
+    /// element in order; unknown records move as with
+    /// [`Structure::remove_element`].
+    pub fn retain_elements<F: FnMut(&Element) -> bool>(&mut self, mut keep: F) {
+        // elements kept before each index
+        let mut before = Vec::with_capacity(self.elements.len() + 1);
+        let mut kept = 0;
+        for element in &self.elements {
+            before.push(kept);
+            kept += keep(element) as usize;
+        }
+        before.push(kept);
+        let dropped = self.elements.len() - kept;
+        for (at, _) in &mut self.raw {
+            *at = before.get(*at).copied().unwrap_or(*at - dropped);
+        }
+        let mut i = 0;
+        self.elements.retain(|_| {
+            i += 1;
+            before[i] > before[i - 1]
+        });
+    }
+}
+
+/// Date and time as stored in BGNLIB/BGNSTR (year, month, day, hour, minute, second)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Timestamp {
+    pub year: i16,
+    pub month: i16,
+    pub day: i16,
+    pub hour: i16,
+    pub minute: i16,
+    pub second: i16,
+}
+
+impl Timestamp {
+    pub fn from_array(d: [i16; 6]) -> Self {
+        Timestamp {
+            year: d[0],
+            month: d[1],
+            day: d[2],
+            hour: d[3],
+            minute: d[4],
+            second: d[5],
+        }
+    }
+
+    pub fn to_array(self) -> [i16; 6] {
+        [
+            self.year,
+            self.month,
+            self.day,
+            self.hour,
+            self.minute,
+            self.second,
+        ]
+    }
+
+    /// split a 12 word BGNLIB/BGNSTR payload into its two timestamps
+    pub fn pair(d: [i16; 12]) -> (Self, Self) {
+        let mut first = [0i16; 6];
+        let mut second = [0i16; 6];
+        first.copy_from_slice(&d[..6]);
+        second.copy_from_slice(&d[6..]);
+        (Timestamp::from_array(first), Timestamp::from_array(second))
+    }
+}
+
+/// A record outside the Stream Format table, kept verbatim.
+///
+/// Only produced when parsing with [`ParseOptions::keep_unknown`];
+/// `bytes` is the record data without the 4-byte record header.
+///
+/// [`ParseOptions::keep_unknown`]: crate::ParseOptions::keep_unknown
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RawRecord {
+    pub rtype: u8,
+    pub dtype: u8,
+    pub bytes: Vec<u8>,
+}
+
 pub enum GDSIIVariant {
     FileHeader(FileHeader),
     ModuleHeader(ModuleHeader),
@@ -29,23 +194,39 @@ pub enum GDSIIVariant {
     TuctosinEnd,
     ModuleEnd,
     FileEnd,
+    Raw(RawRecord),
     Eof, // may include EOF tag in the file
 }
 
 /// File header variant in GDSII
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(tag = "record", content = "value", rename_all = "UPPERCASE")
+)]
+// keeps the record codes aligned past the serde renames
+#[rustfmt::skip]
 pub enum FileHeader {
-    Header(i16),       // 0x00_02
-    BgnLib([i16; 12]), // 0x01_02
-    LibName(String),   // 0x02_06
-    RefLibs(String),   // 0x1F_06
-    Fonts(String),     // 0x20_06
-    AttrTable(String), // 0x23_06
-    Generations(i16),  // 0x22_02
-    Format(i16),       // 0x36_02
-    Mask(String),      // 0x37_06
-    EndMask,           // 0x38_00
-    Units([f64; 2]),   // 0x03_05
+    Header(i16),                    // 0x00_02
+    BgnLib([i16; 12]),              // 0x01_02
+    LibDirSize(i16),                // 0x39_02
+    SrfName(String),                // 0x3A_06
+    LibSecur(Vec<(i16, i16, i16)>), // 0x3B_02 (group, user, access rights)
+    LibName(String),                // 0x02_06
+    RefLibs(Vec<String>),           // 0x1F_06 (44 byte fields)
+    Fonts(Vec<String>),             // 0x20_06 (44 byte fields)
+    AttrTable(String),              // 0x23_06
+    StypTable(String),              // 0x24_06 (unreleased)
+    Generations(i16),               // 0x22_02
+    Format(i16),                    // 0x36_02
+    Mask(String),                   // 0x37_06
+    #[cfg_attr(feature = "serde", serde(rename = "ENDMASKS"))]
+    EndMask,                        // 0x38_00
+    Units([Real8; 2]),              // 0x03_05
+    TapeNum(i16),                   // 0x32_02
+    TapeCode([i16; 6]),             // 0x33_02
+    Reserved(Vec<i32>),             // 0x35_03
 }
 
 impl FileHeader {
@@ -53,15 +234,22 @@ impl FileHeader {
         let tag_str = match self {
             FileHeader::Header(_) => "head",
             FileHeader::BgnLib(_) => "bgn",
+            FileHeader::LibDirSize(_) => "libdirsize",
+            FileHeader::SrfName(_) => "srfname",
+            FileHeader::LibSecur(_) => "libsecur",
             FileHeader::LibName(_) => "libname",
             FileHeader::RefLibs(_) => "reflib",
             FileHeader::Fonts(_) => "font",
             FileHeader::AttrTable(_) => "attr",
+            FileHeader::StypTable(_) => "styptable",
             FileHeader::Generations(_) => "generation",
             FileHeader::Format(_) => "format",
             FileHeader::Mask(_) => "mask",
             FileHeader::EndMask => "endmask",
             FileHeader::Units(_) => "unit",
+            FileHeader::TapeNum(_) => "tapenum",
+            FileHeader::TapeCode(_) => "tapecode",
+            FileHeader::Reserved(_) => "reserved",
         };
         tag_str.to_string()
     }
@@ -71,11 +259,16 @@ impl FileHeader {
 pub enum ModuleHeader {
     BgnStr([i16; 12]), // 0x05_02
     StrName(String),   // 0x06_06
+    StrClass(i16),     // 0x34_01
+    StrType(i16),      // 0x25_02 (unreleased)
 }
 
 /// shape header in GDSII
-#[derive(PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "UPPERCASE"))]
 pub enum TuctosinHeader {
+    #[default]
     Boundary, // 0x08_00
     Path,     // 0x09_00
     Sref,     // 0x0A_00
@@ -83,15 +276,16 @@ pub enum TuctosinHeader {
     Text,     // 0x0C_00
     Node,     // 0x15_00
     Box,      // 0x2D_00
+    TextNode, // 0x14_00 (obsolete, read as TEXT)
 }
 
-impl Default for TuctosinHeader {
-    fn default() -> Self {
-        TuctosinHeader::Boundary
-    }
-}
-
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(tag = "record", content = "value", rename_all = "UPPERCASE")
+)]
+#[rustfmt::skip]
 pub enum Tuctosin {
     ElfFlags(i16),       // 0x26_01
     Plex(i32),           // 0x2F_03
@@ -102,12 +296,61 @@ pub enum Tuctosin {
     Width(i32),          // 0x0F_03
     Sname(String),       // 0x12_06
     Strans(i16),         // 0x1A_01
-    Mag(i64),            // 0x1B_05
-    Angle(i64),          // 0x1C_05
+    Mag(Real8),          // 0x1B_05
+    Angle(Real8),        // 0x1C_05
     ColRow((i16, i16)),  // 0x13_02
     TextType(i16),       // 0x16_02
+    #[cfg_attr(feature = "serde", serde(rename = "PRESENTATION"))]
     Persentation(i16),   // 0x17_01
+    #[cfg_attr(feature = "serde", serde(rename = "STRING"))]
     AsciiString(String), // 0x19_06
     NodeType(i16),       // 0x2A_02
     BoxType(i16),        // 0x2E_02
+    BgnExtn(i32),        // 0x30_03
+    EndExtn(i32),        // 0x31_03
+    PropAttr(i16),       // 0x2B_02
+    PropValue(String),   // 0x2C_06
+    // obsolete and unreleased element records
+    Spacing(i16),       // 0x18_02
+    UInteger(i32),      // 0x1D_03
+    UString(String),    // 0x1E_06
+    ElKey(i32),         // 0x27_03
+    LinkType(i16),      // 0x28_02
+    LinkKeys(Vec<i32>), // 0x29_03
+    // extension markers without data
+    Border,         // 0x3C_00
+    SoftFence,      // 0x3D_00
+    HardFence,      // 0x3E_00
+    SoftWire,       // 0x3F_00
+    HardWire,       // 0x40_00
+    PathPort,       // 0x41_00
+    NodePort,       // 0x42_00
+    UserConstraint, // 0x43_00
+    SpacerError,    // 0x44_00
+    Contact,        // 0x45_00
+}
+
+impl Tuctosin {
+    /// obsolete, unreleased and extension records kept in `Element` extras
+    pub fn is_extra(&self) -> bool {
+        matches!(
+            self,
+            Tuctosin::Spacing(_)
+                | Tuctosin::UInteger(_)
+                | Tuctosin::UString(_)
+                | Tuctosin::ElKey(_)
+                | Tuctosin::LinkType(_)
+                | Tuctosin::LinkKeys(_)
+                | Tuctosin::Border
+                | Tuctosin::SoftFence
+                | Tuctosin::HardFence
+                | Tuctosin::SoftWire
+                | Tuctosin::HardWire
+                | Tuctosin::PathPort
+                | Tuctosin::NodePort
+                | Tuctosin::UserConstraint
+                | Tuctosin::SpacerError
+                | Tuctosin::Contact
+        )
+    }
 }
@@ -0,0 +1,183 @@
+/// GDSII 8-byte real.
+///
+/// Stream Format stores reals in the IBM excess-64 layout: one sign bit, a
+/// 7-bit base-16 exponent biased by 64 and a 56-bit mantissa with the binary
+/// point in front of it, `value = ±mantissa / 2^56 * 16^(exponent - 64)`.
+/// The raw bits are kept so that a value read from a file is written back
+/// unchanged even when it carries more precision than an `f64`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Real8(u64);
+
+const SIGN_BIT: u64 = 1 << 63;
+const MANTISSA_BITS: u64 = 0x00FF_FFFF_FFFF_FFFF;
+const MIN_EXP: i32 = -64;
+const MAX_EXP: i32 = 63;
+
+impl Real8 {
+    pub const ZERO: Real8 = Real8(0);
+
+    pub fn from_bits(bits: u64) -> Self {
+        Real8(bits)
+    }
+
+    pub fn to_bits(self) -> u64 {
+        self.0
+    }
+
+    pub fn from_be_bytes(bytes: [u8; 8]) -> Self {
+        Real8(u64::from_be_bytes(bytes))
+    }
+
+    pub fn to_be_bytes(self) -> [u8; 8] {
+        self.0.to_be_bytes()
+    }
+
+    /// Decode to the nearest `f64`.
+    pub fn to_f64(self) -> f64 {
+        let mantissa = self.0 & MANTISSA_BITS;
+        if mantissa == 0 {
+            return 0.0;
+        }
+        let exp = ((self.0 >> 56) & 0x7F) as i32 - 64;
+        // a single rounding happens in the integer conversion, the scale is
+        // an exact power of two well inside the f64 range
+        let value = mantissa as f64 * pow2(4 * exp - 56);
+        if self.0 & SIGN_BIT != 0 {
+            -value
+        } else {
+            value
+        }
+    }
+
+    /// Encode an `f64`, rounding to nearest (ties to even) when the value is
+    /// too small for a full mantissa. Out of range magnitudes saturate to the
+    /// largest real, NaN encodes as zero.
+    pub fn from_f64(v: f64) -> Self {
+        if v == 0.0 || v.is_nan() {
+            return Real8::ZERO;
+        }
+        let sign = if v.is_sign_negative() { SIGN_BIT } else { 0 };
+        if v.is_infinite() {
+            return Real8(sign | (0x7F << 56) | MANTISSA_BITS);
+        }
+
+        let bits = v.abs().to_bits();
+        let biased = (bits >> 52) as i32;
+        let fraction = bits & ((1 << 52) - 1);
+        // value = m * 2^(e2 - 52) with m holding 53 significant bits
+        let (m, e2) = if biased == 0 {
+            let shift = fraction.leading_zeros() as i32 - 11;
+            (fraction << shift, -1022 - shift)
+        } else {
+            (fraction | (1 << 52), biased - 1023)
+        };
+
+        // pick the exponent with value < 16^exp, the mantissa then starts
+        // with a non zero hex digit and the shift is between 0 and 3 bits
+        let mut exp = e2.div_euclid(4) + 1;
+        let mut mantissa = m << (e2 - 4 * exp + 4);
+        if exp > MAX_EXP {
+            return Real8(sign | (0x7F << 56) | MANTISSA_BITS);
+        }
+        if exp < MIN_EXP {
+            let shift = (4 * (MIN_EXP - exp)) as u32;
+            mantissa = round_shift(mantissa, shift);
+            exp = MIN_EXP;
+            if mantissa == 0 {
+                return Real8(sign);
+            }
+            if mantissa > MANTISSA_BITS {
+                mantissa >>= 4;
+                exp += 1;
+            }
+        }
+        Real8(sign | (((exp + 64) as u64) << 56) | mantissa)
+    }
+}
+
+// 2^k for k inside the normal f64 exponent range
+fn pow2(k: i32) -> f64 {
+    f64::from_bits(((k + 1023) as u64) << 52)
+}
+
+// shift right by `shift` bits rounding half to even
+fn round_shift(m: u64, shift: u32) -> u64 {
+    if shift >= 64 {
+        return 0;
+    }
+    let kept = m >> shift;
+    let rest = m & ((1u64 << shift) - 1);
+    let half = 1u64 << (shift - 1);
+    if rest > half || (rest == half && kept & 1 == 1) {
+        kept + 1
+    } else {
+        kept
+    }
+}
+
+impl From<f64> for Real8 {
+    fn from(v: f64) -> Self {
+        Real8::from_f64(v)
+    }
+}
+
+impl From<Real8> for f64 {
+    fn from(v: Real8) -> Self {
+        v.to_f64()
+    }
+}
+
+impl std::fmt::Display for Real8 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_f64())
+    }
+}
+
+/// A number when the `f64` value encodes back to the same bits, otherwise
+/// the bits as a `"0x..."` string.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Real8 {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if Real8::from_f64(self.to_f64()) == *self {
+            serializer.serialize_f64(self.to_f64())
+        } else {
+            serializer.serialize_str(&format!("0x{:016X}", self.0))
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Real8 {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl serde::de::Visitor<'_> for Visitor {
+            type Value = Real8;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a number or a \"0x...\" string of 16 hex digits")
+            }
+
+            fn visit_f64<E: serde::de::Error>(self, v: f64) -> Result<Real8, E> {
+                Ok(Real8::from_f64(v))
+            }
+
+            fn visit_i64<E: serde::de::Error>(self, v: i64) -> Result<Real8, E> {
+                Ok(Real8::from_f64(v as f64))
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, v: u64) -> Result<Real8, E> {
+                Ok(Real8::from_f64(v as f64))
+            }
+
+            fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Real8, E> {
+                v.strip_prefix("0x")
+                    .and_then(|bits| u64::from_str_radix(bits, 16).ok())
+                    .map(Real8)
+                    .ok_or_else(|| E::invalid_value(serde::de::Unexpected::Str(v), &self))
+            }
+        }
+
+        deserializer.deserialize_any(Visitor)
+    }
+}
@@ -0,0 +1,188 @@
+use super::{FileHeader, ModuleHeader, Tuctosin, TuctosinHeader};
+
+/// Stream Format record names, indexed by record type
+const RECORD_NAMES: [&str; 0x46] = [
+    "HEADER",         // 0x00
+    "BGNLIB",         // 0x01
+    "LIBNAME",        // 0x02
+    "UNITS",          // 0x03
+    "ENDLIB",         // 0x04
+    "BGNSTR",         // 0x05
+    "STRNAME",        // 0x06
+    "ENDSTR",         // 0x07
+    "BOUNDARY",       // 0x08
+    "PATH",           // 0x09
+    "SREF",           // 0x0A
+    "AREF",           // 0x0B
+    "TEXT",           // 0x0C
+    "LAYER",          // 0x0D
+    "DATATYPE",       // 0x0E
+    "WIDTH",          // 0x0F
+    "XY",             // 0x10
+    "ENDEL",          // 0x11
+    "SNAME",          // 0x12
+    "COLROW",         // 0x13
+    "TEXTNODE",       // 0x14
+    "NODE",           // 0x15
+    "TEXTTYPE",       // 0x16
+    "PRESENTATION",   // 0x17
+    "SPACING",        // 0x18
+    "STRING",         // 0x19
+    "STRANS",         // 0x1A
+    "MAG",            // 0x1B
+    "ANGLE",          // 0x1C
+    "UINTEGER",       // 0x1D
+    "USTRING",        // 0x1E
+    "REFLIBS",        // 0x1F
+    "FONTS",          // 0x20
+    "PATHTYPE",       // 0x21
+    "GENERATIONS",    // 0x22
+    "ATTRTABLE",      // 0x23
+    "STYPTABLE",      // 0x24
+    "STRTYPE",        // 0x25
+    "ELFLAGS",        // 0x26
+    "ELKEY",          // 0x27
+    "LINKTYPE",       // 0x28
+    "LINKKEYS",       // 0x29
+    "NODETYPE",       // 0x2A
+    "PROPATTR",       // 0x2B
+    "PROPVALUE",      // 0x2C
+    "BOX",            // 0x2D
+    "BOXTYPE",        // 0x2E
+    "PLEX",           // 0x2F
+    "BGNEXTN",        // 0x30
+    "ENDEXTN",        // 0x31
+    "TAPENUM",        // 0x32
+    "TAPECODE",       // 0x33
+    "STRCLASS",       // 0x34
+    "RESERVED",       // 0x35
+    "FORMAT",         // 0x36
+    "MASK",           // 0x37
+    "ENDMASKS",       // 0x38
+    "LIBDIRSIZE",     // 0x39
+    "SRFNAME",        // 0x3A
+    "LIBSECUR",       // 0x3B
+    "BORDER",         // 0x3C
+    "SOFTFENCE",      // 0x3D
+    "HARDFENCE",      // 0x3E
+    "SOFTWIRE",       // 0x3F
+    "HARDWIRE",       // 0x40
+    "PATHPORT",       // 0x41
+    "NODEPORT",       // 0x42
+    "USERCONSTRAINT", // 0x43
+    "SPACERERROR",    // 0x44
+    "CONTACT",        // 0x45
+];
+
+/// Name of a record type as used in the Stream Format documentation,
+/// `"UNKNOWN"` for types outside the table.
+pub fn record_name(rtype: u8) -> &'static str {
+    RECORD_NAMES
+        .get(rtype as usize)
+        .copied()
+        .unwrap_or("UNKNOWN")
+}
+
+impl FileHeader {
+    /// record type and data type of the record
+    pub fn record_type(&self) -> [u8; 2] {
+        match self {
+            FileHeader::Header(_) => [0x00, 0x02],
+            FileHeader::BgnLib(_) => [0x01, 0x02],
+            FileHeader::LibDirSize(_) => [0x39, 0x02],
+            FileHeader::SrfName(_) => [0x3A, 0x06],
+            FileHeader::LibSecur(_) => [0x3B, 0x02],
+            FileHeader::LibName(_) => [0x02, 0x06],
+            FileHeader::RefLibs(_) => [0x1F, 0x06],
+            FileHeader::Fonts(_) => [0x20, 0x06],
+            FileHeader::AttrTable(_) => [0x23, 0x06],
+            FileHeader::StypTable(_) => [0x24, 0x06],
+            FileHeader::Generations(_) => [0x22, 0x02],
+            FileHeader::Format(_) => [0x36, 0x02],
+            FileHeader::Mask(_) => [0x37, 0x06],
+            FileHeader::EndMask => [0x38, 0x00],
+            FileHeader::Units(_) => [0x03, 0x05],
+            FileHeader::TapeNum(_) => [0x32, 0x02],
+            FileHeader::TapeCode(_) => [0x33, 0x02],
+            FileHeader::Reserved(_) => [0x35, 0x03],
+        }
+    }
+}
+
+impl ModuleHeader {
+    pub fn record_type(&self) -> [u8; 2] {
+        match self {
+            ModuleHeader::BgnStr(_) => [0x05, 0x02],
+            ModuleHeader::StrName(_) => [0x06, 0x06],
+            ModuleHeader::StrClass(_) => [0x34, 0x01],
+            ModuleHeader::StrType(_) => [0x25, 0x02],
+        }
+    }
+}
+
+impl TuctosinHeader {
+    pub fn record_type(&self) -> [u8; 2] {
+        match self {
+            TuctosinHeader::Boundary => [0x08, 0x00],
+            TuctosinHeader::Path => [0x09, 0x00],
+            TuctosinHeader::Sref => [0x0A, 0x00],
+            TuctosinHeader::Aref => [0x0B, 0x00],
+            TuctosinHeader::Text => [0x0C, 0x00],
+            TuctosinHeader::Node => [0x15, 0x00],
+            TuctosinHeader::Box => [0x2D, 0x00],
+            TuctosinHeader::TextNode => [0x14, 0x00],
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        record_name(self.record_type()[0])
+    }
+}
+
+impl Tuctosin {
+    pub fn record_type(&self) -> [u8; 2] {
+        match self {
+            Tuctosin::ElfFlags(_) => [0x26, 0x01],
+            Tuctosin::Plex(_) => [0x2F, 0x03],
+            Tuctosin::Layer(_) => [0x0D, 0x02],
+            Tuctosin::DataType(_) => [0x0E, 0x02],
+            Tuctosin::Xy(_) => [0x10, 0x03],
+            Tuctosin::PathType(_) => [0x21, 0x02],
+            Tuctosin::Width(_) => [0x0F, 0x03],
+            Tuctosin::Sname(_) => [0x12, 0x06],
+            Tuctosin::Strans(_) => [0x1A, 0x01],
+            Tuctosin::Mag(_) => [0x1B, 0x05],
+            Tuctosin::Angle(_) => [0x1C, 0x05],
+            Tuctosin::ColRow(_) => [0x13, 0x02],
+            Tuctosin::TextType(_) => [0x16, 0x02],
+            Tuctosin::Persentation(_) => [0x17, 0x01],
+            Tuctosin::AsciiString(_) => [0x19, 0x06],
+            Tuctosin::NodeType(_) => [0x2A, 0x02],
+            Tuctosin::BoxType(_) => [0x2E, 0x02],
+            Tuctosin::BgnExtn(_) => [0x30, 0x03],
+            Tuctosin::EndExtn(_) => [0x31, 0x03],
+            Tuctosin::PropAttr(_) => [0x2B, 0x02],
+            Tuctosin::PropValue(_) => [0x2C, 0x06],
+            Tuctosin::Spacing(_) => [0x18, 0x02],
+            Tuctosin::UInteger(_) => [0x1D, 0x03],
+            Tuctosin::UString(_) => [0x1E, 0x06],
+            Tuctosin::ElKey(_) => [0x27, 0x03],
+            Tuctosin::LinkType(_) => [0x28, 0x02],
+            Tuctosin::LinkKeys(_) => [0x29, 0x03],
+            Tuctosin::Border => [0x3C, 0x00],
+            Tuctosin::SoftFence => [0x3D, 0x00],
+            Tuctosin::HardFence => [0x3E, 0x00],
+            Tuctosin::SoftWire => [0x3F, 0x00],
+            Tuctosin::HardWire => [0x40, 0x00],
+            Tuctosin::PathPort => [0x41, 0x00],
+            Tuctosin::NodePort => [0x42, 0x00],
+            Tuctosin::UserConstraint => [0x43, 0x00],
+            Tuctosin::SpacerError => [0x44, 0x00],
+            Tuctosin::Contact => [0x45, 0x00],
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        record_name(self.record_type()[0])
+    }
+}
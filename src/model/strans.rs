@@ -0,0 +1,68 @@
+use super::Real8;
+
+/// STRANS record of an SREF, AREF or TEXT together with the MAG and ANGLE
+/// records that may only follow it.
+///
+/// The placed structure or text is first mirrored about the x axis when
+/// `reflect_x` is set, then scaled by the magnification and finally rotated
+/// counterclockwise by the angle.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Strans {
+    pub reflect_x: bool,
+    /// magnification does not combine with the one of the parent reference
+    pub abs_mag: bool,
+    /// angle does not combine with the one of the parent reference
+    pub abs_angle: bool,
+    /// MAG record, a magnification of 1 when absent
+    pub mag: Option<Real8>,
+    /// ANGLE record in degrees, no rotation when absent
+    pub angle: Option<Real8>,
+    /// bits of the record other than the three flags, kept so that they are
+    /// written back unchanged
+    pub reserved: u16,
+}
+
+const REFLECT_X: u16 = 0x8000;
+const ABS_MAG: u16 = 0x0004;
+const ABS_ANGLE: u16 = 0x0002;
+
+impl Strans {
+    /// Decode the STRANS bit field, MAG and ANGLE are left unset.
+    pub fn from_bits(bits: i16) -> Self {
+        let bits = bits as u16;
+        Strans {
+            reflect_x: bits & REFLECT_X != 0,
+            abs_mag: bits & ABS_MAG != 0,
+            abs_angle: bits & ABS_ANGLE != 0,
+            mag: None,
+            angle: None,
+            reserved: bits & !(REFLECT_X | ABS_MAG | ABS_ANGLE),
+        }
+    }
+
+    /// Encode the STRANS bit field.
+    pub fn to_bits(&self) -> i16 {
+        let mut bits = self.reserved & !(REFLECT_X | ABS_MAG | ABS_ANGLE);
+        if self.reflect_x {
+            bits |= REFLECT_X;
+        }
+        if self.abs_mag {
+            bits |= ABS_MAG;
+        }
+        if self.abs_angle {
+            bits |= ABS_ANGLE;
+        }
+        bits as i16
+    }
+
+    pub fn magnification(&self) -> f64 {
+        self.mag.map_or(1.0, Real8::to_f64)
+    }
+
+    /// counterclockwise rotation in degrees
+    pub fn angle_degrees(&self) -> f64 {
+        self.angle.map_or(0.0, Real8::to_f64)
+    }
+}
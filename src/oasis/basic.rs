@@ -0,0 +1,88 @@
+// OASIS primitive types, SEMI P39 section 7
+
+pub(super) const MAGIC: &[u8] = b"%SEMI-OASIS\r\n";
+
+pub(super) fn append_unsigned(data: &mut Vec<u8>, mut v: u64) {
+    loop {
+        let byte = (v & 0x7F) as u8;
+        v >>= 7;
+        if v == 0 {
+            data.push(byte);
+            return;
+        }
+        data.push(byte | 0x80);
+    }
+}
+
+pub(super) fn append_signed(data: &mut Vec<u8>, v: i64) {
+    append_unsigned(data, (v.unsigned_abs() << 1) | (v < 0) as u64);
+}
+
+// integers and reciprocals exactly, anything else as an IEEE double
+pub(super) fn append_real(data: &mut Vec<u8>, v: f64) {
+    let exact = |v: f64| v.fract() == 0.0 && v.abs() < (1u64 << 53) as f64;
+    if exact(v) {
+        append_unsigned(data, (v < 0.0) as u64);
+        append_unsigned(data, v.abs() as u64);
+    } else if v != 0.0 && exact(1.0 / v) {
+        append_unsigned(data, 2 + (v < 0.0) as u64);
+        append_unsigned(data, (1.0 / v).abs() as u64);
+    } else {
+        append_unsigned(data, 7);
+        data.extend(v.to_le_bytes());
+    }
+}
+
+pub(super) fn append_string(data: &mut Vec<u8>, s: &[u8]) {
+    append_unsigned(data, s.len() as u64);
+    data.extend(s);
+}
+
+// direction code of an octangular displacement with its magnitude
+fn octangular(dx: i64, dy: i64) -> Option<(u64, u64)> {
+    let (ax, ay) = (dx.unsigned_abs(), dy.unsigned_abs());
+    match (dx.signum(), dy.signum()) {
+        (_, 0) if dx >= 0 => Some((0, ax)),
+        (0, 1) => Some((1, ay)),
+        (-1, 0) => Some((2, ax)),
+        (0, -1) => Some((3, ay)),
+        _ if ax != ay => None,
+        (1, 1) => Some((4, ax)),
+        (-1, 1) => Some((5, ax)),
+        (-1, -1) => Some((6, ax)),
+        _ => Some((7, ax)),
+    }
+}
+
+pub(super) fn append_g_delta(data: &mut Vec<u8>, (dx, dy): (i64, i64)) {
+    match octangular(dx, dy) {
+        Some((direction, magnitude)) => append_unsigned(data, (magnitude << 4) | (direction << 1)),
+        None => {
+            append_unsigned(data, (dx.unsigned_abs() << 2) | ((dx < 0) as u64) << 1 | 1);
+            append_signed(data, dy);
+        }
+    }
+}
+
+/// Point list of the displacements between consecutive `points`, 2-deltas
+/// when every step is horizontal or vertical, g-deltas otherwise.
+pub(super) fn append_point_list(data: &mut Vec<u8>, points: &[(i64, i64)]) {
+    let deltas: Vec<(i64, i64)> = points
+        .windows(2)
+        .map(|w| (w[1].0 - w[0].0, w[1].1 - w[0].1))
+        .collect();
+    if deltas.iter().all(|(dx, dy)| *dx == 0 || *dy == 0) {
+        append_unsigned(data, 2);
+        append_unsigned(data, deltas.len() as u64);
+        for (dx, dy) in deltas {
+            let (direction, magnitude) = octangular(dx, dy).unwrap();
+            append_unsigned(data, (magnitude << 2) | direction);
+        }
+    } else {
+        append_unsigned(data, 4);
+        append_unsigned(data, deltas.len() as u64);
+        for delta in deltas {
+            append_g_delta(data, delta);
+        }
+    }
+}
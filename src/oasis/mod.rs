@@ -0,0 +1,7 @@
+mod basic;
+mod reader;
+mod writer;
+
+pub(crate) use reader::oasis_parser;
+
+pub use writer::OasisOptions;
@@ -0,0 +1,1300 @@
+use super::basic::MAGIC;
+use super::writer::*;
+use crate::error::GDSIIErrorKind;
+use crate::model::*;
+use std::collections::HashMap;
+use std::ops::Range;
+
+// vertices of the polygon standing in for a CIRCLE
+const CIRCLE_VERTICES: usize = 64;
+
+// most elements one repeated record may expand to
+const MAX_INSTANCES: u64 = 1 << 20;
+
+// corners of each CTRAPEZOID type as (w, h, w, h) factors of x and y,
+// SEMI P39 table 14; triangles repeat their first corner
+const CTRAPEZOIDS: [[[i64; 4]; 4]; 26] = [
+    [[0, 0, 0, 0], [0, 0, 0, 1], [1, -1, 0, 1], [1, 0, 0, 0]],
+    [[0, 0, 0, 0], [0, 0, 0, 1], [1, 0, 0, 1], [1, -1, 0, 0]],
+    [[0, 0, 0, 0], [0, 1, 0, 1], [1, 0, 0, 1], [1, 0, 0, 0]],
+    [[0, 1, 0, 0], [0, 0, 0, 1], [1, 0, 0, 1], [1, 0, 0, 0]],
+    [[0, 0, 0, 0], [0, 1, 0, 1], [1, -1, 0, 1], [1, 0, 0, 0]],
+    [[0, 1, 0, 0], [0, 0, 0, 1], [1, 0, 0, 1], [1, -1, 0, 0]],
+    [[0, 0, 0, 0], [0, 1, 0, 1], [1, 0, 0, 1], [1, -1, 0, 0]],
+    [[0, 1, 0, 0], [0, 0, 0, 1], [1, -1, 0, 1], [1, 0, 0, 0]],
+    [[0, 0, 0, 0], [0, 0, 0, 1], [1, 0, -1, 1], [1, 0, 0, 0]],
+    [[0, 0, 0, 0], [0, 0, -1, 1], [1, 0, 0, 1], [1, 0, 0, 0]],
+    [[0, 0, 0, 0], [0, 0, 0, 1], [1, 0, 0, 1], [1, 0, 1, 0]],
+    [[0, 0, 1, 0], [0, 0, 0, 1], [1, 0, 0, 1], [1, 0, 0, 0]],
+    [[0, 0, 0, 0], [0, 0, 0, 1], [1, 0, -1, 1], [1, 0, 1, 0]],
+    [[0, 0, 1, 0], [0, 0, -1, 1], [1, 0, 0, 1], [1, 0, 0, 0]],
+    [[0, 0, 0, 0], [0, 0, -1, 1], [1, 0, 0, 1], [1, 0, 1, 0]],
+    [[0, 0, 1, 0], [0, 0, 0, 1], [1, 0, -1, 1], [1, 0, 0, 0]],
+    [[0, 0, 0, 0], [0, 0, 1, 0], [1, 0, 0, 0], [0, 0, 0, 0]],
+    [[0, 0, 0, 0], [0, 0, 1, 0], [1, 0, 1, 0], [0, 0, 0, 0]],
+    [[0, 0, 0, 0], [1, 0, 1, 0], [1, 0, 0, 0], [0, 0, 0, 0]],
+    [[0, 0, 1, 0], [1, 0, 1, 0], [1, 0, 0, 0], [0, 0, 1, 0]],
+    [[0, 0, 0, 0], [0, 1, 0, 1], [0, 2, 0, 0], [0, 0, 0, 0]],
+    [[0, 0, 0, 1], [0, 2, 0, 1], [0, 1, 0, 0], [0, 0, 0, 1]],
+    [[0, 0, 0, 0], [0, 0, 2, 0], [1, 0, 1, 0], [0, 0, 0, 0]],
+    [[1, 0, 0, 0], [0, 0, 1, 0], [1, 0, 2, 0], [1, 0, 0, 0]],
+    [[0, 0, 0, 0], [0, 0, 0, 1], [1, 0, 0, 1], [1, 0, 0, 0]],
+    [[0, 0, 0, 0], [0, 0, 1, 0], [1, 0, 1, 0], [1, 0, 0, 0]],
+];
+
+// a name given by reference number or in place
+#[derive(Debug, Clone, PartialEq)]
+enum NameRef {
+    Number(u64),
+    Name(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum PropValue {
+    Real(f64),
+    Unsigned(u64),
+    Signed(i64),
+    String(Vec<u8>),
+    PropString(u64),
+}
+
+// where the instances of a repeated record go
+#[derive(Debug, Clone, PartialEq)]
+enum Repetition {
+    // cols x rows lattice spanned by two steps
+    Grid {
+        cols: u64,
+        rows: u64,
+        col: (i64, i64),
+        row: (i64, i64),
+    },
+    // irregular, the first offset is (0, 0)
+    Offsets(Vec<(i64, i64)>),
+}
+
+#[derive(Default)]
+struct Modal {
+    relative: bool,
+    placement_cell: Option<NameRef>,
+    placement: (i64, i64),
+    layer: Option<u64>,
+    datatype: Option<u64>,
+    textlayer: Option<u64>,
+    texttype: Option<u64>,
+    text: (i64, i64),
+    text_string: Option<NameRef>,
+    geometry: (i64, i64),
+    geometry_w: Option<u64>,
+    geometry_h: Option<u64>,
+    polygon_points: Option<Vec<(i64, i64)>>,
+    path_halfwidth: Option<u64>,
+    path_points: Option<Vec<(i64, i64)>>,
+    path_extensions: (Option<i64>, Option<i64>),
+    ctrapezoid_type: Option<u64>,
+    circle_radius: Option<u64>,
+    repetition: Option<Repetition>,
+    property_name: Option<NameRef>,
+    property_values: Option<Vec<PropValue>>,
+}
+
+// bytes being read, the file itself or an inflated CBLOCK
+struct Input {
+    data: Vec<u8>,
+    pos: usize,
+    // file offset of the CBLOCK record, for errors inside it
+    cblock: Option<usize>,
+}
+
+// a property waiting for the name tables, on `elements` of `structure`
+struct PendingProperty {
+    structure: usize,
+    elements: Range<usize>,
+    name: NameRef,
+    values: Vec<PropValue>,
+    // offset of the PROPERTY record, for errors
+    offset: usize,
+}
+
+struct OasisReader {
+    inputs: Vec<Input>,
+    // offset of the record being read
+    record: usize,
+    modal: Modal,
+    cellnames: HashMap<u64, String>,
+    textstrings: HashMap<u64, String>,
+    propnames: HashMap<u64, String>,
+    propstrings: HashMap<u64, Vec<u8>>,
+    // next implicit reference number of each table
+    implicit: [u64; 4],
+    unit: f64,
+    structures: Vec<Structure>,
+    // cells named by reference number, resolved at the end, each with the
+    // offset of the record holding the number
+    structure_names: Vec<(usize, u64, usize)>,
+    sref_names: Vec<(usize, usize, u64, usize)>,
+    text_names: Vec<(usize, usize, u64, usize)>,
+    properties: Vec<PendingProperty>,
+    // elements the last element record made
+    last: Range<usize>,
+}
+
+fn to_i32(v: i64) -> Option<i32> {
+    i32::try_from(v).ok()
+}
+
+// `n` steps of `step`
+fn times(n: i64, step: (i64, i64)) -> Option<(i64, i64)> {
+    Some((n.checked_mul(step.0)?, n.checked_mul(step.1)?))
+}
+
+impl OasisReader {
+    fn error(&self, reason: impl Into<String>) -> GDSIIErrorKind {
+        GDSIIErrorKind::InvalidOasis {
+            offset: self.record,
+            reason: reason.into(),
+        }
+    }
+
+    fn overflow(&self) -> GDSIIErrorKind {
+        self.error("coordinate overflows")
+    }
+
+    fn int(&self, v: u64) -> Result<i64, GDSIIErrorKind> {
+        i64::try_from(v).map_err(|_| self.error(format!("{} is beyond the supported range", v)))
+    }
+
+    // a layer, datatype or text type
+    fn to_i16(&self, v: u64, name: &str) -> Result<i16, GDSIIErrorKind> {
+        i16::try_from(v)
+            .map_err(|_| self.error(format!("{} {} is beyond the GDSII range", name, v)))
+    }
+
+    fn add(&self, a: (i64, i64), b: (i64, i64)) -> Result<(i64, i64), GDSIIErrorKind> {
+        match (a.0.checked_add(b.0), a.1.checked_add(b.1)) {
+            (Some(x), Some(y)) => Ok((x, y)),
+            _ => Err(self.overflow()),
+        }
+    }
+
+    fn input(&mut self) -> &mut Input {
+        self.inputs.last_mut().unwrap()
+    }
+
+    fn byte(&mut self) -> Result<u8, GDSIIErrorKind> {
+        let input = self.input();
+        match input.data.get(input.pos) {
+            Some(b) => {
+                input.pos += 1;
+                Ok(*b)
+            }
+            None => Err(self.error("stream ends inside a record")),
+        }
+    }
+
+    fn bytes(&mut self, n: usize) -> Result<Vec<u8>, GDSIIErrorKind> {
+        let input = self.input();
+        match input.data.get(input.pos..input.pos.saturating_add(n)) {
+            Some(b) => {
+                let b = b.to_vec();
+                input.pos += n;
+                Ok(b)
+            }
+            None => Err(self.error("stream ends inside a record")),
+        }
+    }
+
+    fn unsigned(&mut self) -> Result<u64, GDSIIErrorKind> {
+        let mut v = 0u64;
+        let mut shift = 0;
+        loop {
+            let b = self.byte()?;
+            if shift < 64 {
+                v |= ((b & 0x7F) as u64) << shift;
+            }
+            if b & 0x80 == 0 {
+                return Ok(v);
+            }
+            shift += 7;
+        }
+    }
+
+    fn unsigned_int(&mut self) -> Result<i64, GDSIIErrorKind> {
+        let v = self.unsigned()?;
+        self.int(v)
+    }
+
+    fn signed(&mut self) -> Result<i64, GDSIIErrorKind> {
+        let v = self.unsigned()?;
+        let magnitude = (v >> 1) as i64;
+        Ok(if v & 1 == 1 { -magnitude } else { magnitude })
+    }
+
+    fn real(&mut self) -> Result<f64, GDSIIErrorKind> {
+        let kind = self.unsigned()?;
+        self.real_of(kind)
+    }
+
+    // a real after its type
+    fn real_of(&mut self, kind: u64) -> Result<f64, GDSIIErrorKind> {
+        Ok(match kind {
+            0 => self.unsigned()? as f64,
+            1 => -(self.unsigned()? as f64),
+            2 => 1.0 / self.unsigned()? as f64,
+            3 => -1.0 / self.unsigned()? as f64,
+            4 => self.unsigned()? as f64 / self.unsigned()? as f64,
+            5 => -(self.unsigned()? as f64) / self.unsigned()? as f64,
+            6 => f32::from_le_bytes(self.bytes(4)?.try_into().unwrap()) as f64,
+            7 => f64::from_le_bytes(self.bytes(8)?.try_into().unwrap()),
+            t => return Err(self.error(format!("unknown real type {}", t))),
+        })
+    }
+
+    fn string(&mut self) -> Result<Vec<u8>, GDSIIErrorKind> {
+        let len = self.unsigned()? as usize;
+        self.bytes(len)
+    }
+
+    fn text(&mut self) -> Result<String, GDSIIErrorKind> {
+        let s = self.string()?;
+        String::from_utf8(s).map_err(|_| self.error("name is not valid UTF-8"))
+    }
+
+    fn octangular(direction: u64, magnitude: i64) -> (i64, i64) {
+        match direction {
+            0 => (magnitude, 0),
+            1 => (0, magnitude),
+            2 => (-magnitude, 0),
+            3 => (0, -magnitude),
+            4 => (magnitude, magnitude),
+            5 => (-magnitude, magnitude),
+            6 => (-magnitude, -magnitude),
+            _ => (magnitude, -magnitude),
+        }
+    }
+
+    fn g_delta(&mut self) -> Result<(i64, i64), GDSIIErrorKind> {
+        let v = self.unsigned()?;
+        if v & 1 == 0 {
+            return Ok(Self::octangular((v >> 1) & 7, (v >> 4) as i64));
+        }
+        let x = (v >> 2) as i64;
+        let x = if v & 2 != 0 { -x } else { x };
+        Ok((x, self.signed()?))
+    }
+
+    // vertices after the first, relative to it; polygons of the manhattan
+    // types get their implied last vertex
+    fn point_list(&mut self, polygon: bool) -> Result<Vec<(i64, i64)>, GDSIIErrorKind> {
+        let kind = self.unsigned()?;
+        let count = self.unsigned()?;
+        let mut deltas = Vec::with_capacity(count.min(1 << 16) as usize);
+        let mut previous = (0, 0);
+        for i in 0..count {
+            let delta = match kind {
+                0 | 1 => {
+                    let d = self.signed()?;
+                    if (i % 2 == 0) == (kind == 0) {
+                        (d, 0)
+                    } else {
+                        (0, d)
+                    }
+                }
+                2 => {
+                    let v = self.unsigned()?;
+                    Self::octangular(v & 3, (v >> 2) as i64)
+                }
+                3 => {
+                    let v = self.unsigned()?;
+                    Self::octangular(v & 7, (v >> 3) as i64)
+                }
+                4 => self.g_delta()?,
+                5 => {
+                    let g = self.g_delta()?;
+                    previous = self.add(previous, g)?;
+                    previous
+                }
+                k => return Err(self.error(format!("unknown point list type {}", k))),
+            };
+            deltas.push(delta);
+        }
+        let mut points = Vec::with_capacity(deltas.len() + 1);
+        let mut p = (0, 0);
+        for (dx, dy) in deltas {
+            p = self.add(p, (dx, dy))?;
+            points.push(p);
+        }
+        if polygon && kind < 2 {
+            points.push(if kind == 0 { (0, p.1) } else { (p.0, 0) });
+        }
+        Ok(points)
+    }
+
+    // number of instances of a repetition, stored as n - 2
+    fn count(&mut self) -> Result<u64, GDSIIErrorKind> {
+        self.unsigned()?
+            .checked_add(2)
+            .ok_or_else(|| self.error("repetition count overflows"))
+    }
+
+    fn repetition(&mut self) -> Result<Repetition, GDSIIErrorKind> {
+        let kind = self.unsigned()?;
+        let repetition = match kind {
+            0 => {
+                return self
+                    .modal
+                    .repetition
+                    .clone()
+                    .ok_or_else(|| self.error("repetition reused before any was given"))
+            }
+            1 => {
+                let (cols, rows) = (self.count()?, self.count()?);
+                let (dx, dy) = (self.unsigned_int()?, self.unsigned_int()?);
+                Repetition::Grid {
+                    cols,
+                    rows,
+                    col: (dx, 0),
+                    row: (0, dy),
+                }
+            }
+            2 | 3 => {
+                let (n, d) = (self.count()?, self.unsigned_int()?);
+                if kind == 2 {
+                    Repetition::Grid {
+                        cols: n,
+                        rows: 1,
+                        col: (d, 0),
+                        row: (0, 0),
+                    }
+                } else {
+                    Repetition::Grid {
+                        cols: 1,
+                        rows: n,
+                        col: (0, 0),
+                        row: (0, d),
+                    }
+                }
+            }
+            4..=7 => {
+                let n = self.count()?;
+                let grid = if kind % 2 == 1 {
+                    self.unsigned_int()?
+                } else {
+                    1
+                };
+                let mut offsets = vec![(0, 0)];
+                let mut at = 0i64;
+                for _ in 1..n {
+                    at = self
+                        .unsigned_int()?
+                        .checked_mul(grid)
+                        .and_then(|s| at.checked_add(s))
+                        .ok_or_else(|| self.overflow())?;
+                    offsets.push(if kind >= 6 { (0, at) } else { (at, 0) });
+                }
+                Repetition::Offsets(offsets)
+            }
+            8 => {
+                let (cols, rows) = (self.count()?, self.count()?);
+                let (col, row) = (self.g_delta()?, self.g_delta()?);
+                Repetition::Grid {
+                    cols,
+                    rows,
+                    col,
+                    row,
+                }
+            }
+            9 => {
+                let n = self.count()?;
+                Repetition::Grid {
+                    cols: n,
+                    rows: 1,
+                    col: self.g_delta()?,
+                    row: (0, 0),
+                }
+            }
+            10 | 11 => {
+                let n = self.count()?;
+                let grid = if kind == 11 { self.unsigned_int()? } else { 1 };
+                let mut offsets = vec![(0, 0)];
+                let mut at = (0, 0);
+                for _ in 1..n {
+                    let d = self.g_delta()?;
+                    let d = times(grid, d).ok_or_else(|| self.overflow())?;
+                    at = self.add(at, d)?;
+                    offsets.push(at);
+                }
+                Repetition::Offsets(offsets)
+            }
+            k => return Err(self.error(format!("unknown repetition type {}", k))),
+        };
+        self.modal.repetition = Some(repetition.clone());
+        Ok(repetition)
+    }
+
+    // offset of each instance of a record, a single one without repetition
+    fn instances(&self, rep: Option<Repetition>) -> Result<Vec<(i64, i64)>, GDSIIErrorKind> {
+        let (cols, rows, col, row) = match rep {
+            None => return Ok(vec![(0, 0)]),
+            Some(Repetition::Offsets(offsets)) if offsets.len() as u64 <= MAX_INSTANCES => {
+                return Ok(offsets)
+            }
+            Some(Repetition::Offsets(offsets)) => (offsets.len() as u64, 1, (0, 0), (0, 0)),
+            Some(Repetition::Grid {
+                cols,
+                rows,
+                col,
+                row,
+            }) => (cols, rows, col, row),
+        };
+        let n = cols
+            .checked_mul(rows)
+            .filter(|n| *n <= MAX_INSTANCES)
+            .ok_or_else(|| {
+                self.error(format!(
+                    "repetition of {} x {} instances is too large to expand",
+                    cols, rows
+                ))
+            })?;
+        let mut offsets = Vec::with_capacity(n as usize);
+        for r in 0..rows as i64 {
+            for c in 0..cols as i64 {
+                let steps = times(c, col).zip(times(r, row));
+                let (a, b) = steps.ok_or_else(|| self.overflow())?;
+                offsets.push(self.add(a, b)?);
+            }
+        }
+        Ok(offsets)
+    }
+
+    // a modal variable the record relies on
+    fn modal<T: Clone>(&self, value: &Option<T>, name: &str) -> Result<T, GDSIIErrorKind> {
+        value
+            .clone()
+            .ok_or_else(|| self.error(format!("modal variable {} used before it was set", name)))
+    }
+
+    // x or y field: absolute, or relative to the modal value
+    fn coordinate(&mut self, present: bool, modal: i64) -> Result<i64, GDSIIErrorKind> {
+        if !present {
+            return Ok(modal);
+        }
+        let v = self.signed()?;
+        if self.modal.relative {
+            modal.checked_add(v).ok_or_else(|| self.overflow())
+        } else {
+            Ok(v)
+        }
+    }
+
+    fn name_ref(&mut self, number: bool) -> Result<NameRef, GDSIIErrorKind> {
+        Ok(if number {
+            NameRef::Number(self.unsigned()?)
+        } else {
+            NameRef::Name(self.text()?)
+        })
+    }
+
+    // a name table record, implicit or explicit numbering
+    fn table_entry(
+        &mut self,
+        table: usize,
+        explicit: bool,
+    ) -> Result<(u64, Vec<u8>), GDSIIErrorKind> {
+        let name = self.string()?;
+        let number = if explicit {
+            self.unsigned()?
+        } else {
+            self.implicit[table] += 1;
+            self.implicit[table] - 1
+        };
+        Ok((number, name))
+    }
+
+    fn structure(&mut self) -> Result<usize, GDSIIErrorKind> {
+        if self.structures.is_empty() {
+            return Err(self.error("element record outside a CELL"));
+        }
+        Ok(self.structures.len() - 1)
+    }
+
+    fn push(&mut self, elements: Vec<Element>) -> Result<(), GDSIIErrorKind> {
+        let s = self.structure()?;
+        let start = self.structures[s].elements.len();
+        self.structures[s].elements.extend(elements);
+        self.last = start..self.structures[s].elements.len();
+        Ok(())
+    }
+
+    fn point(&self, (x, y): (i64, i64)) -> Result<Point, GDSIIErrorKind> {
+        match (to_i32(x), to_i32(y)) {
+            (Some(x), Some(y)) => Ok((x, y)),
+            _ => Err(self.error(format!("point ({}, {}) is beyond the GDSII range", x, y))),
+        }
+    }
+
+    // one BOUNDARY per instance of `points`, given relative to `at`
+    fn polygons(
+        &mut self,
+        points: &[(i64, i64)],
+        at: (i64, i64),
+        rep: Option<Repetition>,
+    ) -> Result<(), GDSIIErrorKind> {
+        let (layer, datatype) = (
+            self.modal(&self.modal.layer, "layer")?,
+            self.modal(&self.modal.datatype, "datatype")?,
+        );
+        let (layer, datatype) = (
+            self.to_i16(layer, "layer")?,
+            self.to_i16(datatype, "datatype")?,
+        );
+        let mut elements = vec![];
+        for offset in self.instances(rep)? {
+            let origin = self.add(at, offset)?;
+            let mut outline = points
+                .iter()
+                .map(|p| self.point(self.add(origin, *p)?))
+                .collect::<Result<Vec<_>, _>>()?;
+            outline.push(outline[0]);
+            elements.push(Element::Boundary(Boundary {
+                layer,
+                datatype,
+                points: outline,
+                ..Default::default()
+            }));
+        }
+        self.push(elements)
+    }
+
+    fn rectangle(&mut self) -> Result<(), GDSIIErrorKind> {
+        let info = self.byte()?;
+        self.layer_fields(info)?;
+        let square = info & 0x80 != 0;
+        if info & 0x40 != 0 {
+            self.modal.geometry_w = Some(self.unsigned()?);
+        }
+        let w = self.modal(&self.modal.geometry_w, "geometry-w")?;
+        if square {
+            self.modal.geometry_h = Some(w);
+        } else if info & 0x20 != 0 {
+            self.modal.geometry_h = Some(self.unsigned()?);
+        }
+        let h = self.modal(&self.modal.geometry_h, "geometry-h")?;
+        let at = self.geometry_xy(info)?;
+        let rep = self.optional_repetition(info & 0x04 != 0)?;
+        let (w, h) = (self.int(w)?, self.int(h)?);
+        self.polygons(&[(0, 0), (w, 0), (w, h), (0, h)], at, rep)
+    }
+
+    // L = 0x01 and D = 0x02 fields
+    fn layer_fields(&mut self, info: u8) -> Result<(), GDSIIErrorKind> {
+        if info & 0x01 != 0 {
+            self.modal.layer = Some(self.unsigned()?);
+        }
+        if info & 0x02 != 0 {
+            self.modal.datatype = Some(self.unsigned()?);
+        }
+        Ok(())
+    }
+
+    // X = 0x10 and Y = 0x08 fields against geometry-x/y
+    fn geometry_xy(&mut self, info: u8) -> Result<(i64, i64), GDSIIErrorKind> {
+        let x = self.coordinate(info & 0x10 != 0, self.modal.geometry.0)?;
+        let y = self.coordinate(info & 0x08 != 0, self.modal.geometry.1)?;
+        self.modal.geometry = (x, y);
+        Ok((x, y))
+    }
+
+    fn optional_repetition(&mut self, present: bool) -> Result<Option<Repetition>, GDSIIErrorKind> {
+        if present {
+            self.repetition().map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn polygon(&mut self) -> Result<(), GDSIIErrorKind> {
+        let info = self.byte()?;
+        self.layer_fields(info)?;
+        if info & 0x20 != 0 {
+            let points = self.point_list(true)?;
+            self.modal.polygon_points = Some(points);
+        }
+        let mut points = self.modal(&self.modal.polygon_points, "polygon-point-list")?;
+        points.insert(0, (0, 0));
+        let at = self.geometry_xy(info)?;
+        let rep = self.optional_repetition(info & 0x04 != 0)?;
+        self.polygons(&points, at, rep)
+    }
+
+    fn path(&mut self) -> Result<(), GDSIIErrorKind> {
+        let info = self.byte()?;
+        self.layer_fields(info)?;
+        if info & 0x40 != 0 {
+            self.modal.path_halfwidth = Some(self.unsigned()?);
+        }
+        let halfwidth = self.modal(&self.modal.path_halfwidth, "path-halfwidth")?;
+        if info & 0x80 != 0 {
+            let scheme = self.unsigned()?;
+            let (start, end) = self.modal.path_extensions;
+            let start = self.extension((scheme >> 2) & 3, start, halfwidth)?;
+            let end = self.extension(scheme & 3, end, halfwidth)?;
+            self.modal.path_extensions = (start, end);
+        }
+        let start_ext = self.modal(&self.modal.path_extensions.0, "path-start-extension")?;
+        let end_ext = self.modal(&self.modal.path_extensions.1, "path-end-extension")?;
+        if info & 0x20 != 0 {
+            let points = self.point_list(false)?;
+            self.modal.path_points = Some(points);
+        }
+        let mut points = self.modal(&self.modal.path_points, "path-point-list")?;
+        points.insert(0, (0, 0));
+        let at = self.geometry_xy(info)?;
+        let rep = self.optional_repetition(info & 0x04 != 0)?;
+        let (layer, datatype) = (
+            self.modal(&self.modal.layer, "layer")?,
+            self.modal(&self.modal.datatype, "datatype")?,
+        );
+        let (layer, datatype) = (
+            self.to_i16(layer, "layer")?,
+            self.to_i16(datatype, "datatype")?,
+        );
+        let width = halfwidth
+            .checked_mul(2)
+            .and_then(|w| i32::try_from(w).ok())
+            .ok_or_else(|| self.error("path width is beyond the GDSII range"))?;
+        let half = width as i64 / 2;
+        let extension = |e: i64| {
+            i32::try_from(e).map_err(|_| self.error("path extension is beyond the GDSII range"))
+        };
+        let (pathtype, bgnextn, endextn) = match (start_ext, end_ext) {
+            (0, 0) => (None, None, None),
+            (s, e) if s == half && e == half => (Some(2), None, None),
+            (s, e) => (Some(4), Some(extension(s)?), Some(extension(e)?)),
+        };
+        let mut elements = vec![];
+        for offset in self.instances(rep)? {
+            let origin = self.add(at, offset)?;
+            let points = points
+                .iter()
+                .map(|p| self.point(self.add(origin, *p)?))
+                .collect::<Result<Vec<_>, _>>()?;
+            elements.push(Element::Path(Path {
+                layer,
+                datatype,
+                pathtype,
+                width: Some(width),
+                bgnextn,
+                endextn,
+                points,
+                ..Default::default()
+            }));
+        }
+        self.push(elements)
+    }
+
+    // one end of a path extension scheme
+    fn extension(
+        &mut self,
+        scheme: u64,
+        modal: Option<i64>,
+        halfwidth: u64,
+    ) -> Result<Option<i64>, GDSIIErrorKind> {
+        Ok(match scheme {
+            0 => modal,
+            1 => Some(0),
+            2 => Some(self.int(halfwidth)?),
+            _ => Some(self.signed()?),
+        })
+    }
+
+    fn trapezoid(&mut self, id: u64) -> Result<(), GDSIIErrorKind> {
+        let info = self.byte()?;
+        self.layer_fields(info)?;
+        if info & 0x40 != 0 {
+            self.modal.geometry_w = Some(self.unsigned()?);
+        }
+        if info & 0x20 != 0 {
+            self.modal.geometry_h = Some(self.unsigned()?);
+        }
+        let w = self.modal(&self.modal.geometry_w, "geometry-w")?;
+        let h = self.modal(&self.modal.geometry_h, "geometry-h")?;
+        let (w, h) = (self.int(w)?, self.int(h)?);
+        let a = if id != 25 { self.signed()? } else { 0 };
+        let b = if id != 24 { self.signed()? } else { 0 };
+        let at = self.geometry_xy(info)?;
+        let rep = self.optional_repetition(info & 0x04 != 0)?;
+        let points = if info & 0x80 != 0 {
+            [
+                (0, a.max(0)),
+                (0, h + b.min(0)),
+                (w, h - b.max(0)),
+                (w, -a.min(0)),
+            ]
+        } else {
+            [
+                (a.max(0), h),
+                (w + b.min(0), h),
+                (w - b.max(0), 0),
+                (-a.min(0), 0),
+            ]
+        };
+        self.polygons(&points, at, rep)
+    }
+
+    fn ctrapezoid(&mut self) -> Result<(), GDSIIErrorKind> {
+        let info = self.byte()?;
+        self.layer_fields(info)?;
+        if info & 0x80 != 0 {
+            self.modal.ctrapezoid_type = Some(self.unsigned()?);
+        }
+        let kind = self.modal(&self.modal.ctrapezoid_type, "ctrapezoid-type")? as usize;
+        if kind >= CTRAPEZOIDS.len() {
+            return Err(self.error(format!("unknown CTRAPEZOID type {}", kind)));
+        }
+        if info & 0x40 != 0 {
+            self.modal.geometry_w = Some(self.unsigned()?);
+        }
+        if info & 0x20 != 0 {
+            self.modal.geometry_h = Some(self.unsigned()?);
+        }
+        // some types take one dimension from the other
+        let (w, h) = match kind {
+            16..=19 | 25 => {
+                let w = self.modal(&self.modal.geometry_w, "geometry-w")?;
+                self.modal.geometry_h = Some(w);
+                (w, w)
+            }
+            20 | 21 => {
+                let h = self.modal(&self.modal.geometry_h, "geometry-h")?;
+                let w = h.checked_mul(2).ok_or_else(|| self.overflow())?;
+                self.modal.geometry_w = Some(w);
+                (w, h)
+            }
+            22 | 23 => {
+                let w = self.modal(&self.modal.geometry_w, "geometry-w")?;
+                let h = w.checked_mul(2).ok_or_else(|| self.overflow())?;
+                self.modal.geometry_h = Some(h);
+                (w, h)
+            }
+            _ => (
+                self.modal(&self.modal.geometry_w, "geometry-w")?,
+                self.modal(&self.modal.geometry_h, "geometry-h")?,
+            ),
+        };
+        let at = self.geometry_xy(info)?;
+        let rep = self.optional_repetition(info & 0x04 != 0)?;
+        let (w, h) = (self.int(w)?, self.int(h)?);
+        let corner = |[xw, xh, yw, yh]: &[i64; 4]| {
+            let x = xw.checked_mul(w)?.checked_add(xh.checked_mul(h)?)?;
+            let y = yw.checked_mul(w)?.checked_add(yh.checked_mul(h)?)?;
+            Some((x, y))
+        };
+        let mut points = CTRAPEZOIDS[kind]
+            .iter()
+            .map(|c| corner(c).ok_or_else(|| self.overflow()))
+            .collect::<Result<Vec<_>, _>>()?;
+        if points[3] == points[0] {
+            points.pop();
+        }
+        self.polygons(&points, at, rep)
+    }
+
+    fn circle(&mut self) -> Result<(), GDSIIErrorKind> {
+        let info = self.byte()?;
+        self.layer_fields(info)?;
+        if info & 0x20 != 0 {
+            self.modal.circle_radius = Some(self.unsigned()?);
+        }
+        let r = self.modal(&self.modal.circle_radius, "circle-radius")? as f64;
+        let at = self.geometry_xy(info)?;
+        let rep = self.optional_repetition(info & 0x04 != 0)?;
+        let points: Vec<(i64, i64)> = (0..CIRCLE_VERTICES)
+            .map(|i| {
+                let (sin, cos) =
+                    (i as f64 * std::f64::consts::TAU / CIRCLE_VERTICES as f64).sin_cos();
+                ((r * cos).round() as i64, (r * sin).round() as i64)
+            })
+            .collect();
+        self.polygons(&points, at, rep)
+    }
+
+    fn placement(&mut self, id: u64) -> Result<(), GDSIIErrorKind> {
+        let info = self.byte()?;
+        if info & 0x80 != 0 {
+            self.modal.placement_cell = Some(self.name_ref(info & 0x40 != 0)?);
+        }
+        let cell = self.modal(&self.modal.placement_cell, "placement-cell")?;
+        let (mag, angle) = if id == PLACEMENT {
+            (1.0, ((info >> 1) & 3) as f64 * 90.0)
+        } else {
+            let mag = if info & 0x04 != 0 { self.real()? } else { 1.0 };
+            let angle = if info & 0x02 != 0 { self.real()? } else { 0.0 };
+            (mag, angle)
+        };
+        let x = self.coordinate(info & 0x20 != 0, self.modal.placement.0)?;
+        let y = self.coordinate(info & 0x10 != 0, self.modal.placement.1)?;
+        self.modal.placement = (x, y);
+        let rep = self.optional_repetition(info & 0x08 != 0)?;
+        let reflect_x = info & 0x01 != 0;
+        let strans = (reflect_x || mag != 1.0 || angle != 0.0).then(|| Strans {
+            reflect_x,
+            mag: (mag != 1.0).then(|| Real8::from_f64(mag)),
+            angle: (angle != 0.0).then(|| Real8::from_f64(angle)),
+            ..Default::default()
+        });
+        let (sname, number) = match cell {
+            NameRef::Name(name) => (name, None),
+            NameRef::Number(n) => (String::new(), Some(n)),
+        };
+        let origin = self.point((x, y))?;
+        let elements = match rep {
+            None => vec![Element::SRef(SRef {
+                sname,
+                strans,
+                origin,
+                ..Default::default()
+            })],
+            Some(Repetition::Grid {
+                cols,
+                rows,
+                col,
+                row,
+            }) if cols <= i16::MAX as u64 && rows <= i16::MAX as u64 => {
+                let span = |n: u64, step: (i64, i64)| {
+                    let d = times(n as i64, step).ok_or_else(|| self.overflow())?;
+                    self.point(self.add((x, y), d)?)
+                };
+                vec![Element::ARef(ARef {
+                    sname,
+                    strans,
+                    cols: cols as i16,
+                    rows: rows as i16,
+                    origin,
+                    col_point: span(cols, col)?,
+                    row_point: span(rows, row)?,
+                    ..Default::default()
+                })]
+            }
+            rep => self
+                .instances(rep)?
+                .into_iter()
+                .map(|offset| {
+                    Ok(Element::SRef(SRef {
+                        sname: sname.clone(),
+                        strans,
+                        origin: self.point(self.add((x, y), offset)?)?,
+                        ..Default::default()
+                    }))
+                })
+                .collect::<Result<Vec<_>, GDSIIErrorKind>>()?,
+        };
+        self.push(elements)?;
+        if let Some(n) = number {
+            let s = self.structures.len() - 1;
+            for e in self.last.clone() {
+                self.sref_names.push((s, e, n, self.record));
+            }
+        }
+        Ok(())
+    }
+
+    fn text_record(&mut self) -> Result<(), GDSIIErrorKind> {
+        let info = self.byte()?;
+        if info & 0x40 != 0 {
+            self.modal.text_string = Some(self.name_ref(info & 0x20 != 0)?);
+        }
+        let string = self.modal(&self.modal.text_string, "text-string")?;
+        if info & 0x01 != 0 {
+            self.modal.textlayer = Some(self.unsigned()?);
+        }
+        if info & 0x02 != 0 {
+            self.modal.texttype = Some(self.unsigned()?);
+        }
+        let layer = self.modal(&self.modal.textlayer, "textlayer")?;
+        let texttype = self.modal(&self.modal.texttype, "texttype")?;
+        let x = self.coordinate(info & 0x10 != 0, self.modal.text.0)?;
+        let y = self.coordinate(info & 0x08 != 0, self.modal.text.1)?;
+        self.modal.text = (x, y);
+        let rep = self.optional_repetition(info & 0x04 != 0)?;
+        let (text, number) = match string {
+            NameRef::Name(s) => (s, None),
+            NameRef::Number(n) => (String::new(), Some(n)),
+        };
+        let (layer, texttype) = (
+            self.to_i16(layer, "textlayer")?,
+            self.to_i16(texttype, "texttype")?,
+        );
+        let mut elements = vec![];
+        for offset in self.instances(rep)? {
+            elements.push(Element::Text(Text {
+                layer,
+                texttype,
+                origin: self.point(self.add((x, y), offset)?)?,
+                string: text.clone(),
+                ..Default::default()
+            }));
+        }
+        self.push(elements)?;
+        if let Some(n) = number {
+            let s = self.structures.len() - 1;
+            for e in self.last.clone() {
+                self.text_names.push((s, e, n, self.record));
+            }
+        }
+        Ok(())
+    }
+
+    fn property(&mut self) -> Result<(), GDSIIErrorKind> {
+        let info = self.byte()?;
+        if info & 0x04 != 0 {
+            self.modal.property_name = Some(self.name_ref(info & 0x02 != 0)?);
+        }
+        let name = self.modal(&self.modal.property_name, "last-property-name")?;
+        let values = if info & 0x08 != 0 {
+            self.modal(&self.modal.property_values, "last-value-list")?
+        } else {
+            let count = match info >> 4 {
+                15 => self.unsigned()?,
+                n => n as u64,
+            };
+            let mut values = vec![];
+            for _ in 0..count {
+                let value = match self.unsigned()? {
+                    t @ 0..=7 => PropValue::Real(self.real_of(t)?),
+                    8 => PropValue::Unsigned(self.unsigned()?),
+                    9 => PropValue::Signed(self.signed()?),
+                    10..=12 => PropValue::String(self.string()?),
+                    13..=15 => PropValue::PropString(self.unsigned()?),
+                    t => return Err(self.error(format!("unknown property value type {}", t))),
+                };
+                values.push(value);
+            }
+            self.modal.property_values = Some(values.clone());
+            values
+        };
+        self.attach_property(name, values);
+        Ok(())
+    }
+
+    // keep a property of the last element record; cell and file properties
+    // have no GDSII counterpart
+    fn attach_property(&mut self, name: NameRef, values: Vec<PropValue>) {
+        if self.last.is_empty() || self.structures.is_empty() {
+            return;
+        }
+        self.properties.push(PendingProperty {
+            structure: self.structures.len() - 1,
+            elements: self.last.clone(),
+            name,
+            values,
+            offset: self.record,
+        });
+    }
+
+    fn cblock(&mut self) -> Result<(), GDSIIErrorKind> {
+        let kind = self.unsigned()?;
+        let size = self.unsigned()? as usize;
+        let compressed_size = self.unsigned()? as usize;
+        let compressed = self.bytes(compressed_size)?;
+        if kind != 0 {
+            return Err(self.error(format!("unknown CBLOCK compression {}", kind)));
+        }
+        let data = miniz_oxide::inflate::decompress_to_vec_with_limit(&compressed, size)
+            .map_err(|_| self.error("CBLOCK does not inflate"))?;
+        if data.len() != size {
+            return Err(self.error("CBLOCK inflates to the wrong size"));
+        }
+        let cblock = Some(self.record);
+        self.inputs.push(Input {
+            data,
+            pos: 0,
+            cblock,
+        });
+        Ok(())
+    }
+
+    fn interval(&mut self) -> Result<(), GDSIIErrorKind> {
+        match self.unsigned()? {
+            0 => {}
+            1..=3 => {
+                self.unsigned()?;
+            }
+            _ => {
+                self.unsigned()?;
+                self.unsigned()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn start(&mut self) -> Result<(), GDSIIErrorKind> {
+        let version = self.string()?;
+        if version != b"1.0" {
+            return Err(self.error(format!(
+                "unsupported OASIS version {}",
+                String::from_utf8_lossy(&version)
+            )));
+        }
+        self.unit = self.real()?;
+        if self.unsigned()? == 0 {
+            for _ in 0..12 {
+                self.unsigned()?;
+            }
+        }
+        Ok(())
+    }
+
+    // read records until END, false when the stream ends first
+    fn records(&mut self) -> Result<bool, GDSIIErrorKind> {
+        loop {
+            let input = self.input();
+            if input.pos >= input.data.len() {
+                if self.inputs.len() == 1 {
+                    return Ok(false);
+                }
+                self.inputs.pop();
+                continue;
+            }
+            let (pos, cblock) = (input.pos, input.cblock);
+            self.record = cblock.unwrap_or(pos);
+            let id = self.unsigned()?;
+            if cblock.is_some() && matches!(id, START | END | CBLOCK) {
+                return Err(self.error(format!("record {} inside a CBLOCK", id)));
+            }
+            match id {
+                0 => {}
+                START => self.start()?,
+                END => return Ok(true),
+                CELLNAME | 4 => {
+                    let (n, name) = self.table_entry(0, id == 4)?;
+                    let name = String::from_utf8(name)
+                        .map_err(|_| self.error("name is not valid UTF-8"))?;
+                    self.cellnames.insert(n, name);
+                }
+                TEXTSTRING | 6 => {
+                    let (n, name) = self.table_entry(1, id == 6)?;
+                    let name = String::from_utf8(name)
+                        .map_err(|_| self.error("text is not valid UTF-8"))?;
+                    self.textstrings.insert(n, name);
+                }
+                PROPNAME | 8 => {
+                    let (n, name) = self.table_entry(2, id == 8)?;
+                    let name = String::from_utf8(name)
+                        .map_err(|_| self.error("name is not valid UTF-8"))?;
+                    self.propnames.insert(n, name);
+                }
+                9 | 10 => {
+                    let (n, value) = self.table_entry(3, id == 10)?;
+                    self.propstrings.insert(n, value);
+                }
+                11 | 12 => {
+                    self.string()?;
+                    self.interval()?;
+                    self.interval()?;
+                }
+                CELL_REF | 14 => {
+                    self.modal = Modal::default();
+                    self.last = 0..0;
+                    let name = self.name_ref(id == CELL_REF)?;
+                    let mut structure = Structure::default();
+                    match name {
+                        NameRef::Name(name) => structure.name = name,
+                        NameRef::Number(n) => {
+                            self.structure_names
+                                .push((self.structures.len(), n, self.record))
+                        }
+                    }
+                    self.structures.push(structure);
+                }
+                15 => self.modal.relative = false,
+                16 => self.modal.relative = true,
+                PLACEMENT | PLACEMENT_TRANSFORM => self.placement(id)?,
+                TEXT => self.text_record()?,
+                RECTANGLE => self.rectangle()?,
+                POLYGON => self.polygon()?,
+                PATH => self.path()?,
+                23..=25 => self.trapezoid(id)?,
+                26 => self.ctrapezoid()?,
+                27 => self.circle()?,
+                PROPERTY => self.property()?,
+                29 => {
+                    let name = self.modal(&self.modal.property_name, "last-property-name")?;
+                    let values = self.modal(&self.modal.property_values, "last-value-list")?;
+                    self.attach_property(name, values);
+                }
+                30 | 31 => {
+                    self.unsigned()?;
+                    self.string()?;
+                    if id == 31 {
+                        self.unsigned()?;
+                    }
+                }
+                32 => {
+                    self.unsigned()?;
+                    self.string()?;
+                }
+                33 => {
+                    let info = self.byte()?;
+                    self.unsigned()?;
+                    self.layer_fields(info)?;
+                    self.string()?;
+                    self.geometry_xy(info)?;
+                    self.optional_repetition(info & 0x04 != 0)?;
+                }
+                CBLOCK => self.cblock()?,
+                id => return Err(self.error(format!("unknown record id {}", id))),
+            }
+        }
+    }
+
+    fn name(
+        &self,
+        table: &HashMap<u64, String>,
+        n: u64,
+        kind: &str,
+        offset: usize,
+    ) -> Result<String, GDSIIErrorKind> {
+        table
+            .get(&n)
+            .cloned()
+            .ok_or_else(|| GDSIIErrorKind::InvalidOasis {
+                offset,
+                reason: format!("{} reference number {} is not defined", kind, n),
+            })
+    }
+
+    // names given by reference number, and GDSII properties
+    fn resolve(&mut self) -> Result<(), GDSIIErrorKind> {
+        for (s, n, at) in std::mem::take(&mut self.structure_names) {
+            self.structures[s].name = self.name(&self.cellnames, n, "CELLNAME", at)?;
+        }
+        for (s, e, n, at) in std::mem::take(&mut self.sref_names) {
+            let name = self.name(&self.cellnames, n, "CELLNAME", at)?;
+            match &mut self.structures[s].elements[e] {
+                Element::SRef(r) => r.sname = name,
+                Element::ARef(r) => r.sname = name,
+                _ => unreachable!("only placements are named by cell"),
+            }
+        }
+        for (s, e, n, at) in std::mem::take(&mut self.text_names) {
+            let string = self.name(&self.textstrings, n, "TEXTSTRING", at)?;
+            if let Element::Text(t) = &mut self.structures[s].elements[e] {
+                t.string = string;
+            }
+        }
+        for p in std::mem::take(&mut self.properties) {
+            let name = match p.name {
+                NameRef::Name(name) => name,
+                NameRef::Number(n) => self.name(&self.propnames, n, "PROPNAME", p.offset)?,
+            };
+            if name != GDS_PROPERTY {
+                continue;
+            }
+            let attr = match p.values.first() {
+                Some(PropValue::Unsigned(v)) => i16::try_from(*v).ok(),
+                Some(PropValue::Signed(v)) => i16::try_from(*v).ok(),
+                _ => continue,
+            };
+            let Some(attr) = attr else {
+                return Err(GDSIIErrorKind::InvalidOasis {
+                    offset: p.offset,
+                    reason: "PROPATTR is beyond the GDSII range".to_string(),
+                });
+            };
+            let value = match p.values.get(1) {
+                Some(PropValue::String(s)) => s.clone(),
+                Some(PropValue::PropString(n)) => match self.propstrings.get(n) {
+                    Some(s) => s.clone(),
+                    None => {
+                        return Err(GDSIIErrorKind::InvalidOasis {
+                            offset: p.offset,
+                            reason: format!("PROPSTRING reference number {} is not defined", n),
+                        })
+                    }
+                },
+                _ => continue,
+            };
+            let property = Property {
+                attr,
+                value: String::from_utf8_lossy(&value).into_owned(),
+            };
+            for e in p.elements {
+                if let Some(props) = props_mut(&mut self.structures[p.structure].elements[e]) {
+                    props.push(property.clone());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn props_mut(element: &mut Element) -> Option<&mut Vec<Property>> {
+    match element {
+        Element::Boundary(e) => Some(&mut e.props),
+        Element::Path(e) => Some(&mut e.props),
+        Element::SRef(e) => Some(&mut e.props),
+        Element::ARef(e) => Some(&mut e.props),
+        Element::Text(e) => Some(&mut e.props),
+        Element::Node(e) => Some(&mut e.props),
+        Element::Box(e) => Some(&mut e.props),
+    }
+}
+
+// library from an OASIS stream
+pub(crate) fn oasis_parser(data: &[u8]) -> Result<Library, GDSIIErrorKind> {
+    if !data.starts_with(MAGIC) {
+        return Err(GDSIIErrorKind::InvalidOasis {
+            offset: 0,
+            reason: "missing %SEMI-OASIS magic".to_string(),
+        });
+    }
+    let mut reader = OasisReader {
+        inputs: vec![Input {
+            data: data.to_vec(),
+            pos: MAGIC.len(),
+            cblock: None,
+        }],
+        record: MAGIC.len(),
+        modal: Modal::default(),
+        cellnames: HashMap::new(),
+        textstrings: HashMap::new(),
+        propnames: HashMap::new(),
+        propstrings: HashMap::new(),
+        implicit: [0; 4],
+        unit: 0.0,
+        structures: vec![],
+        structure_names: vec![],
+        sref_names: vec![],
+        text_names: vec![],
+        properties: vec![],
+        last: 0..0,
+    };
+    if !reader.records()? {
+        return Err(GDSIIErrorKind::InvalidOasis {
+            offset: data.len(),
+            reason: "stream ends before END".to_string(),
+        });
+    }
+    if reader.unit <= 0.0 {
+        return Err(GDSIIErrorKind::InvalidOasis {
+            offset: MAGIC.len(),
+            reason: "missing START record".to_string(),
+        });
+    }
+    reader.resolve()?;
+    // OASIS units are grid steps per micron, user units are microns
+    let units = [
+        Real8::from_f64(1.0 / reader.unit),
+        Real8::from_f64(1e-6 / reader.unit),
+    ];
+    Ok(Library {
+        header: vec![
+            FileHeader::Header(600),
+            FileHeader::BgnLib([0; 12]),
+            FileHeader::LibName("LIB".to_string()),
+            FileHeader::Units(units),
+        ],
+        structures: reader.structures,
+        ..Default::default()
+    })
+}
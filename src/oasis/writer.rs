@@ -0,0 +1,600 @@
+use super::basic::*;
+use crate::error::GDSIIErrorKind;
+use crate::model::*;
+use std::collections::HashMap;
+use std::io::Write;
+
+/// How [`Library::to_oasis`] writes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct OasisOptions {
+    /// deflate the contents of every cell into a CBLOCK
+    pub compress: bool,
+}
+
+// standard property carrying a GDSII PROPATTR/PROPVALUE pair
+pub(super) const GDS_PROPERTY: &str = "S_GDS_PROPERTY";
+
+// record ids, SEMI P39 table 8
+pub(super) const START: u64 = 1;
+pub(super) const END: u64 = 2;
+pub(super) const CELLNAME: u64 = 3;
+pub(super) const TEXTSTRING: u64 = 5;
+pub(super) const PROPNAME: u64 = 7;
+pub(super) const CELL_REF: u64 = 13;
+pub(super) const PLACEMENT: u64 = 17;
+pub(super) const PLACEMENT_TRANSFORM: u64 = 18;
+pub(super) const TEXT: u64 = 19;
+pub(super) const RECTANGLE: u64 = 20;
+pub(super) const POLYGON: u64 = 21;
+pub(super) const PATH: u64 = 22;
+pub(super) const PROPERTY: u64 = 28;
+pub(super) const CBLOCK: u64 = 34;
+
+// modal variables, reset at every CELL; xy-mode stays absolute
+#[derive(Default)]
+struct Modal {
+    placement_cell: Option<u64>,
+    placement: (i64, i64),
+    layer: Option<u64>,
+    datatype: Option<u64>,
+    textlayer: Option<u64>,
+    texttype: Option<u64>,
+    text: (i64, i64),
+    text_string: Option<u64>,
+    geometry: (i64, i64),
+    geometry_w: Option<u64>,
+    geometry_h: Option<u64>,
+    polygon_points: Option<Vec<u8>>,
+    path_halfwidth: Option<u64>,
+    path_points: Option<Vec<u8>>,
+    path_extensions: (Option<i64>, Option<i64>),
+    repetition: Option<Vec<u8>>,
+    property_name: Option<u64>,
+}
+
+// set `modal` to `value`, telling whether it has to be written
+fn changed<T: PartialEq>(modal: &mut Option<T>, value: T) -> bool {
+    if modal.as_ref() == Some(&value) {
+        return false;
+    }
+    *modal = Some(value);
+    true
+}
+
+// layers and datatypes are unsigned in OASIS, negative numbers keep their
+// 16 bit pattern
+fn unsigned(v: i16) -> u64 {
+    v as u16 as u64
+}
+
+fn wide((x, y): Point) -> (i64, i64) {
+    (x as i64, y as i64)
+}
+
+// name tables, numbered in order of first use
+#[derive(Default)]
+struct Names<'a> {
+    ids: HashMap<&'a str, u64>,
+    names: Vec<&'a str>,
+}
+
+impl<'a> Names<'a> {
+    fn add(&mut self, name: &'a str) {
+        if !self.ids.contains_key(name) {
+            self.ids.insert(name, self.names.len() as u64);
+            self.names.push(name);
+        }
+    }
+}
+
+// an AREF as (instance displacement, repetition) when its lattice is exact
+fn aref_lattice(e: &ARef) -> Option<Vec<u8>> {
+    let (cols, rows) = (e.cols as i64, e.rows as i64);
+    let (ox, oy) = wide(e.origin);
+    let (cx, cy) = (wide(e.col_point).0 - ox, wide(e.col_point).1 - oy);
+    let (rx, ry) = (wide(e.row_point).0 - ox, wide(e.row_point).1 - oy);
+    if cx % cols != 0 || cy % cols != 0 || rx % rows != 0 || ry % rows != 0 {
+        return None;
+    }
+    let (col, row) = ((cx / cols, cy / cols), (rx / rows, ry / rows));
+    let mut rep = vec![];
+    match (cols, rows) {
+        (1, 1) => return Some(rep),
+        (_, 1) if col.1 == 0 && col.0 >= 0 => {
+            append_unsigned(&mut rep, 2);
+            append_unsigned(&mut rep, cols as u64 - 2);
+            append_unsigned(&mut rep, col.0 as u64);
+        }
+        (1, _) if row.0 == 0 && row.1 >= 0 => {
+            append_unsigned(&mut rep, 3);
+            append_unsigned(&mut rep, rows as u64 - 2);
+            append_unsigned(&mut rep, row.1 as u64);
+        }
+        (_, 1) | (1, _) => {
+            let (n, step) = if rows == 1 { (cols, col) } else { (rows, row) };
+            append_unsigned(&mut rep, 9);
+            append_unsigned(&mut rep, n as u64 - 2);
+            append_g_delta(&mut rep, step);
+        }
+        _ if col.1 == 0 && row.0 == 0 && col.0 >= 0 && row.1 >= 0 => {
+            append_unsigned(&mut rep, 1);
+            append_unsigned(&mut rep, cols as u64 - 2);
+            append_unsigned(&mut rep, rows as u64 - 2);
+            append_unsigned(&mut rep, col.0 as u64);
+            append_unsigned(&mut rep, row.1 as u64);
+        }
+        _ => {
+            append_unsigned(&mut rep, 8);
+            append_unsigned(&mut rep, cols as u64 - 2);
+            append_unsigned(&mut rep, rows as u64 - 2);
+            append_g_delta(&mut rep, col);
+            append_g_delta(&mut rep, row);
+        }
+    }
+    Some(rep)
+}
+
+// the axis aligned rectangle a closed BOUNDARY/BOX outline draws
+fn rectangle(points: &[Point]) -> Option<(Point, Point)> {
+    if points.len() != 5 || points[0] != points[4] {
+        return None;
+    }
+    let (min, max) = (
+        (
+            points.iter().map(|p| p.0).min()?,
+            points.iter().map(|p| p.1).min()?,
+        ),
+        (
+            points.iter().map(|p| p.0).max()?,
+            points.iter().map(|p| p.1).max()?,
+        ),
+    );
+    let corners = points[..4]
+        .iter()
+        .all(|p| (p.0 == min.0 || p.0 == max.0) && (p.1 == min.1 || p.1 == max.1));
+    let edges = points
+        .windows(2)
+        .all(|w| (w[0].0 == w[1].0) != (w[0].1 == w[1].1));
+    (corners && edges).then_some((min, max))
+}
+
+struct CellWriter<'a, 'n> {
+    cells: &'n Names<'a>,
+    texts: &'n Names<'a>,
+    gds_property: u64,
+    modal: Modal,
+    data: Vec<u8>,
+}
+
+impl CellWriter<'_, '_> {
+    // the repetition field, 0 when it repeats the previous one
+    fn repetition(&mut self, rep: &[u8]) {
+        if rep.is_empty() {
+            return;
+        }
+        if changed(&mut self.modal.repetition, rep.to_vec()) {
+            self.data.extend(rep);
+        } else {
+            append_unsigned(&mut self.data, 0);
+        }
+    }
+
+    // layer and datatype bits (L = 1, D = 2) with the fields after `info`
+    fn layer(&mut self, layer: i16, datatype: i16) -> (u8, Vec<u8>) {
+        let (mut info, mut fields) = (0, vec![]);
+        if changed(&mut self.modal.layer, unsigned(layer)) {
+            info |= 0x01;
+            append_unsigned(&mut fields, unsigned(layer));
+        }
+        if changed(&mut self.modal.datatype, unsigned(datatype)) {
+            info |= 0x02;
+            append_unsigned(&mut fields, unsigned(datatype));
+        }
+        (info, fields)
+    }
+
+    // X = 0x10 and Y = 0x08 bits against the geometry-x/y modals
+    fn geometry_xy(&mut self, (x, y): (i64, i64)) -> (u8, Vec<u8>) {
+        let (mut info, mut fields) = (0, vec![]);
+        if x != self.modal.geometry.0 {
+            info |= 0x10;
+            append_signed(&mut fields, x);
+        }
+        if y != self.modal.geometry.1 {
+            info |= 0x08;
+            append_signed(&mut fields, y);
+        }
+        self.modal.geometry = (x, y);
+        (info, fields)
+    }
+
+    fn properties(&mut self, props: &[Property]) {
+        for p in props {
+            let mut info = 0x20 | 0x01; // two values, standard property
+            if changed(&mut self.modal.property_name, self.gds_property) {
+                info |= 0x04 | 0x02;
+            }
+            append_unsigned(&mut self.data, PROPERTY);
+            self.data.push(info);
+            if info & 0x04 != 0 {
+                append_unsigned(&mut self.data, self.gds_property);
+            }
+            append_unsigned(&mut self.data, 8);
+            append_unsigned(&mut self.data, unsigned(p.attr));
+            append_unsigned(&mut self.data, 11);
+            append_string(&mut self.data, p.value.as_bytes());
+        }
+    }
+
+    fn rectangle(
+        &mut self,
+        layer: i16,
+        datatype: i16,
+        (min, max): (Point, Point),
+        props: &[Property],
+    ) {
+        let (w, h) = (wide(max).0 - wide(min).0, wide(max).1 - wide(min).1);
+        let (mut info, layer_fields) = self.layer(layer, datatype);
+        let mut fields = layer_fields;
+        if w == h {
+            info |= 0x80;
+        }
+        if changed(&mut self.modal.geometry_w, w as u64) {
+            info |= 0x40;
+            append_unsigned(&mut fields, w as u64);
+        }
+        if w == h {
+            self.modal.geometry_h = Some(h as u64);
+        } else if changed(&mut self.modal.geometry_h, h as u64) {
+            info |= 0x20;
+            append_unsigned(&mut fields, h as u64);
+        }
+        let (xy, xy_fields) = self.geometry_xy(wide(min));
+        fields.extend(xy_fields);
+        append_unsigned(&mut self.data, RECTANGLE);
+        self.data.push(info | xy);
+        self.data.extend(fields);
+        self.properties(props);
+    }
+
+    fn polygon(&mut self, e: &Boundary) {
+        if let Some(corners) = rectangle(&e.points) {
+            return self.rectangle(e.layer, e.datatype, corners, &e.props);
+        }
+        let mut points: Vec<(i64, i64)> = e.points.iter().map(|p| wide(*p)).collect();
+        // OASIS polygons close themselves
+        if points.len() > 1 && points.first() == points.last() {
+            points.pop();
+        }
+        let Some(&start) = points.first() else {
+            return;
+        };
+        let mut list = vec![];
+        append_point_list(&mut list, &points);
+        let (mut info, mut fields) = self.layer(e.layer, e.datatype);
+        if changed(&mut self.modal.polygon_points, list.clone()) {
+            info |= 0x20;
+            fields.extend(list);
+        }
+        let (xy, xy_fields) = self.geometry_xy(start);
+        fields.extend(xy_fields);
+        append_unsigned(&mut self.data, POLYGON);
+        self.data.push(info | xy);
+        self.data.extend(fields);
+        self.properties(&e.props);
+    }
+
+    fn path(&mut self, e: &Path) {
+        let points: Vec<(i64, i64)> = e.points.iter().map(|p| wide(*p)).collect();
+        let Some(&start) = points.first() else {
+            return;
+        };
+        let halfwidth = e.width.unwrap_or(0).unsigned_abs() as u64 / 2;
+        // round ends have no OASIS form, they become half-width extensions
+        let (start_ext, end_ext) = match e.pathtype.unwrap_or(0) {
+            1 | 2 => (halfwidth as i64, halfwidth as i64),
+            4 => (e.bgnextn.unwrap_or(0) as i64, e.endextn.unwrap_or(0) as i64),
+            _ => (0, 0),
+        };
+        let (mut info, mut fields) = self.layer(e.layer, e.datatype);
+        if changed(&mut self.modal.path_halfwidth, halfwidth) {
+            info |= 0x40;
+            append_unsigned(&mut fields, halfwidth);
+        }
+        if self.modal.path_extensions != (Some(start_ext), Some(end_ext)) {
+            info |= 0x80;
+            self.modal.path_extensions = (Some(start_ext), Some(end_ext));
+            // flush, half-width or explicit, start in bits 3-2
+            let scheme = |ext: i64| match ext {
+                0 => 1,
+                ext if ext == halfwidth as i64 => 2,
+                _ => 3,
+            };
+            append_unsigned(&mut fields, scheme(start_ext) << 2 | scheme(end_ext));
+            if scheme(start_ext) == 3 {
+                append_signed(&mut fields, start_ext);
+            }
+            if scheme(end_ext) == 3 {
+                append_signed(&mut fields, end_ext);
+            }
+        }
+        let mut list = vec![];
+        append_point_list(&mut list, &points);
+        if changed(&mut self.modal.path_points, list.clone()) {
+            info |= 0x20;
+            fields.extend(list);
+        }
+        let (xy, xy_fields) = self.geometry_xy(start);
+        fields.extend(xy_fields);
+        append_unsigned(&mut self.data, PATH);
+        self.data.push(info | xy);
+        self.data.extend(fields);
+        self.properties(&e.props);
+    }
+
+    fn placement(
+        &mut self,
+        sname: &str,
+        strans: Option<&Strans>,
+        origin: (i64, i64),
+        rep: &[u8],
+        props: &[Property],
+    ) {
+        let strans = strans.copied().unwrap_or_default();
+        let (mag, angle) = (
+            strans.magnification(),
+            strans.angle_degrees().rem_euclid(360.0),
+        );
+        let quarter = mag == 1.0 && angle % 90.0 == 0.0;
+        let mut info = if strans.reflect_x { 0x01 } else { 0 };
+        let mut fields = vec![];
+        let cell = self.cells.ids[sname];
+        if changed(&mut self.modal.placement_cell, cell) {
+            info |= 0x80 | 0x40;
+            append_unsigned(&mut fields, cell);
+        }
+        if quarter {
+            info |= ((angle / 90.0) as u8) << 1;
+        } else {
+            if mag != 1.0 {
+                info |= 0x04;
+                append_real(&mut fields, mag);
+            }
+            if angle != 0.0 {
+                info |= 0x02;
+                append_real(&mut fields, angle);
+            }
+        }
+        if origin.0 != self.modal.placement.0 {
+            info |= 0x20;
+            append_signed(&mut fields, origin.0);
+        }
+        if origin.1 != self.modal.placement.1 {
+            info |= 0x10;
+            append_signed(&mut fields, origin.1);
+        }
+        self.modal.placement = origin;
+        if !rep.is_empty() {
+            info |= 0x08;
+        }
+        append_unsigned(
+            &mut self.data,
+            if quarter {
+                PLACEMENT
+            } else {
+                PLACEMENT_TRANSFORM
+            },
+        );
+        self.data.push(info);
+        self.data.extend(fields);
+        self.repetition(rep);
+        self.properties(props);
+    }
+
+    fn aref(&mut self, e: &ARef) {
+        if e.cols <= 0 || e.rows <= 0 {
+            return;
+        }
+        match aref_lattice(e) {
+            Some(rep) => {
+                self.placement(&e.sname, e.strans.as_ref(), wide(e.origin), &rep, &e.props)
+            }
+            // instances off the integer grid are placed one by one
+            None => {
+                for t in e.transforms() {
+                    let origin = ((t.offset.0.round()) as i64, (t.offset.1.round()) as i64);
+                    self.placement(&e.sname, e.strans.as_ref(), origin, &[], &e.props);
+                }
+            }
+        }
+    }
+
+    fn text(&mut self, e: &Text) {
+        let mut info = 0;
+        let mut fields = vec![];
+        let string = self.texts.ids[e.string.as_str()];
+        if changed(&mut self.modal.text_string, string) {
+            info |= 0x40 | 0x20;
+            append_unsigned(&mut fields, string);
+        }
+        if changed(&mut self.modal.textlayer, unsigned(e.layer)) {
+            info |= 0x01;
+            append_unsigned(&mut fields, unsigned(e.layer));
+        }
+        if changed(&mut self.modal.texttype, unsigned(e.texttype)) {
+            info |= 0x02;
+            append_unsigned(&mut fields, unsigned(e.texttype));
+        }
+        let (x, y) = wide(e.origin);
+        if x != self.modal.text.0 {
+            info |= 0x10;
+            append_signed(&mut fields, x);
+        }
+        if y != self.modal.text.1 {
+            info |= 0x08;
+            append_signed(&mut fields, y);
+        }
+        self.modal.text = (x, y);
+        append_unsigned(&mut self.data, TEXT);
+        self.data.push(info);
+        self.data.extend(fields);
+        self.properties(&e.props);
+    }
+
+    fn element(&mut self, element: &Element) {
+        match element {
+            Element::Boundary(e) => self.polygon(e),
+            Element::Path(e) => self.path(e),
+            Element::SRef(e) => {
+                self.placement(&e.sname, e.strans.as_ref(), wide(e.origin), &[], &e.props)
+            }
+            Element::ARef(e) => self.aref(e),
+            Element::Text(e) => self.text(e),
+            Element::Box(e) => match rectangle(&e.points) {
+                Some(corners) => self.rectangle(e.layer, e.boxtype, corners, &e.props),
+                None => self.polygon(&Boundary {
+                    layer: e.layer,
+                    datatype: e.boxtype,
+                    points: e.points.clone(),
+                    props: e.props.clone(),
+                    ..Default::default()
+                }),
+            },
+            // connectivity only, OASIS has nothing to hold it
+            Element::Node(_) => {}
+        }
+    }
+}
+
+// OASIS grid steps per micron for meters per database unit
+fn grid_per_micron(meters: f64) -> f64 {
+    let unit = 1e-6 / meters;
+    if (unit - unit.round()).abs() < 1e-9 * unit {
+        unit.round()
+    } else {
+        unit
+    }
+}
+
+impl Library {
+    /// The library as an OASIS stream.
+    ///
+    /// BOUNDARY and BOX become RECTANGLE or POLYGON, element properties the
+    /// standard `S_GDS_PROPERTY`; AREFs become one repeated PLACEMENT when
+    /// their lattice is on the grid. OASIS has no place for NODE, the
+    /// TEXT presentation and STRANS, round path ends, odd path widths or the
+    /// absolute magnification and angle flags, which are left out or
+    /// approximated.
+    pub fn to_oasis(&self, options: &OasisOptions) -> Vec<u8> {
+        let mut data = vec![];
+        self.write_oasis(&mut data, options)
+            .expect("writing to a Vec cannot fail");
+        data
+    }
+
+    pub fn save_oasis<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+        options: &OasisOptions,
+    ) -> Result<(), GDSIIErrorKind> {
+        let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        self.write_oasis(&mut file, options)?;
+        file.flush()?;
+        Ok(())
+    }
+
+    pub fn write_oasis<W: Write>(
+        &self,
+        mut w: W,
+        options: &OasisOptions,
+    ) -> Result<(), GDSIIErrorKind> {
+        let mut cells = Names::default();
+        let mut texts = Names::default();
+        for s in &self.structures {
+            cells.add(&s.name);
+        }
+        let mut has_props = false;
+        for element in self.structures.iter().flat_map(|s| &s.elements) {
+            match element {
+                Element::SRef(e) => cells.add(&e.sname),
+                Element::ARef(e) => cells.add(&e.sname),
+                Element::Text(e) => texts.add(&e.string),
+                _ => {}
+            }
+            has_props |= !element.props().is_empty();
+        }
+
+        let mut data = MAGIC.to_vec();
+        append_unsigned(&mut data, START);
+        append_string(&mut data, b"1.0");
+        append_real(
+            &mut data,
+            grid_per_micron(self.units().map_or(1e-9, |u| u.1)),
+        );
+        // table offsets go in END
+        append_unsigned(&mut data, 1);
+
+        let mut offsets = [(0u64, 0u64); 6];
+        offsets[0] = (1, data.len() as u64);
+        for name in &cells.names {
+            append_unsigned(&mut data, CELLNAME);
+            append_string(&mut data, name.as_bytes());
+        }
+        if !texts.names.is_empty() {
+            offsets[1] = (1, data.len() as u64);
+            for text in &texts.names {
+                append_unsigned(&mut data, TEXTSTRING);
+                append_string(&mut data, text.as_bytes());
+            }
+        }
+        if has_props {
+            offsets[2] = (1, data.len() as u64);
+            append_unsigned(&mut data, PROPNAME);
+            append_string(&mut data, GDS_PROPERTY.as_bytes());
+        }
+
+        for s in &self.structures {
+            append_unsigned(&mut data, CELL_REF);
+            append_unsigned(&mut data, cells.ids[s.name.as_str()]);
+            let mut cell = CellWriter {
+                cells: &cells,
+                texts: &texts,
+                gds_property: 0,
+                modal: Modal::default(),
+                data: vec![],
+            };
+            for element in &s.elements {
+                cell.element(element);
+            }
+            if options.compress && !cell.data.is_empty() {
+                let compressed = miniz_oxide::deflate::compress_to_vec(&cell.data, 6);
+                append_unsigned(&mut data, CBLOCK);
+                append_unsigned(&mut data, 0);
+                append_unsigned(&mut data, cell.data.len() as u64);
+                append_unsigned(&mut data, compressed.len() as u64);
+                data.extend(compressed);
+            } else {
+                data.extend(cell.data);
+            }
+            // stream the cells out instead of holding the whole file
+            w.write_all(&data)?;
+            data.clear();
+        }
+
+        let mut end = vec![];
+        append_unsigned(&mut end, END);
+        for (flag, offset) in offsets {
+            append_unsigned(&mut end, flag);
+            append_unsigned(&mut end, offset);
+        }
+        // padded so that END is 256 bytes with its validation scheme (none);
+        // the offsets take at most 121 bytes, so the padding length always
+        // takes two
+        let pad = 256 - end.len() - 1 - 2;
+        append_string(&mut end, &vec![0; pad]);
+        append_unsigned(&mut end, 0);
+        w.write_all(&data)?;
+        w.write_all(&end)?;
+        Ok(())
+    }
+}
@@ -1,11 +1,12 @@
 use super::ParseGDIIRes;
+use crate::model::Real8;
 use byteorder::{BigEndian, ByteOrder};
-use nom::bytes::streaming::{tag, take};
+use nom::bytes::streaming::take;
 
 // return valid data size(exclude two byte "size" and two byte "type")
 pub(super) fn take_size(s: &[u8]) -> ParseGDIIRes<&[u8], usize> {
     let (s, d) = take(2usize)(s)?;
-    let parsed_d = BigEndian::read_u16(&d);
+    let parsed_d = BigEndian::read_u16(d);
     Ok((s, parsed_d as usize))
 }
 
@@ -14,6 +15,14 @@ pub(super) fn take_type(s: &[u8]) -> ParseGDIIRes<&[u8], [u8; 2]> {
     Ok((s, [d[0], d[1]]))
 }
 
-pub(super) fn end_tag(s: &[u8]) -> ParseGDIIRes<&[u8], &[u8]> {
-    tag([0x00, 0x04, 0x04, 0x00])(s)
+pub(super) fn real8(s: &[u8]) -> Real8 {
+    Real8::from_bits(BigEndian::read_u64(s))
+}
+
+// drop the NUL byte that pads an odd length string to an even record size
+pub(super) fn strip_pad(s: &[u8]) -> &[u8] {
+    match s.split_last() {
+        Some((0, rest)) => rest,
+        _ => s,
+    }
 }
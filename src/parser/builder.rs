@@ -0,0 +1,197 @@
+use super::element::build_element;
+use crate::error::GDSIIErrorKind;
+use crate::model::*;
+
+/// header record of the element being read and its body records, each with
+/// its byte offset
+type PendingElement = (usize, TuctosinHeader, Vec<(usize, Tuctosin)>);
+
+/// unknown records of the element being read, each with the number of body
+/// records before it
+type PendingRaw = Vec<(usize, RawRecord)>;
+
+/// Folds parsed records into a [`Library`] one record at a time and checks
+/// that they nest as BGNSTR ... element ... ENDEL ... ENDSTR.
+#[derive(Default)]
+pub(super) struct LibraryBuilder {
+    library: Library,
+    structure: Option<Structure>,
+    element: Option<PendingElement>,
+    element_raw: PendingRaw,
+    // STRNAME of the open structure has been read
+    named: bool,
+    finished: bool,
+}
+
+impl LibraryBuilder {
+    /// name of the structure currently open, for error context
+    pub(super) fn structure_name(&self) -> Option<String> {
+        self.structure.as_ref().map(|s| s.name.clone())
+    }
+
+    /// whether ENDLIB has been seen
+    pub(super) fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    fn unexpected(&self, offset: usize, rtype: [u8; 2]) -> GDSIIErrorKind {
+        GDSIIErrorKind::UnexpectedRecord {
+            offset,
+            record: record_name(rtype[0]),
+            structure: self.structure_name(),
+        }
+    }
+
+    // the open structure while its header records may still follow: no
+    // element, unknown record or STRTYPE has been read yet
+    fn structure_header(&mut self) -> Option<&mut Structure> {
+        match self.structure.as_mut() {
+            Some(s)
+                if self.named
+                    && self.element.is_none()
+                    && s.elements.is_empty()
+                    && s.raw.is_empty()
+                    && s.strtype.is_none() =>
+            {
+                Some(s)
+            }
+            _ => None,
+        }
+    }
+
+    // everything but STRNAME needs the open structure to be named first
+    fn check_named(&self, offset: usize) -> std::result::Result<(), GDSIIErrorKind> {
+        if self.structure.is_some() && !self.named {
+            return Err(GDSIIErrorKind::MissingRecord {
+                offset,
+                element: "BGNSTR",
+                missing: "STRNAME",
+                structure: None,
+            });
+        }
+        Ok(())
+    }
+
+    pub(super) fn push(
+        &mut self,
+        offset: usize,
+        variant: GDSIIVariant,
+    ) -> std::result::Result<(), GDSIIErrorKind> {
+        if self.finished {
+            return Err(self.unexpected(offset, variant_type(&variant)));
+        }
+        if !matches!(
+            variant,
+            GDSIIVariant::ModuleHeader(ModuleHeader::StrName(_))
+        ) {
+            self.check_named(offset)?;
+        }
+        match variant {
+            GDSIIVariant::FileHeader(header) => {
+                // the saver writes every library record before the first
+                // structure, one read later would move
+                if self.structure.is_some() || !self.library.structures.is_empty() {
+                    return Err(self.unexpected(offset, header.record_type()));
+                }
+                self.library.header.push(header);
+            }
+            GDSIIVariant::ModuleHeader(module) => match module {
+                ModuleHeader::BgnStr(t) => {
+                    if self.structure.is_some() {
+                        return Err(self.unexpected(offset, module.record_type()));
+                    }
+                    self.named = false;
+                    let (created, modified) = Timestamp::pair(t);
+                    self.structure = Some(Structure {
+                        created,
+                        modified,
+                        ..Default::default()
+                    });
+                }
+                // BGNSTR STRNAME [STRCLASS] [STRTYPE] before the first element
+                ModuleHeader::StrName(name) => match self.structure.as_mut() {
+                    Some(structure) if !self.named => {
+                        structure.name = name;
+                        self.named = true;
+                    }
+                    _ => return Err(self.unexpected(offset, [0x06, 0x06])),
+                },
+                ModuleHeader::StrClass(class) => match self.structure_header() {
+                    Some(structure) if structure.strclass.is_none() => {
+                        structure.strclass = Some(class)
+                    }
+                    _ => return Err(self.unexpected(offset, [0x34, 0x01])),
+                },
+                ModuleHeader::StrType(t) => match self.structure_header() {
+                    Some(structure) => structure.strtype = Some(t),
+                    _ => return Err(self.unexpected(offset, [0x25, 0x02])),
+                },
+            },
+            GDSIIVariant::TuctosinHeader(toc_header) => {
+                if self.structure.is_none() || self.element.is_some() {
+                    return Err(self.unexpected(offset, toc_header.record_type()));
+                }
+                self.element = Some((offset, toc_header, vec![]));
+            }
+            GDSIIVariant::Tuctosin(toc) => match self.element.as_mut() {
+                Some((_, _, records)) => records.push((offset, toc)),
+                None => return Err(self.unexpected(offset, toc.record_type())),
+            },
+            GDSIIVariant::TuctosinEnd => {
+                let (start, toc_header, records) = match self.element.take() {
+                    Some(element) => element,
+                    None => return Err(self.unexpected(offset, [0x11, 0x00])),
+                };
+                let raw = std::mem::take(&mut self.element_raw);
+                // an element is only ever opened inside a structure
+                if let Some(structure) = self.structure.as_mut() {
+                    let element = build_element(start, toc_header, records, raw, &structure.name)?;
+                    structure.elements.push(element);
+                }
+            }
+            GDSIIVariant::ModuleEnd => {
+                if self.element.is_some() {
+                    return Err(self.unexpected(offset, [0x07, 0x00]));
+                }
+                match self.structure.take() {
+                    Some(structure) => self.library.structures.push(structure),
+                    None => return Err(self.unexpected(offset, [0x07, 0x00])),
+                }
+            }
+            GDSIIVariant::FileEnd => {
+                if self.structure.is_some() {
+                    return Err(self.unexpected(offset, [0x04, 0x00]));
+                }
+                self.finished = true;
+            }
+            // attach to the innermost open element, structure or the library
+            GDSIIVariant::Raw(raw) => match (self.element.as_ref(), self.structure.as_mut()) {
+                (Some((_, _, records)), _) => self.element_raw.push((records.len(), raw)),
+                (None, Some(structure)) => structure.raw.push((structure.elements.len(), raw)),
+                (None, None) => {
+                    let at = self.library.header.len() + self.library.structures.len();
+                    self.library.raw.push((at, raw));
+                }
+            },
+            GDSIIVariant::Eof => {}
+        }
+        Ok(())
+    }
+
+    pub(super) fn finish(self) -> Library {
+        self.library
+    }
+}
+
+fn variant_type(variant: &GDSIIVariant) -> [u8; 2] {
+    match variant {
+        GDSIIVariant::FileHeader(h) => h.record_type(),
+        GDSIIVariant::ModuleHeader(h) => h.record_type(),
+        GDSIIVariant::TuctosinHeader(h) => h.record_type(),
+        GDSIIVariant::Tuctosin(t) => t.record_type(),
+        GDSIIVariant::TuctosinEnd => [0x11, 0x00],
+        GDSIIVariant::ModuleEnd => [0x07, 0x00],
+        GDSIIVariant::Raw(raw) => [raw.rtype, raw.dtype],
+        GDSIIVariant::FileEnd | GDSIIVariant::Eof => [0x04, 0x00],
+    }
+}
@@ -0,0 +1,286 @@
+use crate::error::GDSIIErrorKind;
+use crate::model::*;
+
+/// every body record an element may carry, filled in record order
+#[derive(Default)]
+struct ElementFields {
+    elflags: Option<i16>,
+    plex: Option<i32>,
+    layer: Option<i16>,
+    datatype: Option<i16>,
+    xy: Option<Vec<Point>>,
+    pathtype: Option<i16>,
+    width: Option<i32>,
+    bgnextn: Option<i32>,
+    endextn: Option<i32>,
+    sname: Option<String>,
+    strans: Option<i16>,
+    mag: Option<Real8>,
+    angle: Option<Real8>,
+    colrow: Option<(i16, i16)>,
+    texttype: Option<i16>,
+    presentation: Option<i16>,
+    string: Option<String>,
+    nodetype: Option<i16>,
+    boxtype: Option<i16>,
+    props: Vec<Property>,
+    extra: Vec<Tuctosin>,
+    raw: Vec<(usize, RawRecord)>,
+    // PROPATTR waiting for its PROPVALUE
+    pending_attr: Option<i16>,
+}
+
+// whether `toc` is part of the grammar of a `header` element
+fn is_allowed(header: TuctosinHeader, toc: &Tuctosin) -> bool {
+    use TuctosinHeader as H;
+    match toc {
+        t if t.is_extra() => true,
+        Tuctosin::ElfFlags(_)
+        | Tuctosin::Plex(_)
+        | Tuctosin::Xy(_)
+        | Tuctosin::PropAttr(_)
+        | Tuctosin::PropValue(_) => true,
+        Tuctosin::Layer(_) => !matches!(header, H::Sref | H::Aref),
+        Tuctosin::DataType(_) => matches!(header, H::Boundary | H::Path),
+        Tuctosin::PathType(_) | Tuctosin::Width(_) => matches!(header, H::Path | H::Text),
+        Tuctosin::BgnExtn(_) | Tuctosin::EndExtn(_) => header == H::Path,
+        Tuctosin::Sname(_) => matches!(header, H::Sref | H::Aref),
+        Tuctosin::Strans(_) | Tuctosin::Mag(_) | Tuctosin::Angle(_) => {
+            matches!(header, H::Sref | H::Aref | H::Text)
+        }
+        Tuctosin::ColRow(_) => header == H::Aref,
+        Tuctosin::TextType(_) | Tuctosin::Persentation(_) | Tuctosin::AsciiString(_) => {
+            header == H::Text
+        }
+        Tuctosin::NodeType(_) => header == H::Node,
+        Tuctosin::BoxType(_) => header == H::Box,
+        _ => false,
+    }
+}
+
+/// Assemble the body records between an element header and ENDEL.
+///
+/// `offset` is the position of the header record, each body record carries
+/// its own offset, `raw` holds the unknown records kept in between and
+/// `structure` names the enclosing structure for errors.
+pub(super) fn build_element(
+    offset: usize,
+    header: TuctosinHeader,
+    records: Vec<(usize, Tuctosin)>,
+    raw: Vec<(usize, RawRecord)>,
+    structure: &str,
+) -> std::result::Result<Element, GDSIIErrorKind> {
+    let unexpected = |at: usize, record: &'static str| GDSIIErrorKind::UnexpectedRecord {
+        offset: at,
+        record,
+        structure: Some(structure.to_string()),
+    };
+    let missing = |record: &'static str| GDSIIErrorKind::MissingRecord {
+        offset,
+        element: header.name(),
+        missing: record,
+        structure: Some(structure.to_string()),
+    };
+    let point_count = |expected: usize, actual: usize| GDSIIErrorKind::InvalidPointCount {
+        offset,
+        element: header.name(),
+        expected,
+        actual,
+        structure: Some(structure.to_string()),
+    };
+
+    // TEXTNODE shares the grammar of TEXT
+    let kind = match header {
+        TuctosinHeader::TextNode => TuctosinHeader::Text,
+        h => h,
+    };
+    let mut f = ElementFields {
+        raw,
+        ..Default::default()
+    };
+    let order: Vec<[u8; 2]> = records.iter().map(|(_, toc)| toc.record_type()).collect();
+    // MAG or ANGLE, which only exist as part of a STRANS
+    let mut transform = None;
+    for (i, (at, toc)) in records.into_iter().enumerate() {
+        if !is_allowed(kind, &toc) {
+            return Err(unexpected(at, toc.name()));
+        }
+        // the model holds one of each record apart from properties and
+        // extension records
+        let repeatable =
+            toc.is_extra() || matches!(toc, Tuctosin::PropAttr(_) | Tuctosin::PropValue(_));
+        if !repeatable && order[..i].contains(&order[i]) {
+            return Err(unexpected(at, toc.name()));
+        }
+        // PROPATTR is always directly followed by its PROPVALUE
+        if f.pending_attr.is_some() != matches!(toc, Tuctosin::PropValue(_)) {
+            return Err(unexpected(at, toc.name()));
+        }
+        match toc {
+            t if t.is_extra() => f.extra.push(t),
+            Tuctosin::ElfFlags(d) => f.elflags = Some(d),
+            Tuctosin::Plex(d) => f.plex = Some(d),
+            Tuctosin::Layer(d) => f.layer = Some(d),
+            Tuctosin::DataType(d) => f.datatype = Some(d),
+            Tuctosin::Xy(d) => f.xy = Some(d),
+            Tuctosin::PathType(d) => f.pathtype = Some(d),
+            Tuctosin::Width(d) => f.width = Some(d),
+            Tuctosin::BgnExtn(d) => f.bgnextn = Some(d),
+            Tuctosin::EndExtn(d) => f.endextn = Some(d),
+            Tuctosin::Sname(d) => f.sname = Some(d),
+            Tuctosin::Strans(d) => f.strans = Some(d),
+            Tuctosin::Mag(d) => {
+                transform.get_or_insert((at, "MAG"));
+                f.mag = Some(d)
+            }
+            Tuctosin::Angle(d) => {
+                transform.get_or_insert((at, "ANGLE"));
+                f.angle = Some(d)
+            }
+            Tuctosin::ColRow(d) => f.colrow = Some(d),
+            Tuctosin::TextType(d) => f.texttype = Some(d),
+            Tuctosin::Persentation(d) => f.presentation = Some(d),
+            Tuctosin::AsciiString(d) => f.string = Some(d),
+            Tuctosin::NodeType(d) => f.nodetype = Some(d),
+            Tuctosin::BoxType(d) => f.boxtype = Some(d),
+            Tuctosin::PropAttr(d) => f.pending_attr = Some(d),
+            Tuctosin::PropValue(value) => {
+                if let Some(attr) = f.pending_attr.take() {
+                    f.props.push(Property { attr, value });
+                }
+            }
+            _ => {}
+        }
+    }
+    if f.pending_attr.is_some() {
+        return Err(missing("PROPVALUE"));
+    }
+    if let (None, Some((at, record))) = (f.strans, transform) {
+        return Err(unexpected(at, record));
+    }
+
+    let xy = f.xy.ok_or_else(|| missing("XY"))?;
+    let strans = f.strans.map(|bits| Strans {
+        mag: f.mag,
+        angle: f.angle,
+        ..Strans::from_bits(bits)
+    });
+    let layer = || f.layer.ok_or_else(|| missing("LAYER"));
+    let mut element = match header {
+        TuctosinHeader::Boundary => Element::Boundary(Boundary {
+            elflags: f.elflags,
+            plex: f.plex,
+            layer: layer()?,
+            datatype: f.datatype.ok_or_else(|| missing("DATATYPE"))?,
+            points: xy,
+            props: f.props,
+            extra: f.extra,
+            raw: f.raw,
+            order: vec![],
+        }),
+        TuctosinHeader::Path => Element::Path(Path {
+            elflags: f.elflags,
+            plex: f.plex,
+            layer: layer()?,
+            datatype: f.datatype.ok_or_else(|| missing("DATATYPE"))?,
+            pathtype: f.pathtype,
+            width: f.width,
+            bgnextn: f.bgnextn,
+            endextn: f.endextn,
+            points: xy,
+            props: f.props,
+            extra: f.extra,
+            raw: f.raw,
+            order: vec![],
+        }),
+        TuctosinHeader::Sref => Element::SRef(SRef {
+            elflags: f.elflags,
+            plex: f.plex,
+            sname: f.sname.ok_or_else(|| missing("SNAME"))?,
+            strans,
+            origin: single_point(&xy).ok_or_else(|| point_count(1, xy.len()))?,
+            props: f.props,
+            extra: f.extra,
+            raw: f.raw,
+            order: vec![],
+        }),
+        TuctosinHeader::Aref => {
+            let (cols, rows) = f.colrow.ok_or_else(|| missing("COLROW"))?;
+            if xy.len() != 3 {
+                return Err(point_count(3, xy.len()));
+            }
+            Element::ARef(ARef {
+                elflags: f.elflags,
+                plex: f.plex,
+                sname: f.sname.ok_or_else(|| missing("SNAME"))?,
+                strans,
+                cols,
+                rows,
+                origin: xy[0],
+                col_point: xy[1],
+                row_point: xy[2],
+                props: f.props,
+                extra: f.extra,
+                raw: f.raw,
+                order: vec![],
+            })
+        }
+        TuctosinHeader::Text | TuctosinHeader::TextNode => Element::Text(Text {
+            elflags: f.elflags,
+            plex: f.plex,
+            layer: layer()?,
+            texttype: f.texttype.ok_or_else(|| missing("TEXTTYPE"))?,
+            presentation: f.presentation,
+            pathtype: f.pathtype,
+            width: f.width,
+            strans,
+            origin: single_point(&xy).ok_or_else(|| point_count(1, xy.len()))?,
+            string: f.string.ok_or_else(|| missing("STRING"))?,
+            props: f.props,
+            extra: f.extra,
+            raw: f.raw,
+            order: vec![],
+            textnode: header == TuctosinHeader::TextNode,
+        }),
+        TuctosinHeader::Node => Element::Node(Node {
+            elflags: f.elflags,
+            plex: f.plex,
+            layer: layer()?,
+            nodetype: f.nodetype.ok_or_else(|| missing("NODETYPE"))?,
+            points: xy,
+            props: f.props,
+            extra: f.extra,
+            raw: f.raw,
+            order: vec![],
+        }),
+        TuctosinHeader::Box => Element::Box(GdsBox {
+            elflags: f.elflags,
+            plex: f.plex,
+            layer: layer()?,
+            boxtype: f.boxtype.ok_or_else(|| missing("BOXTYPE"))?,
+            points: xy,
+            props: f.props,
+            extra: f.extra,
+            raw: f.raw,
+            order: vec![],
+        }),
+    };
+    // body records keep the order they were read in when it is not the
+    // one the saver writes
+    let saved: Vec<[u8; 2]> = element
+        .records()
+        .iter()
+        .map(Tuctosin::record_type)
+        .collect();
+    if saved != order {
+        element.set_order(order);
+    }
+    Ok(element)
+}
+
+fn single_point(xy: &[Point]) -> Option<Point> {
+    match xy {
+        [p] => Some(*p),
+        _ => None,
+    }
+}
@@ -1,17 +1,21 @@
-use crate::error::GDSIIErrorKind;
+use crate::error::{ExpectedLength, GDSIIErrorKind};
 use crate::model::*;
-use nom::{
-    error::{ErrorKind, ParseError},
-    multi::many_till,
-    Finish,
-};
+use nom::error::{ErrorKind, ParseError};
 use std::fmt::Debug;
 
+/// Record level failure, `I` is the input at the start of the record.
 #[derive(Debug, PartialEq)]
-pub(self) enum ParseGDSIIError<I> {
-    ParseIntError,
+enum ParseGDSIIError<I> {
     Nom(I, ErrorKind),
-    Utf8Error,
+    InvalidLength {
+        input: I,
+        d_type: [u8; 2],
+        expected: ExpectedLength,
+        actual: usize,
+    },
+    InvalidRecordSize(I, usize),
+    UnknownRecord(I, [u8; 2]),
+    Utf8Error(I, [u8; 2]),
 }
 
 impl<I> ParseError<I> for ParseGDSIIError<I> {
@@ -27,51 +31,100 @@ impl<I> ParseError<I> for ParseGDSIIError<I> {
 type ParseGDIIRes<T, U> = nom::IResult<T, U, ParseGDSIIError<T>>;
 
 mod basic;
+mod builder;
+mod element;
+mod reader;
 mod variant_parser;
 
-use basic::end_tag;
-use variant_parser::*;
+use builder::LibraryBuilder;
+pub use reader::RecordReader;
+use std::io::Read;
 
-pub fn gds2_parser(s: &[u8]) -> std::result::Result<GDSIIModel, GDSIIErrorKind> {
-    match many_till(variant_parser, end_tag)(s).finish() {
-        Ok((_, (data, _))) => {
-            let mut gds2_model = GDSIIModel::default();
-            let mut current_tuctosin_type = TuctosinHeader::default();
-            // main process
-            for d in data {
-                match d {
-                    GDSIIVariant::FileHeader(header) => {
-                        gds2_model.header.insert(header.get_tag(), header);
-                    }
-                    GDSIIVariant::ModuleHeader(module) => match module {
-                        ModuleHeader::BgnStr(t) => gds2_model.structure_time = t,
-                        ModuleHeader::StrName(t) => gds2_model.structure_name = t,
-                    },
-                    GDSIIVariant::TuctosinHeader(toc_header) => {
-                        if toc_header != current_tuctosin_type {
-                            current_tuctosin_type = toc_header;
-                        }
-                    }
-                    GDSIIVariant::Tuctosin(shape) => match current_tuctosin_type {
-                        TuctosinHeader::Boundary => gds2_model.s_boundary.push(shape),
-                        TuctosinHeader::Path => gds2_model.s_path.push(shape),
-                        TuctosinHeader::Sref => gds2_model.s_sref.push(shape),
-                        TuctosinHeader::Aref => gds2_model.s_aref.push(shape),
-                        TuctosinHeader::Text => gds2_model.s_text.push(shape),
-                        TuctosinHeader::Node => gds2_model.s_node.push(shape),
-                        TuctosinHeader::Box => gds2_model.s_box.push(shape),
-                    },
-                    GDSIIVariant::FileEnd => {
-                        // summerize
-                        // gds2_model.summerize();
-                        break;
-                        // early return
-                    }
-                    _ => {}
-                }
+/// Options of the `_with` entry points such as [`parse_gds2_with`](crate::parse_gds2_with).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// keep records outside the Stream Format table as [`RawRecord`]s
+    /// instead of failing with [`GDSIIErrorKind::UnknownRecord`]
+    pub keep_unknown: bool,
+}
+
+pub fn gds2_parser(s: &[u8]) -> std::result::Result<Library, GDSIIErrorKind> {
+    gds2_parser_with(s, &ParseOptions::default())
+}
+
+pub fn gds2_parser_with(
+    s: &[u8],
+    options: &ParseOptions,
+) -> std::result::Result<Library, GDSIIErrorKind> {
+    gds2_read(s, options)
+}
+
+/// Build a [`Library`] from the records of `reader`.
+pub fn gds2_read<R: Read>(
+    reader: R,
+    options: &ParseOptions,
+) -> std::result::Result<Library, GDSIIErrorKind> {
+    let mut builder = LibraryBuilder::default();
+    let mut records = RecordReader::with_options(reader, *options);
+    // main process, stops at ENDLIB, the block padding after it is kept as is
+    while !builder.is_finished() {
+        match records.next_record()? {
+            Some((offset, variant)) => builder.push(offset, variant)?,
+            None => {
+                return Err(GDSIIErrorKind::UnexpectedEof {
+                    offset: records.offset(),
+                    structure: builder.structure_name(),
+                })
             }
-            Ok(gds2_model)
         }
-        Err(_) => Err(GDSIIErrorKind::InvalidGDSII),
+    }
+    let mut library = builder.finish();
+    records.into_inner().read_to_end(&mut library.tail)?;
+    Ok(library)
+}
+
+// locate a parse failure inside `record`, which starts at `offset` of the
+// stream
+fn to_error_kind(
+    record: &[u8],
+    offset: usize,
+    e: nom::Err<ParseGDSIIError<&[u8]>>,
+    structure: Option<String>,
+) -> GDSIIErrorKind {
+    let at = |input: &[u8]| offset + record.len() - input.len();
+    let e = match e {
+        nom::Err::Incomplete(_) => return GDSIIErrorKind::UnexpectedEof { offset, structure },
+        nom::Err::Error(e) | nom::Err::Failure(e) => e,
+    };
+    match e {
+        ParseGDSIIError::InvalidLength {
+            input,
+            d_type,
+            expected,
+            actual,
+        } => GDSIIErrorKind::InvalidLength {
+            offset: at(input),
+            record: record_name(d_type[0]),
+            expected,
+            actual,
+            structure,
+        },
+        ParseGDSIIError::InvalidRecordSize(input, size) => GDSIIErrorKind::InvalidRecordSize {
+            offset: at(input),
+            size,
+            structure,
+        },
+        ParseGDSIIError::UnknownRecord(input, d_type) => GDSIIErrorKind::UnknownRecord {
+            offset: at(input),
+            rtype: d_type[0],
+            dtype: d_type[1],
+            structure,
+        },
+        ParseGDSIIError::Utf8Error(input, d_type) => GDSIIErrorKind::InvalidString {
+            offset: at(input),
+            record: record_name(d_type[0]),
+            structure,
+        },
+        ParseGDSIIError::Nom(_, _) => GDSIIErrorKind::InvalidGDSII,
     }
 }
@@ -0,0 +1,158 @@
+use super::variant_parser::{raw_record, variant_parser};
+use super::{to_error_kind, ParseGDSIIError, ParseOptions};
+use crate::error::GDSIIErrorKind;
+use crate::model::*;
+use byteorder::{BigEndian, ByteOrder};
+use std::io::{ErrorKind, Read};
+
+/// Pull-based reader yielding one typed record at a time.
+///
+/// Only the record being decoded is held in memory, so any [`Read`] works:
+/// files, stdin, pipes or decompressing readers. Reading stops after
+/// ENDLIB; [`RecordReader::into_inner`] gives back the reader positioned at
+/// the padding that follows it.
+///
+/// ```no_run
+/// use gds2_io::{GDSIIVariant, RecordReader};
+///
+/// let file = std::fs::File::open("chip.gds")?;
+/// let mut boundaries = 0;
+/// for record in RecordReader::new(std::io::BufReader::new(file)) {
+///     if let (_, GDSIIVariant::TuctosinHeader(h)) = record? {
+///         boundaries += (h.name() == "BOUNDARY") as usize;
+///     }
+/// }
+/// # Ok::<(), gds2_io::GDSIIErrorKind>(())
+/// ```
+pub struct RecordReader<R: Read> {
+    reader: R,
+    options: ParseOptions,
+    // byte offset of the next record
+    offset: usize,
+    // the record being decoded, header included, reused between records
+    buf: Vec<u8>,
+    // STRNAME of the open structure, for error context
+    structure: Option<String>,
+    done: bool,
+}
+
+impl<R: Read> RecordReader<R> {
+    pub fn new(reader: R) -> Self {
+        RecordReader::with_options(reader, ParseOptions::default())
+    }
+
+    pub fn with_options(reader: R, options: ParseOptions) -> Self {
+        RecordReader {
+            reader,
+            options,
+            offset: 0,
+            buf: Vec::with_capacity(4),
+            structure: None,
+            done: false,
+        }
+    }
+
+    /// byte offset of the next record
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// name of the structure the last record belongs to
+    pub fn structure_name(&self) -> Option<&str> {
+        self.structure.as_deref()
+    }
+
+    /// the underlying reader, positioned after the last record read
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Next record with its byte offset, `None` once ENDLIB has been read or
+    /// the stream ends cleanly between two records.
+    pub fn next_record(
+        &mut self,
+    ) -> std::result::Result<Option<(usize, GDSIIVariant)>, GDSIIErrorKind> {
+        if self.done {
+            return Ok(None);
+        }
+        match self.read_record() {
+            Ok(Some(record)) => Ok(Some(record)),
+            other => {
+                self.done = true;
+                other
+            }
+        }
+    }
+
+    fn read_record(
+        &mut self,
+    ) -> std::result::Result<Option<(usize, GDSIIVariant)>, GDSIIErrorKind> {
+        let offset = self.offset;
+        self.buf.resize(4, 0);
+        match read_full(&mut self.reader, &mut self.buf)? {
+            0 => return Ok(None),
+            4 => {}
+            _ => return Err(self.eof(offset)),
+        }
+        // an invalid size is reported by the record parser below
+        let size = BigEndian::read_u16(&self.buf) as usize;
+        if size > 4 && size.is_multiple_of(2) {
+            self.buf.resize(size, 0);
+            if read_full(&mut self.reader, &mut self.buf[4..])? != size - 4 {
+                return Err(self.eof(offset));
+            }
+        }
+        let record = self.buf.as_slice();
+        let parsed = match variant_parser(record) {
+            Err(nom::Err::Failure(ParseGDSIIError::UnknownRecord(..)))
+                if self.options.keep_unknown =>
+            {
+                raw_record(record).map(|(rest, raw)| (rest, GDSIIVariant::Raw(raw)))
+            }
+            parsed => parsed,
+        };
+        let variant = match parsed {
+            Ok((_, variant)) => variant,
+            Err(e) => return Err(to_error_kind(record, offset, e, self.structure.clone())),
+        };
+        self.offset += record.len();
+        match &variant {
+            GDSIIVariant::ModuleHeader(ModuleHeader::StrName(name)) => {
+                self.structure = Some(name.clone())
+            }
+            GDSIIVariant::ModuleEnd => self.structure = None,
+            GDSIIVariant::FileEnd => self.done = true,
+            _ => {}
+        }
+        Ok(Some((offset, variant)))
+    }
+
+    fn eof(&self, offset: usize) -> GDSIIErrorKind {
+        GDSIIErrorKind::UnexpectedEof {
+            offset,
+            structure: self.structure.clone(),
+        }
+    }
+}
+
+impl<R: Read> Iterator for RecordReader<R> {
+    type Item = std::result::Result<(usize, GDSIIVariant), GDSIIErrorKind>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_record().transpose()
+    }
+}
+
+// fill `buf` as far as the stream allows, returning the bytes read
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
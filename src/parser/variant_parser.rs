@@ -1,301 +1,388 @@
 use super::basic::*;
-use super::ParseGDIIRes;
+use super::{ParseGDIIRes, ParseGDSIIError};
+use crate::error::ExpectedLength::{self, *};
 use crate::model::*;
-use byteorder::{BigEndian, ByteOrder, ReadBytesExt};
+use byteorder::{BigEndian, ByteOrder};
 use nom::bytes::streaming::take;
 
-use std::io::Cursor;
-pub(super) fn variant_parser(s: &[u8]) -> ParseGDIIRes<&[u8], GDSIIVariant> {
-    let (s, size) = take_size(s)?;
-    let (s, d_type) = take_type(s)?;
-    // data size
+type RecordCheck<'a, T> = Result<T, nom::Err<ParseGDSIIError<&'a [u8]>>>;
 
-    // FIXME there is bug when size == 0
-    if size < 4usize {
-        return Ok((s, GDSIIVariant::Eof));
+// reject a record whose data length does not fit its record type
+fn check_len<'a>(
+    input: &'a [u8],
+    d_type: [u8; 2],
+    data: &[u8],
+    expected: ExpectedLength,
+) -> RecordCheck<'a, ()> {
+    if expected.accepts(data.len()) {
+        Ok(())
+    } else {
+        Err(nom::Err::Failure(ParseGDSIIError::InvalidLength {
+            input,
+            d_type,
+            expected,
+            actual: data.len(),
+        }))
+    }
+}
+
+// ASCII record data without its NUL padding
+fn gds_string<'a>(input: &'a [u8], d_type: [u8; 2], data: &[u8]) -> RecordCheck<'a, String> {
+    String::from_utf8(strip_pad(data).to_vec())
+        .map_err(|_| nom::Err::Failure(ParseGDSIIError::Utf8Error(input, d_type)))
+}
+
+// REFLIBS and FONTS hold several names, each NUL padded to 44 bytes
+fn fixed_strings<'a>(
+    input: &'a [u8],
+    d_type: [u8; 2],
+    data: &[u8],
+) -> RecordCheck<'a, Vec<String>> {
+    data.chunks(44)
+        .map(|c| {
+            let end = c.iter().rposition(|b| *b != 0).map_or(0, |i| i + 1);
+            String::from_utf8(c[..end].to_vec())
+                .map_err(|_| nom::Err::Failure(ParseGDSIIError::Utf8Error(input, d_type)))
+        })
+        .collect()
+}
+
+fn i16_array(data: &[u8]) -> Vec<i16> {
+    data.chunks_exact(2).map(BigEndian::read_i16).collect()
+}
+
+fn i32_array(data: &[u8]) -> Vec<i32> {
+    data.chunks_exact(4).map(BigEndian::read_i32).collect()
+}
+
+/// Parse one record, errors carry the input at the start of the record.
+pub(super) fn variant_parser(input: &[u8]) -> ParseGDIIRes<&[u8], GDSIIVariant> {
+    let (s, size) = take_size(input)?;
+    let (s, d_type) = take_type(s)?;
+    // data size, records always hold an even number of bytes
+    if size < 4usize || !size.is_multiple_of(2) {
+        return Err(nom::Err::Failure(ParseGDSIIError::InvalidRecordSize(
+            input, size,
+        )));
     }
     let d_size = size - 4usize;
 
     let (s, data) = take(d_size)(s)?;
-    // println!("{}", d_size);
     let module_header = match d_type {
         // File Header
         [0x00, 0x02] => {
-            assert!(d_size == 2usize, "mismatch header length in file header");
-            let header = BigEndian::read_i16(&data);
+            check_len(input, d_type, data, Exact(2))?;
+            let header = BigEndian::read_i16(data);
             GDSIIVariant::FileHeader(FileHeader::Header(header))
         }
         [0x01, 0x02] => {
-            assert!(d_size == 24usize, "mismatch data bgn length in file header");
+            check_len(input, d_type, data, Exact(24))?;
             let mut bgn = [0i16; 12];
-            BigEndian::read_i16_into(&data, &mut bgn);
+            BigEndian::read_i16_into(data, &mut bgn);
             GDSIIVariant::FileHeader(FileHeader::BgnLib(bgn))
         }
         [0x02, 0x06] => {
-            // let escaped_ascii = data
-            //     .iter()
-            //     .map(|v| ascii::escape_default(*v).next().unwrap())
-            //     .collect();
-            GDSIIVariant::FileHeader(FileHeader::LibName(
-                String::from_utf8(data.to_vec()).unwrap(),
-            ))
+            GDSIIVariant::FileHeader(FileHeader::LibName(gds_string(input, d_type, data)?))
         }
         [0x1F, 0x06] => {
-            assert!(d_size == 90usize, "mismatch reflib length in file header");
-            // let escaped_ascii = data
-            //     .iter()
-            //     .map(|v| ascii::escape_default(*v).next().unwrap())
-            //     .collect();
-            GDSIIVariant::FileHeader(FileHeader::RefLibs(
-                String::from_utf8(data.to_vec()).unwrap(),
-            ))
+            check_len(input, d_type, data, MultipleOf(44))?;
+            GDSIIVariant::FileHeader(FileHeader::RefLibs(fixed_strings(input, d_type, data)?))
         }
         [0x20, 0x06] => {
-            assert!(d_size == 176usize, "mismatch font length in file header");
-            // let escaped_ascii = data
-            //     .iter()
-            //     .map(|v| ascii::escape_default(*v).next().unwrap())
-            //     .collect();
-            GDSIIVariant::FileHeader(FileHeader::Fonts(String::from_utf8(data.to_vec()).unwrap()))
+            check_len(input, d_type, data, Exact(176))?;
+            GDSIIVariant::FileHeader(FileHeader::Fonts(fixed_strings(input, d_type, data)?))
         }
         [0x23, 0x06] => {
-            assert!(
-                d_size == 44usize,
-                "mismatch attr_table length in file header"
-            );
-            GDSIIVariant::FileHeader(FileHeader::AttrTable(
-                String::from_utf8_lossy(data).to_string(),
-            ))
+            check_len(input, d_type, data, AtMost(44))?;
+            GDSIIVariant::FileHeader(FileHeader::AttrTable(gds_string(input, d_type, data)?))
         }
         [0x22, 0x02] => {
-            assert!(
-                d_size == 2usize,
-                "mismatch generation length in file header"
-            );
-            let generation = BigEndian::read_i16(&data);
+            check_len(input, d_type, data, Exact(2))?;
+            let generation = BigEndian::read_i16(data);
             GDSIIVariant::FileHeader(FileHeader::Generations(generation))
         }
         [0x36, 0x02] => {
-            assert!(d_size == 2usize, "mismatch format length in file header");
-            let format = BigEndian::read_i16(&data);
+            check_len(input, d_type, data, Exact(2))?;
+            let format = BigEndian::read_i16(data);
             GDSIIVariant::FileHeader(FileHeader::Format(format))
         }
         [0x37, 0x06] => {
-            GDSIIVariant::FileHeader(FileHeader::Mask(String::from_utf8_lossy(data).to_string()))
+            GDSIIVariant::FileHeader(FileHeader::Mask(gds_string(input, d_type, data)?))
         }
         [0x38, 0x00] => {
-            assert!(d_size == 0usize, "mismatch mask length in file header");
+            check_len(input, d_type, data, Exact(0))?;
             GDSIIVariant::FileHeader(FileHeader::EndMask)
         }
         [0x03, 0x05] => {
-            assert!(d_size == 16usize, "mismatch units length in file header");
-            let mut units = [0.0f64; 2];
-            BigEndian::read_f64_into(&data, &mut units);
+            check_len(input, d_type, data, Exact(16))?;
+            let units = [real8(&data[..8]), real8(&data[8..])];
             GDSIIVariant::FileHeader(FileHeader::Units(units))
         }
         // File End
         [0x04, 0x00] => {
-            assert!(d_size == 0usize, "there should be no data in file tile");
+            check_len(input, d_type, data, Exact(0))?;
             GDSIIVariant::FileEnd
         }
         // Module Header
         [0x05, 0x02] => {
-            assert!(
-                d_size == 24usize,
-                "mismatch bgn_str length in module header"
-            );
+            check_len(input, d_type, data, Exact(24))?;
             let mut bgn = [0i16; 12];
-            BigEndian::read_i16_into(&data, &mut bgn);
+            BigEndian::read_i16_into(data, &mut bgn);
             GDSIIVariant::ModuleHeader(ModuleHeader::BgnStr(bgn))
         }
         [0x06, 0x06] => {
-            // let escaped_ascii = data
-            //     .iter()
-            //     .map(|v| ascii::escape_default(*v).next().unwrap())
-            //     .collect();
-            GDSIIVariant::ModuleHeader(ModuleHeader::StrName(
-                String::from_utf8(data.to_vec()).unwrap(),
-            ))
+            GDSIIVariant::ModuleHeader(ModuleHeader::StrName(gds_string(input, d_type, data)?))
         }
         // Module End
         [0x07, 0x00] => {
-            assert!(d_size == 0usize, "there should be no data in module tile");
+            check_len(input, d_type, data, Exact(0))?;
             GDSIIVariant::ModuleEnd
         }
         // Tuctosin header
         [0x08, 0x00] => {
-            assert!(
-                d_size == 0usize,
-                "there should be no data in tuctosin header"
-            );
+            check_len(input, d_type, data, Exact(0))?;
             GDSIIVariant::TuctosinHeader(TuctosinHeader::Boundary)
         }
         [0x09, 0x00] => {
-            assert!(
-                d_size == 0usize,
-                "there should be no data in tuctosin header"
-            );
+            check_len(input, d_type, data, Exact(0))?;
             GDSIIVariant::TuctosinHeader(TuctosinHeader::Path)
         }
         [0x0A, 0x00] => {
-            assert!(
-                d_size == 0usize,
-                "there should be no data in tuctosin header"
-            );
+            check_len(input, d_type, data, Exact(0))?;
             GDSIIVariant::TuctosinHeader(TuctosinHeader::Sref)
         }
         [0x0B, 0x00] => {
-            assert!(
-                d_size == 0usize,
-                "there should be no data in tuctosin header"
-            );
+            check_len(input, d_type, data, Exact(0))?;
             GDSIIVariant::TuctosinHeader(TuctosinHeader::Aref)
         }
         [0x0C, 0x00] => {
-            assert!(
-                d_size == 0usize,
-                "there should be no data in tuctosin header"
-            );
+            check_len(input, d_type, data, Exact(0))?;
             GDSIIVariant::TuctosinHeader(TuctosinHeader::Text)
         }
         [0x15, 0x00] => {
-            assert!(
-                d_size == 0usize,
-                "there should be no data in tuctosin header"
-            );
+            check_len(input, d_type, data, Exact(0))?;
             GDSIIVariant::TuctosinHeader(TuctosinHeader::Node)
         }
         [0x2D, 0x00] => {
-            assert!(
-                d_size == 0usize,
-                "there should be no data in tuctosin header"
-            );
+            check_len(input, d_type, data, Exact(0))?;
             GDSIIVariant::TuctosinHeader(TuctosinHeader::Box)
         }
         // Tuctosin End
         [0x11, 0x00] => {
-            assert!(d_size == 0usize, "there should be no data in tuctosin tile");
+            check_len(input, d_type, data, Exact(0))?;
             GDSIIVariant::TuctosinEnd
         }
         // Tuctosin Body
         [0x26, 0x01] => {
-            assert!(
-                d_size == 2usize,
-                "mismatch elf_flags length in tuctosin body"
-            );
-            let elfflags = BigEndian::read_i16(&data);
+            check_len(input, d_type, data, Exact(2))?;
+            let elfflags = BigEndian::read_i16(data);
             GDSIIVariant::Tuctosin(Tuctosin::ElfFlags(elfflags))
         }
         [0x2F, 0x03] => {
-            assert!(d_size == 4usize, "mismatch plex length in tuctosin body");
+            check_len(input, d_type, data, Exact(4))?;
             // let byted = data.as_bytes();
-            let plex = BigEndian::read_i32(&data);
+            let plex = BigEndian::read_i32(data);
             GDSIIVariant::Tuctosin(Tuctosin::Plex(plex))
         }
         [0x0D, 0x02] => {
-            assert!(d_size == 2usize, "mismatch layer length in tuctosin body");
-            let layer = BigEndian::read_i16(&data);
+            check_len(input, d_type, data, Exact(2))?;
+            let layer = BigEndian::read_i16(data);
             GDSIIVariant::Tuctosin(Tuctosin::Layer(layer))
         }
         [0x0E, 0x02] => {
-            assert!(
-                d_size == 2usize,
-                "mismatch data_type length in tuctosin body"
-            );
-            let data_type = BigEndian::read_i16(&data);
+            check_len(input, d_type, data, Exact(2))?;
+            let data_type = BigEndian::read_i16(data);
             GDSIIVariant::Tuctosin(Tuctosin::DataType(data_type))
         }
         // tuctosin body
-        // TODO: seek better solution
         [0x10, 0x03] => {
-            assert!(d_size % 8 == 0, "mismatch x_y length in tuctosin body");
-            let mut reader = Cursor::new(data);
-            let mut shapes = vec![];
-            let shape_len = d_size / 8;
-            for _ in 0..shape_len {
-                let x = reader.read_i32::<BigEndian>().unwrap();
-                let y = reader.read_i32::<BigEndian>().unwrap();
-                shapes.push((x, y));
-            }
+            check_len(input, d_type, data, MultipleOf(8))?;
+            let shapes = data
+                .chunks_exact(8)
+                .map(|c| (BigEndian::read_i32(&c[..4]), BigEndian::read_i32(&c[4..])))
+                .collect();
             GDSIIVariant::Tuctosin(Tuctosin::Xy(shapes))
         }
         [0x21, 0x02] => {
-            assert!(
-                d_size == 2usize,
-                "mismatch path_type length in tuctosin body"
-            );
-            let path_type = BigEndian::read_i16(&data);
+            check_len(input, d_type, data, Exact(2))?;
+            let path_type = BigEndian::read_i16(data);
             GDSIIVariant::Tuctosin(Tuctosin::PathType(path_type))
         }
         [0x0F, 0x03] => {
-            assert!(d_size == 4usize, "mismatch width length in tuctosin body");
-            let width = BigEndian::read_i32(&data);
+            check_len(input, d_type, data, Exact(4))?;
+            let width = BigEndian::read_i32(data);
             GDSIIVariant::Tuctosin(Tuctosin::Width(width))
         }
-        [0x12, 0x06] => {
-            GDSIIVariant::Tuctosin(Tuctosin::Sname(String::from_utf8_lossy(data).to_string()))
-        }
+        [0x12, 0x06] => GDSIIVariant::Tuctosin(Tuctosin::Sname(gds_string(input, d_type, data)?)),
         [0x1A, 0x01] => {
-            assert!(
-                d_size == 2usize,
-                "mismatch path_type length in tuctosin body"
-            );
-            let strans = BigEndian::read_i16(&data);
-            GDSIIVariant::Tuctosin(Tuctosin::PathType(strans))
+            check_len(input, d_type, data, Exact(2))?;
+            let strans = BigEndian::read_i16(data);
+            GDSIIVariant::Tuctosin(Tuctosin::Strans(strans))
         }
         [0x1B, 0x05] => {
-            assert!(d_size == 8usize, "mismatch mag length in tuctosin body");
-            let mag = BigEndian::read_i64(&data);
+            check_len(input, d_type, data, Exact(8))?;
+            let mag = real8(data);
             GDSIIVariant::Tuctosin(Tuctosin::Mag(mag))
         }
         [0x1C, 0x05] => {
-            assert!(d_size == 8usize, "mismatch angle length in tuctosin body");
-            let angle = BigEndian::read_i64(&data);
+            check_len(input, d_type, data, Exact(8))?;
+            let angle = real8(data);
             GDSIIVariant::Tuctosin(Tuctosin::Angle(angle))
         }
         [0x13, 0x02] => {
-            assert!(d_size == 4usize, "mismatch col_row length in tuctosin body");
-            // let byted = data.as_bytes();
-            let col = BigEndian::read_i16(&data);
-            let row = BigEndian::read_i16(&data);
+            check_len(input, d_type, data, Exact(4))?;
+            let col = BigEndian::read_i16(&data[..2]);
+            let row = BigEndian::read_i16(&data[2..]);
             GDSIIVariant::Tuctosin(Tuctosin::ColRow((col, row)))
         }
         [0x16, 0x02] => {
-            assert!(
-                d_size == 2usize,
-                "mismatch text_type length in tuctosin body"
-            );
-            let text_type = BigEndian::read_i16(&data);
+            check_len(input, d_type, data, Exact(2))?;
+            let text_type = BigEndian::read_i16(data);
             GDSIIVariant::Tuctosin(Tuctosin::TextType(text_type))
         }
         [0x17, 0x01] => {
-            assert!(
-                d_size == 2usize,
-                "mismatch persentation length in tuctosin body"
-            );
-            let pers = BigEndian::read_i16(&data);
+            check_len(input, d_type, data, Exact(2))?;
+            let pers = BigEndian::read_i16(data);
             GDSIIVariant::Tuctosin(Tuctosin::Persentation(pers))
         }
-        [0x19, 0x06] => GDSIIVariant::Tuctosin(Tuctosin::AsciiString(
-            String::from_utf8_lossy(data).to_string(),
-        )),
+        [0x19, 0x06] => {
+            GDSIIVariant::Tuctosin(Tuctosin::AsciiString(gds_string(input, d_type, data)?))
+        }
         [0x2A, 0x02] => {
-            assert!(
-                d_size == 2usize,
-                "mismatch node_type length in tuctosin body"
-            );
-            let node_type = BigEndian::read_i16(&data);
+            check_len(input, d_type, data, Exact(2))?;
+            let node_type = BigEndian::read_i16(data);
             GDSIIVariant::Tuctosin(Tuctosin::NodeType(node_type))
         }
         [0x2E, 0x02] => {
-            assert!(
-                d_size == 2usize,
-                "mismatch box_type length in tuctosin body"
-            );
-            let box_type = BigEndian::read_i16(&data);
+            check_len(input, d_type, data, Exact(2))?;
+            let box_type = BigEndian::read_i16(data);
             GDSIIVariant::Tuctosin(Tuctosin::BoxType(box_type))
         }
-        _ => unreachable!(),
+        [0x30, 0x03] => {
+            check_len(input, d_type, data, Exact(4))?;
+            let bgnextn = BigEndian::read_i32(data);
+            GDSIIVariant::Tuctosin(Tuctosin::BgnExtn(bgnextn))
+        }
+        [0x31, 0x03] => {
+            check_len(input, d_type, data, Exact(4))?;
+            let endextn = BigEndian::read_i32(data);
+            GDSIIVariant::Tuctosin(Tuctosin::EndExtn(endextn))
+        }
+        [0x2B, 0x02] => {
+            check_len(input, d_type, data, Exact(2))?;
+            let attr = BigEndian::read_i16(data);
+            GDSIIVariant::Tuctosin(Tuctosin::PropAttr(attr))
+        }
+        [0x2C, 0x06] => {
+            GDSIIVariant::Tuctosin(Tuctosin::PropValue(gds_string(input, d_type, data)?))
+        }
+        [0x39, 0x02] => {
+            check_len(input, d_type, data, Exact(2))?;
+            GDSIIVariant::FileHeader(FileHeader::LibDirSize(BigEndian::read_i16(data)))
+        }
+        [0x3A, 0x06] => {
+            GDSIIVariant::FileHeader(FileHeader::SrfName(gds_string(input, d_type, data)?))
+        }
+        [0x3B, 0x02] => {
+            check_len(input, d_type, data, MultipleOf(6))?;
+            let acl = i16_array(data)
+                .chunks_exact(3)
+                .map(|c| (c[0], c[1], c[2]))
+                .collect();
+            GDSIIVariant::FileHeader(FileHeader::LibSecur(acl))
+        }
+        [0x24, 0x06] => {
+            GDSIIVariant::FileHeader(FileHeader::StypTable(gds_string(input, d_type, data)?))
+        }
+        [0x32, 0x02] => {
+            check_len(input, d_type, data, Exact(2))?;
+            GDSIIVariant::FileHeader(FileHeader::TapeNum(BigEndian::read_i16(data)))
+        }
+        [0x33, 0x02] => {
+            check_len(input, d_type, data, Exact(12))?;
+            let mut code = [0i16; 6];
+            BigEndian::read_i16_into(data, &mut code);
+            GDSIIVariant::FileHeader(FileHeader::TapeCode(code))
+        }
+        [0x35, 0x03] => {
+            check_len(input, d_type, data, MultipleOf(4))?;
+            GDSIIVariant::FileHeader(FileHeader::Reserved(i32_array(data)))
+        }
+        [0x34, 0x01] => {
+            check_len(input, d_type, data, Exact(2))?;
+            GDSIIVariant::ModuleHeader(ModuleHeader::StrClass(BigEndian::read_i16(data)))
+        }
+        [0x25, 0x02] => {
+            check_len(input, d_type, data, Exact(2))?;
+            GDSIIVariant::ModuleHeader(ModuleHeader::StrType(BigEndian::read_i16(data)))
+        }
+        [0x14, 0x00] => {
+            check_len(input, d_type, data, Exact(0))?;
+            GDSIIVariant::TuctosinHeader(TuctosinHeader::TextNode)
+        }
+        [0x18, 0x02] => {
+            check_len(input, d_type, data, Exact(2))?;
+            GDSIIVariant::Tuctosin(Tuctosin::Spacing(BigEndian::read_i16(data)))
+        }
+        [0x1D, 0x03] => {
+            check_len(input, d_type, data, Exact(4))?;
+            GDSIIVariant::Tuctosin(Tuctosin::UInteger(BigEndian::read_i32(data)))
+        }
+        [0x1E, 0x06] => GDSIIVariant::Tuctosin(Tuctosin::UString(gds_string(input, d_type, data)?)),
+        [0x27, 0x03] => {
+            check_len(input, d_type, data, Exact(4))?;
+            GDSIIVariant::Tuctosin(Tuctosin::ElKey(BigEndian::read_i32(data)))
+        }
+        [0x28, 0x02] => {
+            check_len(input, d_type, data, Exact(2))?;
+            GDSIIVariant::Tuctosin(Tuctosin::LinkType(BigEndian::read_i16(data)))
+        }
+        [0x29, 0x03] => {
+            check_len(input, d_type, data, MultipleOf(4))?;
+            GDSIIVariant::Tuctosin(Tuctosin::LinkKeys(i32_array(data)))
+        }
+        [0x3C..=0x45, 0x00] => {
+            check_len(input, d_type, data, Exact(0))?;
+            let marker = match d_type[0] {
+                0x3C => Tuctosin::Border,
+                0x3D => Tuctosin::SoftFence,
+                0x3E => Tuctosin::HardFence,
+                0x3F => Tuctosin::SoftWire,
+                0x40 => Tuctosin::HardWire,
+                0x41 => Tuctosin::PathPort,
+                0x42 => Tuctosin::NodePort,
+                0x43 => Tuctosin::UserConstraint,
+                0x44 => Tuctosin::SpacerError,
+                _ => Tuctosin::Contact,
+            };
+            GDSIIVariant::Tuctosin(marker)
+        }
+        _ => {
+            return Err(nom::Err::Failure(ParseGDSIIError::UnknownRecord(
+                input, d_type,
+            )))
+        }
     };
     Ok((s, module_header))
 }
+
+/// Take one record verbatim, used for record types `variant_parser` rejects
+/// as unknown.
+pub(super) fn raw_record(input: &[u8]) -> ParseGDIIRes<&[u8], RawRecord> {
+    let (s, size) = take_size(input)?;
+    let (s, d_type) = take_type(s)?;
+    if size < 4usize || !size.is_multiple_of(2) {
+        return Err(nom::Err::Failure(ParseGDSIIError::InvalidRecordSize(
+            input, size,
+        )));
+    }
+    let (s, data) = take(size - 4usize)(s)?;
+    Ok((
+        s,
+        RawRecord {
+            rtype: d_type[0],
+            dtype: d_type[1],
+            bytes: data.to_vec(),
+        },
+    ))
+}
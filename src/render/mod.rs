@@ -0,0 +1,173 @@
+mod raster;
+mod style;
+mod svg;
+
+pub use raster::{Image, RasterOptions};
+pub use style::{Color, Hatch, LayerStyle, LayerStyles};
+pub use svg::SvgOptions;
+
+use crate::error::GDSIIErrorKind;
+use crate::geometry::{BBox, BBoxOptions};
+use crate::hierarchy::{BBoxCache, FlattenOptions};
+use crate::model::*;
+use std::collections::BTreeMap;
+
+// colour of the boxes of unexpanded references
+const FRAME: Color = Color::new(0x80, 0x80, 0x80);
+
+/// Part of a cell to draw and the size of the picture.
+///
+/// The window is scaled to fit the picture and centred in it; `None`
+/// shows the whole cell with a small margin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Viewport {
+    /// area in database units
+    pub window: Option<BBox>,
+    /// picture size in pixels
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Viewport {
+            window: None,
+            width: 800,
+            height: 800,
+        }
+    }
+}
+
+// database units to pixels, y pointing down
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Projection {
+    pub(crate) scale: f64,
+    x0: f64,
+    y1: f64,
+    dx: f64,
+    dy: f64,
+}
+
+impl Projection {
+    fn new(window: &BBox, width: u32, height: u32) -> Self {
+        let w = window.width().max(1) as f64;
+        let h = window.height().max(1) as f64;
+        let scale = (width as f64 / w).min(height as f64 / h);
+        Projection {
+            scale,
+            x0: window.min.0 as f64,
+            y1: window.max.1 as f64,
+            dx: (width as f64 - w * scale) / 2.0,
+            dy: (height as f64 - h * scale) / 2.0,
+        }
+    }
+
+    pub(crate) fn apply(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        (
+            self.dx + (x - self.x0) * self.scale,
+            self.dy + (self.y1 - y) * self.scale,
+        )
+    }
+}
+
+// what a renderer draws on one layer
+pub(crate) enum Item {
+    // vertices in database units, not closed
+    Polygon(Vec<(f64, f64)>),
+    Text(Text),
+}
+
+// a cell flattened for drawing
+pub(crate) struct Scene {
+    pub(crate) projection: Projection,
+    pub(crate) layers: BTreeMap<(i16, i16), Vec<Item>>,
+    // boxes of references left unexpanded, with the structure they place
+    pub(crate) frames: Vec<(BBox, String)>,
+}
+
+impl Scene {
+    // the elements of `cell` inside the viewport, references expanded down
+    // to `max_depth`
+    pub(crate) fn new(
+        library: &Library,
+        cell: &str,
+        max_depth: Option<usize>,
+        styles: &LayerStyles,
+        viewport: &Viewport,
+    ) -> Result<Scene, GDSIIErrorKind> {
+        let mut cache = BBoxCache::new(library, &BBoxOptions { include_text: true });
+        let window = match viewport.window {
+            Some(window) => window,
+            None => {
+                let bbox = cache.structure(cell)?.unwrap_or(BBox::new((0, 0), (1, 1)));
+                let margin = (bbox.width().max(bbox.height()) + 1) / 50;
+                // the margin stops at the ends of the coordinate range
+                let grow =
+                    |v: i32, d: i64| (v as i64 + d).clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+                BBox::new(
+                    (grow(bbox.min.0, -margin), grow(bbox.min.1, -margin)),
+                    (grow(bbox.max.0, margin), grow(bbox.max.1, margin)),
+                )
+            }
+        };
+        let options = FlattenOptions {
+            max_depth,
+            keep_text: true,
+            keep_node: false,
+        };
+        let mut layers: BTreeMap<(i16, i16), Vec<Item>> = BTreeMap::new();
+        let mut frames = vec![];
+        for element in library.flat_elements(cell, &options)? {
+            let element = element?;
+            let item = match &element {
+                Element::Boundary(Boundary { points, .. })
+                | Element::Box(GdsBox { points, .. }) => {
+                    let mut points: Vec<(f64, f64)> =
+                        points.iter().map(|p| (p.0 as f64, p.1 as f64)).collect();
+                    if points.len() > 1 && points.first() == points.last() {
+                        points.pop();
+                    }
+                    Item::Polygon(points)
+                }
+                Element::Path(path) => Item::Polygon(path.outline()),
+                Element::Text(text) => Item::Text(text.clone()),
+                Element::SRef(SRef { sname, .. }) | Element::ARef(ARef { sname, .. }) => {
+                    if let Some(bbox) = cache.placed(&element, cell)? {
+                        if bbox.intersects(&window) {
+                            frames.push((bbox, sname.clone()));
+                        }
+                    }
+                    continue;
+                }
+                Element::Node(_) => continue,
+            };
+            let visible = match &item {
+                Item::Polygon(points) => points_bbox(points).is_some_and(|b| b.intersects(&window)),
+                Item::Text(text) => window.contains(text.origin),
+            };
+            let key = (element.layer().unwrap(), element.datatype().unwrap());
+            if visible && !styles.is_hidden(key.0, key.1) {
+                layers.entry(key).or_default().push(item);
+            }
+        }
+        Ok(Scene {
+            projection: Projection::new(&window, viewport.width, viewport.height),
+            layers,
+            frames,
+        })
+    }
+}
+
+// smallest box in whole database units around `points`
+fn points_bbox(points: &[(f64, f64)]) -> Option<BBox> {
+    let first = points.first()?;
+    let (mut min, mut max) = (*first, *first);
+    for (x, y) in points {
+        min = (min.0.min(*x), min.1.min(*y));
+        max = (max.0.max(*x), max.1.max(*y));
+    }
+    Some(BBox::new(
+        (min.0.floor() as i32, min.1.floor() as i32),
+        (max.0.ceil() as i32, max.1.ceil() as i32),
+    ))
+}
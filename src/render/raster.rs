@@ -0,0 +1,335 @@
+use super::{Color, Hatch, Item, LayerStyles, Scene, Viewport, FRAME};
+use crate::error::GDSIIErrorKind;
+use crate::model::Library;
+use std::io::Write;
+
+// sub-scanlines per pixel row for anti-aliasing
+const SUBSAMPLES: usize = 4;
+
+/// How [`Library::rasterize`] draws.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RasterOptions {
+    /// levels of SREF/AREF to expand, deeper references are drawn as
+    /// boxes; `None` expands all
+    pub max_depth: Option<usize>,
+    pub viewport: Viewport,
+    /// transparent when `None`
+    pub background: Option<Color>,
+}
+
+/// RGBA picture, 8 bits per channel, rows top to bottom.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    /// `width * height * 4` bytes, alpha not premultiplied
+    pub pixels: Vec<u8>,
+}
+
+// edge of a polygon in pixels, `y0 < y1`, `winding` +1 downwards
+struct Edge {
+    x0: f64,
+    y0: f64,
+    x1: f64,
+    y1: f64,
+    winding: i32,
+}
+
+// edges of polygons filled together with the non-zero rule
+#[derive(Default)]
+struct Edges(Vec<Edge>);
+
+impl Edges {
+    // `points` in pixels, turned counterclockwise so that overlapping
+    // polygons add up instead of cancelling
+    fn polygon(&mut self, points: &[(f64, f64)]) {
+        if points.len() < 3 {
+            return;
+        }
+        let area: f64 = (0..points.len())
+            .map(|i| {
+                let (a, b) = (points[i], points[(i + 1) % points.len()]);
+                a.0 * b.1 - b.0 * a.1
+            })
+            .sum();
+        let reverse = area < 0.0;
+        for i in 0..points.len() {
+            let (mut a, mut b) = (points[i], points[(i + 1) % points.len()]);
+            if reverse {
+                (a, b) = (b, a);
+            }
+            if a.1 == b.1 {
+                continue;
+            }
+            let (winding, (a, b)) = if a.1 < b.1 { (1, (a, b)) } else { (-1, (b, a)) };
+            self.0.push(Edge {
+                x0: a.0,
+                y0: a.1,
+                x1: b.0,
+                y1: b.1,
+                winding,
+            });
+        }
+    }
+
+    // a band of `width` pixels along every side of the closed `points`
+    fn outline(&mut self, points: &[(f64, f64)], width: f64) {
+        for i in 0..points.len() {
+            let (a, b) = (points[i], points[(i + 1) % points.len()]);
+            let length = (b.0 - a.0).hypot(b.1 - a.1);
+            if length == 0.0 {
+                continue;
+            }
+            let n = (
+                -(b.1 - a.1) / length * width / 2.0,
+                (b.0 - a.0) / length * width / 2.0,
+            );
+            self.polygon(&[
+                (a.0 + n.0, a.1 + n.1),
+                (b.0 + n.0, b.1 + n.1),
+                (b.0 - n.0, b.1 - n.1),
+                (a.0 - n.0, a.1 - n.1),
+            ]);
+        }
+    }
+
+    // share of every pixel inside, 0 to 1
+    fn coverage(mut self, width: usize, height: usize) -> Vec<f32> {
+        let mut coverage = vec![0f32; width * height];
+        self.0.sort_by(|a, b| a.y0.total_cmp(&b.y0));
+        let mut next = 0;
+        let mut active: Vec<&Edge> = vec![];
+        let mut crossings: Vec<(f64, i32)> = vec![];
+        let share = 1.0 / SUBSAMPLES as f32;
+        for row in 0..height {
+            let row_coverage = &mut coverage[row * width..(row + 1) * width];
+            for s in 0..SUBSAMPLES {
+                let y = row as f64 + (s as f64 + 0.5) / SUBSAMPLES as f64;
+                while next < self.0.len() && self.0[next].y0 <= y {
+                    active.push(&self.0[next]);
+                    next += 1;
+                }
+                active.retain(|e| e.y1 > y);
+                crossings.clear();
+                crossings.extend(active.iter().filter(|e| e.y0 <= y).map(|e| {
+                    let x = e.x0 + (y - e.y0) * (e.x1 - e.x0) / (e.y1 - e.y0);
+                    (x, e.winding)
+                }));
+                crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+                let mut winding = 0;
+                let mut start = 0.0;
+                for &(x, w) in &crossings {
+                    if winding == 0 {
+                        start = x;
+                    }
+                    winding += w;
+                    if winding == 0 {
+                        span(row_coverage, start, x, share);
+                    }
+                }
+            }
+        }
+        for c in &mut coverage {
+            *c = c.min(1.0);
+        }
+        coverage
+    }
+}
+
+// add `share` times the part of each pixel between `a` and `b`
+fn span(row: &mut [f32], a: f64, b: f64, share: f32) {
+    let (a, b) = (a.max(0.0), b.min(row.len() as f64));
+    if a >= b {
+        return;
+    }
+    let (first, last) = (a.floor() as usize, b.floor() as usize);
+    if first == last {
+        row[first] += (b - a) as f32 * share;
+        return;
+    }
+    row[first] += (first as f64 + 1.0 - a) as f32 * share;
+    for c in &mut row[first + 1..last] {
+        *c += share;
+    }
+    if last < row.len() {
+        row[last] += (b - last as f64) as f32 * share;
+    }
+}
+
+// whether the hatch draws the pixel, on the 8 pixel tiles of the SVG
+// patterns
+fn hatched(hatch: Hatch, x: usize, y: usize) -> bool {
+    let (x, y) = (x % 8, y % 8);
+    match hatch {
+        Hatch::Solid => true,
+        Hatch::Hollow => false,
+        Hatch::Diagonal => (x + y) % 8 == 7,
+        Hatch::BackDiagonal => x == y,
+        Hatch::Cross => (x + y) % 8 == 7 || x == y,
+        Hatch::Horizontal => y == 4,
+        Hatch::Vertical => x == 4,
+        Hatch::Dots => (3..5).contains(&x) && (3..5).contains(&y),
+    }
+}
+
+impl Image {
+    /// transparent black
+    pub fn new(width: u32, height: u32) -> Self {
+        Image {
+            width,
+            height,
+            pixels: vec![0; width as usize * height as usize * 4],
+        }
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let i = (y as usize * self.width as usize + x as usize) * 4;
+        self.pixels[i..i + 4].try_into().unwrap()
+    }
+
+    pub fn fill(&mut self, color: Color) {
+        for p in self.pixels.chunks_mut(4) {
+            p.copy_from_slice(&[color.r, color.g, color.b, 255]);
+        }
+    }
+
+    // `color` laid over the pixels at `alpha` times each pixel's value
+    fn blend(&mut self, color: Color, alpha: impl Fn(usize, usize) -> f32) {
+        let width = self.width as usize;
+        for (i, p) in self.pixels.chunks_mut(4).enumerate() {
+            let a = alpha(i % width, i / width);
+            if a <= 0.0 {
+                continue;
+            }
+            let below = p[3] as f32 / 255.0 * (1.0 - a);
+            let out = a + below;
+            for (c, v) in p[..3].iter_mut().zip([color.r, color.g, color.b]) {
+                *c = ((v as f32 * a + *c as f32 * below) / out).round() as u8;
+            }
+            p[3] = (out * 255.0).round() as u8;
+        }
+    }
+
+    /// PNG file of the image, deflated, no filtering
+    pub fn to_png(&self) -> Vec<u8> {
+        let mut data = vec![];
+        self.write_png(&mut data)
+            .expect("writing to a Vec cannot fail");
+        data
+    }
+
+    pub fn write_png<W: Write>(&self, mut w: W) -> Result<(), GDSIIErrorKind> {
+        let mut ihdr = vec![];
+        ihdr.extend(self.width.to_be_bytes());
+        ihdr.extend(self.height.to_be_bytes());
+        // 8 bits per channel, RGBA, deflate, adaptive filtering, no interlace
+        ihdr.extend([8, 6, 0, 0, 0]);
+        let row = self.width as usize * 4;
+        let mut raw = Vec::with_capacity((row + 1) * self.height as usize);
+        for line in self.pixels.chunks(row.max(1)).take(self.height as usize) {
+            raw.push(0);
+            raw.extend(line);
+        }
+        let idat = miniz_oxide::deflate::compress_to_vec_zlib(&raw, 6);
+        w.write_all(b"\x89PNG\r\n\x1a\n")?;
+        for (kind, data) in [(b"IHDR", &ihdr[..]), (b"IDAT", &idat[..]), (b"IEND", &[])] {
+            w.write_all(&(data.len() as u32).to_be_bytes())?;
+            let mut chunk = kind.to_vec();
+            chunk.extend(data);
+            w.write_all(&chunk)?;
+            w.write_all(&crc32(&chunk).to_be_bytes())?;
+        }
+        Ok(())
+    }
+
+    pub fn save_png<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), GDSIIErrorKind> {
+        let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        self.write_png(&mut file)?;
+        file.flush()?;
+        Ok(())
+    }
+}
+
+// CRC-32 of PNG chunks, ISO 3309
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for b in data {
+        crc ^= *b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+impl Library {
+    /// Draw the structure `cell` into an RGBA image.
+    ///
+    /// Layers are laid one over the other in ascending (layer, datatype)
+    /// order, each filled in its style's colour, hatch and opacity with
+    /// anti-aliased edges and outlined in its stroke colour. Shapes of one
+    /// layer merge, overlaps are not darker. Paths are drawn as their
+    /// [`outline`](crate::Path::outline); texts have no shape and are left
+    /// out.
+    pub fn rasterize(
+        &self,
+        cell: &str,
+        styles: &LayerStyles,
+        options: &RasterOptions,
+    ) -> Result<Image, GDSIIErrorKind> {
+        let scene = Scene::new(self, cell, options.max_depth, styles, &options.viewport)?;
+        let mut image = Image::new(options.viewport.width, options.viewport.height);
+        if let Some(background) = options.background {
+            image.fill(background);
+        }
+        let (width, height) = (image.width as usize, image.height as usize);
+        for (&(layer, datatype), items) in &scene.layers {
+            let style = styles.get(layer, datatype);
+            let mut fill = Edges::default();
+            let mut stroke = Edges::default();
+            for item in items {
+                if let Item::Polygon(points) = item {
+                    let points: Vec<(f64, f64)> =
+                        points.iter().map(|p| scene.projection.apply(*p)).collect();
+                    if style.hatch != Hatch::Hollow {
+                        fill.polygon(&points);
+                    }
+                    if style.stroke.is_some() {
+                        stroke.outline(&points, style.stroke_width);
+                    }
+                }
+            }
+            if !fill.0.is_empty() {
+                let coverage = fill.coverage(width, height);
+                let opacity = style.opacity.clamp(0.0, 1.0) as f32;
+                image.blend(style.fill, |x, y| {
+                    if hatched(style.hatch, x, y) {
+                        coverage[y * width + x] * opacity
+                    } else {
+                        0.0
+                    }
+                });
+            }
+            if let (Some(color), false) = (style.stroke, stroke.0.is_empty()) {
+                let coverage = stroke.coverage(width, height);
+                image.blend(color, |x, y| coverage[y * width + x]);
+            }
+        }
+        let mut frames = Edges::default();
+        for (bbox, _) in &scene.frames {
+            let corners = bbox
+                .corners()
+                .map(|p| scene.projection.apply((p.0 as f64, p.1 as f64)));
+            frames.outline(&corners, 1.0);
+        }
+        if !frames.0.is_empty() {
+            let coverage = frames.coverage(width, height);
+            image.blend(FRAME, |x, y| coverage[y * width + x]);
+        }
+        Ok(image)
+    }
+}
@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+
+/// 8-bit RGB colour.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color {
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Color { r, g, b }
+    }
+
+    /// `#rrggbb` or `#rgb`
+    pub fn parse(text: &str) -> Option<Color> {
+        let hex = text.strip_prefix('#')?;
+        let digit = |i: usize, n: usize| u8::from_str_radix(hex.get(i..i + n)?, 16).ok();
+        match hex.len() {
+            6 => Some(Color::new(digit(0, 2)?, digit(2, 2)?, digit(4, 2)?)),
+            3 => Some(Color::new(
+                digit(0, 1)? * 17,
+                digit(1, 1)? * 17,
+                digit(2, 1)? * 17,
+            )),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Color {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
+/// How the inside of a shape is filled.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Hatch {
+    #[default]
+    Solid,
+    /// outline only
+    Hollow,
+    /// lines rising to the right
+    Diagonal,
+    /// lines falling to the right
+    BackDiagonal,
+    /// both diagonals
+    Cross,
+    Horizontal,
+    Vertical,
+    Dots,
+}
+
+/// Look of the shapes on one layer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayerStyle {
+    pub fill: Color,
+    /// outline colour, none when `None`
+    pub stroke: Option<Color>,
+    /// outline width in pixels
+    pub stroke_width: f64,
+    /// opacity of the fill, 0 to 1; outlines and texts are opaque
+    pub opacity: f64,
+    pub hatch: Hatch,
+}
+
+impl LayerStyle {
+    /// solid fill at half opacity outlined in the same colour
+    pub fn new(color: Color) -> Self {
+        LayerStyle {
+            fill: color,
+            stroke: Some(color),
+            stroke_width: 1.0,
+            opacity: 0.5,
+            hatch: Hatch::Solid,
+        }
+    }
+
+    pub fn with_hatch(self, hatch: Hatch) -> Self {
+        LayerStyle { hatch, ..self }
+    }
+
+    pub fn with_opacity(self, opacity: f64) -> Self {
+        LayerStyle { opacity, ..self }
+    }
+}
+
+// colours of layers without a style, picked by layer number
+const PALETTE: [Color; 12] = [
+    Color::new(0x1f, 0x77, 0xb4),
+    Color::new(0xff, 0x7f, 0x0e),
+    Color::new(0x2c, 0xa0, 0x2c),
+    Color::new(0xd6, 0x27, 0x28),
+    Color::new(0x94, 0x67, 0xbd),
+    Color::new(0x8c, 0x56, 0x4b),
+    Color::new(0xe3, 0x77, 0xc2),
+    Color::new(0x7f, 0x7f, 0x7f),
+    Color::new(0xbc, 0xbd, 0x22),
+    Color::new(0x17, 0xbe, 0xcf),
+    Color::new(0x39, 0x3b, 0x79),
+    Color::new(0xad, 0x49, 0x4a),
+];
+
+/// Styles by (layer, datatype), looked up like a [`crate::LayerMap`]: an
+/// exact pair first, then a whole layer. Layers with neither get a solid
+/// style in a colour picked by layer number. TEXTTYPE is looked up as the
+/// datatype.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct LayerStyles {
+    pairs: HashMap<(i16, i16), LayerStyle>,
+    layers: HashMap<i16, LayerStyle>,
+    hidden: Vec<(i16, Option<i16>)>,
+}
+
+impl LayerStyles {
+    pub fn new() -> Self {
+        LayerStyles::default()
+    }
+
+    pub fn insert(&mut self, layer: i16, datatype: i16, style: LayerStyle) {
+        self.pairs.insert((layer, datatype), style);
+    }
+
+    /// style of every datatype of `layer` without one of its own
+    pub fn insert_layer(&mut self, layer: i16, style: LayerStyle) {
+        self.layers.insert(layer, style);
+    }
+
+    /// leave `layer` out, only its `datatype` when given
+    pub fn hide(&mut self, layer: i16, datatype: Option<i16>) {
+        self.hidden.push((layer, datatype));
+    }
+
+    pub fn is_hidden(&self, layer: i16, datatype: i16) -> bool {
+        self.hidden
+            .iter()
+            .any(|(l, d)| *l == layer && d.is_none_or(|d| d == datatype))
+    }
+
+    pub fn get(&self, layer: i16, datatype: i16) -> LayerStyle {
+        match self.pairs.get(&(layer, datatype)) {
+            Some(style) => style.clone(),
+            None => match self.layers.get(&layer) {
+                Some(style) => style.clone(),
+                None => LayerStyle::new(PALETTE[layer.rem_euclid(PALETTE.len() as i16) as usize]),
+            },
+        }
+    }
+}
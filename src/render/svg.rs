@@ -0,0 +1,258 @@
+use super::{Color, Hatch, Item, LayerStyles, Scene, Viewport, FRAME};
+use crate::error::GDSIIErrorKind;
+use crate::model::{Library, Text};
+use std::fmt::Write;
+
+/// How [`Library::to_svg`] draws.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgOptions {
+    /// levels of SREF/AREF to expand, deeper references are drawn as
+    /// dashed boxes named after the structure; `None` expands all
+    pub max_depth: Option<usize>,
+    pub viewport: Viewport,
+    /// transparent when `None`
+    pub background: Option<Color>,
+    /// draw TEXT labels
+    pub texts: bool,
+    /// height of TEXT labels in pixels
+    pub text_size: f64,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        SvgOptions {
+            max_depth: None,
+            viewport: Viewport::default(),
+            background: None,
+            texts: true,
+            text_size: 12.0,
+        }
+    }
+}
+
+// pixel coordinate with at most two decimals
+fn num(v: f64) -> String {
+    let s = format!("{:.2}", v);
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    match s {
+        "-0" => "0".to_string(),
+        s => s.to_string(),
+    }
+}
+
+fn escape(out: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+}
+
+// pattern tile of a hatch, `None` for solid and hollow fills
+fn hatch_tile(hatch: Hatch, color: Color) -> Option<String> {
+    let lines = match hatch {
+        Hatch::Solid | Hatch::Hollow => return None,
+        Hatch::Dots => {
+            return Some(format!(
+                r#"<rect x="3" y="3" width="2" height="2" fill="{}"/>"#,
+                color
+            ))
+        }
+        Hatch::Diagonal => "M0 8L8 0M-2 2L2 -2M6 10L10 6",
+        Hatch::BackDiagonal => "M0 0L8 8M-2 6L2 10M6 -2L10 2",
+        Hatch::Cross => "M0 8L8 0M-2 2L2 -2M6 10L10 6M0 0L8 8M-2 6L2 10M6 -2L10 2",
+        Hatch::Horizontal => "M0 4H8",
+        Hatch::Vertical => "M4 0V8",
+    };
+    Some(format!(
+        r#"<path d="{}" stroke="{}" stroke-width="1"/>"#,
+        lines, color
+    ))
+}
+
+fn text(out: &mut String, scene: &Scene, text: &Text, size: f64) {
+    let (x, y) = scene
+        .projection
+        .apply((text.origin.0 as f64, text.origin.1 as f64));
+    let presentation = text.presentation.unwrap_or(0);
+    let anchor = ["start", "middle", "end", "start"][(presentation & 3) as usize];
+    let baseline =
+        ["hanging", "central", "alphabetic", "alphabetic"][((presentation >> 2) & 3) as usize];
+    write!(
+        out,
+        r#"<text x="{}" y="{}" font-size="{}" text-anchor="{}" dominant-baseline="{}""#,
+        num(x),
+        num(y),
+        num(size),
+        anchor,
+        baseline
+    )
+    .unwrap();
+    let angle = text.strans.map_or(0.0, |s| s.angle_degrees());
+    if angle != 0.0 {
+        // counterclockwise in the layout, y points down in the picture
+        write!(
+            out,
+            r#" transform="rotate({} {} {})""#,
+            num(-angle),
+            num(x),
+            num(y)
+        )
+        .unwrap();
+    }
+    out.push('>');
+    escape(out, &text.string);
+    out.push_str("</text>\n");
+}
+
+impl Library {
+    /// SVG picture of the structure `cell`.
+    ///
+    /// Each (layer, datatype) is one `<g id="layer-L-D">` in ascending
+    /// order, styled from `styles`; paths are drawn as their
+    /// [`outline`](crate::Path::outline), boxes as polygons and texts as
+    /// `<text>` at their origin, rotated by their angle and anchored as
+    /// their presentation asks. Shapes outside the viewport are left out.
+    pub fn to_svg(
+        &self,
+        cell: &str,
+        styles: &LayerStyles,
+        options: &SvgOptions,
+    ) -> Result<String, GDSIIErrorKind> {
+        let scene = Scene::new(self, cell, options.max_depth, styles, &options.viewport)?;
+        let (width, height) = (options.viewport.width, options.viewport.height);
+        let mut out = String::new();
+        writeln!(
+            out,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#,
+            width, height
+        )
+        .unwrap();
+        let mut defs = String::new();
+        for &(layer, datatype) in scene.layers.keys() {
+            let style = styles.get(layer, datatype);
+            if let Some(tile) = hatch_tile(style.hatch, style.fill) {
+                writeln!(
+                    defs,
+                    r#"<pattern id="hatch-{}-{}" width="8" height="8" patternUnits="userSpaceOnUse">{}</pattern>"#,
+                    layer, datatype, tile
+                )
+                .unwrap();
+            }
+        }
+        if !defs.is_empty() {
+            writeln!(out, "<defs>\n{}</defs>", defs).unwrap();
+        }
+        if let Some(background) = options.background {
+            writeln!(
+                out,
+                r#"<rect width="100%" height="100%" fill="{}"/>"#,
+                background
+            )
+            .unwrap();
+        }
+        for (&(layer, datatype), items) in &scene.layers {
+            let style = styles.get(layer, datatype);
+            let fill = match style.hatch {
+                Hatch::Solid => style.fill.to_string(),
+                Hatch::Hollow => "none".to_string(),
+                _ => format!("url(#hatch-{}-{})", layer, datatype),
+            };
+            write!(
+                out,
+                r#"<g id="layer-{}-{}" fill="{}" fill-opacity="{}""#,
+                layer,
+                datatype,
+                fill,
+                num(style.opacity)
+            )
+            .unwrap();
+            match style.stroke {
+                Some(stroke) => writeln!(
+                    out,
+                    r#" stroke="{}" stroke-width="{}" stroke-linejoin="miter">"#,
+                    stroke,
+                    num(style.stroke_width)
+                )
+                .unwrap(),
+                None => writeln!(out, r#" stroke="none">"#).unwrap(),
+            }
+            let mut labels = String::new();
+            for item in items {
+                match item {
+                    Item::Polygon(points) if !points.is_empty() => {
+                        out.push_str(r#"<polygon points=""#);
+                        for (i, p) in points.iter().enumerate() {
+                            let (x, y) = scene.projection.apply(*p);
+                            let sep = if i == 0 { "" } else { " " };
+                            write!(out, "{}{},{}", sep, num(x), num(y)).unwrap();
+                        }
+                        out.push_str("\"/>\n");
+                    }
+                    Item::Polygon(_) => {}
+                    Item::Text(t) if options.texts => {
+                        text(&mut labels, &scene, t, options.text_size)
+                    }
+                    Item::Text(_) => {}
+                }
+            }
+            if !labels.is_empty() {
+                // labels in the layer colour, opaque and without outline
+                writeln!(
+                    out,
+                    r#"<g fill="{}" fill-opacity="1" stroke="none" font-family="monospace">"#,
+                    style.stroke.unwrap_or(style.fill)
+                )
+                .unwrap();
+                out.push_str(&labels);
+                out.push_str("</g>\n");
+            }
+            out.push_str("</g>\n");
+        }
+        if !scene.frames.is_empty() {
+            writeln!(
+                out,
+                r#"<g id="references" fill="none" stroke="{}" stroke-width="1" stroke-dasharray="4 2">"#,
+                FRAME
+            )
+            .unwrap();
+            for (bbox, name) in &scene.frames {
+                let (x0, y0) = scene
+                    .projection
+                    .apply((bbox.min.0 as f64, bbox.max.1 as f64));
+                let (x1, y1) = scene
+                    .projection
+                    .apply((bbox.max.0 as f64, bbox.min.1 as f64));
+                write!(
+                    out,
+                    r#"<rect x="{}" y="{}" width="{}" height="{}"><title>"#,
+                    num(x0),
+                    num(y0),
+                    num(x1 - x0),
+                    num(y1 - y0)
+                )
+                .unwrap();
+                escape(&mut out, name);
+                out.push_str("</title></rect>\n");
+            }
+            out.push_str("</g>\n");
+        }
+        out.push_str("</svg>\n");
+        Ok(out)
+    }
+
+    pub fn save_svg<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+        cell: &str,
+        styles: &LayerStyles,
+        options: &SvgOptions,
+    ) -> Result<(), GDSIIErrorKind> {
+        std::fs::write(path, self.to_svg(cell, styles, options)?)?;
+        Ok(())
+    }
+}
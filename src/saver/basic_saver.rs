@@ -1,15 +1,129 @@
-use crate::model::Tuctosin;
+use crate::error::GDSIIErrorKind;
+use crate::model::{record_name, FileHeader, RawRecord, Tuctosin};
 use byteorder::{BigEndian, ByteOrder};
+use std::ops::RangeBounds;
+
+// largest even record size
+const MAX_RECORD: usize = 0xFFFE;
+
+/// append the size and tag of a record holding `length` bytes of data
+pub fn append_record_head(
+    data: &mut Vec<u8>,
+    tag: [u8; 2],
+    length: usize,
+) -> Result<(), GDSIIErrorKind> {
+    if length > MAX_RECORD - 4 {
+        return Err(GDSIIErrorKind::RecordTooLong {
+            record: record_name(tag[0]),
+            length,
+        });
+    }
+    data.extend(((length + 4) as u16).to_be_bytes());
+    data.extend(tag);
+    Ok(())
+}
 
 pub fn append_tuctosin_end(data: &mut Vec<u8>) {
     let mut byted_size = [0u8; 2];
-    BigEndian::write_u16(&mut byted_size, 0 + 4);
+    BigEndian::write_u16(&mut byted_size, 4);
     data.extend(&byted_size);
     data.extend(&[0x11, 0x00]);
 }
 
+/// append an ASCII record, NUL padded to an even length
+pub fn append_string(data: &mut Vec<u8>, tag: [u8; 2], d: &str) -> Result<(), GDSIIErrorKind> {
+    let pad = d.len() % 2;
+    append_record_head(data, tag, d.len() + pad)?;
+    data.extend(d.as_bytes());
+    data.extend(std::iter::repeat_n(0u8, pad));
+    Ok(())
+}
+
+/// append a record holding a list of 2-byte integers
+pub fn append_i16s(data: &mut Vec<u8>, tag: [u8; 2], d: &[i16]) -> Result<(), GDSIIErrorKind> {
+    append_record_head(data, tag, d.len() * 2)?;
+    for i in d {
+        data.extend(&i.to_be_bytes());
+    }
+    Ok(())
+}
+
+/// append a record holding a list of 4-byte integers
+pub fn append_i32s(data: &mut Vec<u8>, tag: [u8; 2], d: &[i32]) -> Result<(), GDSIIErrorKind> {
+    append_record_head(data, tag, d.len() * 4)?;
+    for i in d {
+        data.extend(&i.to_be_bytes());
+    }
+    Ok(())
+}
+
+/// append a record of NUL padded 44-byte names, as REFLIBS and FONTS
+pub fn append_fixed_strings(
+    data: &mut Vec<u8>,
+    tag: [u8; 2],
+    d: &[String],
+) -> Result<(), GDSIIErrorKind> {
+    append_record_head(data, tag, d.len() * 44)?;
+    for name in d {
+        let bytes = &name.as_bytes()[..name.len().min(44)];
+        data.extend(bytes);
+        data.extend(std::iter::repeat_n(0u8, 44 - bytes.len()));
+    }
+    Ok(())
+}
+
+/// append the raw records anchored at an index in `at`
+pub fn append_raw<R: RangeBounds<usize>>(
+    data: &mut Vec<u8>,
+    raw: &[(usize, RawRecord)],
+    at: R,
+) -> Result<(), GDSIIErrorKind> {
+    for (_, r) in raw.iter().filter(|(i, _)| at.contains(i)) {
+        append_record_head(data, [r.rtype, r.dtype], r.bytes.len())?;
+        data.extend(&r.bytes);
+    }
+    Ok(())
+}
+
+impl FileHeader {
+    pub fn byted(&self) -> Result<Vec<u8>, GDSIIErrorKind> {
+        let mut data = vec![];
+        let tag = self.record_type();
+        match self {
+            FileHeader::Header(d)
+            | FileHeader::LibDirSize(d)
+            | FileHeader::Generations(d)
+            | FileHeader::Format(d)
+            | FileHeader::TapeNum(d) => append_i16s(&mut data, tag, &[*d])?,
+            FileHeader::BgnLib(d) => append_i16s(&mut data, tag, d)?,
+            FileHeader::TapeCode(d) => append_i16s(&mut data, tag, d)?,
+            FileHeader::LibSecur(d) => {
+                let acl: Vec<i16> = d.iter().flat_map(|(g, u, r)| [*g, *u, *r]).collect();
+                append_i16s(&mut data, tag, &acl)?
+            }
+            FileHeader::SrfName(d)
+            | FileHeader::LibName(d)
+            | FileHeader::AttrTable(d)
+            | FileHeader::StypTable(d)
+            | FileHeader::Mask(d) => append_string(&mut data, tag, d)?,
+            FileHeader::RefLibs(d) | FileHeader::Fonts(d) => {
+                append_fixed_strings(&mut data, tag, d)?
+            }
+            FileHeader::EndMask => append_i16s(&mut data, tag, &[])?,
+            FileHeader::Units(d) => {
+                data.extend(&[0x00, 0x14]);
+                data.extend(&tag);
+                data.extend(&d[0].to_be_bytes());
+                data.extend(&d[1].to_be_bytes());
+            }
+            FileHeader::Reserved(d) => append_i32s(&mut data, tag, d)?,
+        }
+        Ok(data)
+    }
+}
+
 impl Tuctosin {
-    pub fn byted(&self) -> Vec<u8> {
+    pub fn byted(&self) -> Result<Vec<u8>, GDSIIErrorKind> {
         let mut data = vec![];
         let mut byted_size = [0; 2];
         match &self {
@@ -19,7 +133,7 @@ impl Tuctosin {
                 data.extend_from_slice(&byted_size);
                 data.extend_from_slice(&[0x26, 0x01]);
                 BigEndian::write_i16(&mut byted_d, *d);
-                data.extend_from_slice(&byted_d.to_vec());
+                data.extend_from_slice(&byted_d);
             }
             Tuctosin::Plex(d) => {
                 let mut byted_d = [0; 4];
@@ -27,7 +141,7 @@ impl Tuctosin {
                 data.extend_from_slice(&byted_size);
                 data.extend_from_slice(&[0x2F, 0x03]);
                 BigEndian::write_i32(&mut byted_d, *d);
-                data.extend_from_slice(&byted_d.to_vec());
+                data.extend_from_slice(&byted_d);
             }
             Tuctosin::Layer(d) => {
                 let mut byted_d = [0; 2];
@@ -35,7 +149,7 @@ impl Tuctosin {
                 data.extend_from_slice(&byted_size);
                 data.extend_from_slice(&[0x0D, 0x02]);
                 BigEndian::write_i16(&mut byted_d, *d);
-                data.extend_from_slice(&byted_d.to_vec());
+                data.extend_from_slice(&byted_d);
             }
             Tuctosin::DataType(d) => {
                 let mut byted_d = [0; 2];
@@ -43,29 +157,27 @@ impl Tuctosin {
                 data.extend_from_slice(&byted_size);
                 data.extend_from_slice(&[0x0E, 0x02]);
                 BigEndian::write_i16(&mut byted_d, *d);
-                data.extend_from_slice(&byted_d.to_vec());
+                data.extend_from_slice(&byted_d);
             }
             Tuctosin::Xy(d) => {
                 let mut byted_d = [0; 4];
-                BigEndian::write_i16(&mut byted_size, (d.len() * 8) as i16 + 4);
-                data.extend_from_slice(&byted_size);
-                data.extend_from_slice(&[0x10, 0x03]);
+                append_record_head(&mut data, [0x10, 0x03], d.len() * 8)?;
                 for i in d {
                     // write x
                     BigEndian::write_i32(&mut byted_d, i.0);
-                    data.extend_from_slice(&byted_d.to_vec());
+                    data.extend_from_slice(&byted_d);
                     // write y
                     BigEndian::write_i32(&mut byted_d, i.1);
-                    data.extend_from_slice(&byted_d.to_vec());
+                    data.extend_from_slice(&byted_d);
                 }
             }
             Tuctosin::PathType(d) => {
-                let mut byted_d = [0; 4];
+                let mut byted_d = [0; 2];
                 BigEndian::write_i16(&mut byted_size, 2 + 4);
                 data.extend_from_slice(&byted_size);
                 data.extend_from_slice(&[0x21, 0x02]);
                 BigEndian::write_i16(&mut byted_d, *d);
-                data.extend_from_slice(&byted_d.to_vec());
+                data.extend_from_slice(&byted_d);
             }
             Tuctosin::Width(d) => {
                 let mut byted_d = [0; 4];
@@ -73,21 +185,16 @@ impl Tuctosin {
                 data.extend_from_slice(&byted_size);
                 data.extend_from_slice(&[0x0F, 0x03]);
                 BigEndian::write_i32(&mut byted_d, *d);
-                data.extend_from_slice(&byted_d.to_vec());
-            }
-            Tuctosin::Sname(d) => {
-                BigEndian::write_i16(&mut byted_size, d.as_bytes().len() as i16 + 4);
-                data.extend_from_slice(&byted_size);
-                data.extend_from_slice(&[0x12, 0x06]);
-                data.extend_from_slice(d.as_bytes());
+                data.extend_from_slice(&byted_d);
             }
+            Tuctosin::Sname(d) => append_string(&mut data, [0x12, 0x06], d)?,
             Tuctosin::Strans(d) => {
                 let mut byted_d = [0; 2];
                 BigEndian::write_i16(&mut byted_size, 2 + 4);
                 data.extend_from_slice(&byted_size);
                 data.extend_from_slice(&[0x1A, 0x01]);
                 BigEndian::write_i16(&mut byted_d, *d);
-                data.extend_from_slice(&byted_d.to_vec());
+                data.extend_from_slice(&byted_d);
             }
 
             Tuctosin::Mag(d) => {
@@ -95,16 +202,16 @@ impl Tuctosin {
                 BigEndian::write_i16(&mut byted_size, 8 + 4);
                 data.extend_from_slice(&byted_size);
                 data.extend_from_slice(&[0x1B, 0x05]);
-                BigEndian::write_i64(&mut byted_d, *d);
-                data.extend_from_slice(&byted_d.to_vec());
+                BigEndian::write_u64(&mut byted_d, d.to_bits());
+                data.extend_from_slice(&byted_d);
             }
             Tuctosin::Angle(d) => {
                 let mut byted_d = [0; 8];
                 BigEndian::write_i16(&mut byted_size, 8 + 4);
                 data.extend_from_slice(&byted_size);
                 data.extend_from_slice(&[0x1C, 0x05]);
-                BigEndian::write_i64(&mut byted_d, *d);
-                data.extend_from_slice(&byted_d.to_vec());
+                BigEndian::write_u64(&mut byted_d, d.to_bits());
+                data.extend_from_slice(&byted_d);
             }
             Tuctosin::ColRow(d) => {
                 let mut byted_d = [0; 2];
@@ -112,9 +219,9 @@ impl Tuctosin {
                 data.extend_from_slice(&byted_size);
                 data.extend_from_slice(&[0x13, 0x02]);
                 BigEndian::write_i16(&mut byted_d, d.0);
-                data.extend_from_slice(&byted_d.to_vec());
+                data.extend_from_slice(&byted_d);
                 BigEndian::write_i16(&mut byted_d, d.1);
-                data.extend_from_slice(&byted_d.to_vec());
+                data.extend_from_slice(&byted_d);
             }
             Tuctosin::TextType(d) => {
                 let mut byted_d = [0; 2];
@@ -122,7 +229,7 @@ impl Tuctosin {
                 data.extend_from_slice(&byted_size);
                 data.extend_from_slice(&[0x16, 0x02]);
                 BigEndian::write_i16(&mut byted_d, *d);
-                data.extend_from_slice(&byted_d.to_vec());
+                data.extend_from_slice(&byted_d);
             }
 
             Tuctosin::Persentation(d) => {
@@ -131,21 +238,16 @@ impl Tuctosin {
                 data.extend_from_slice(&byted_size);
                 data.extend_from_slice(&[0x17, 0x01]);
                 BigEndian::write_i16(&mut byted_d, *d);
-                data.extend_from_slice(&byted_d.to_vec());
-            }
-            Tuctosin::AsciiString(d) => {
-                BigEndian::write_i16(&mut byted_size, d.as_bytes().len() as i16 + 4);
-                data.extend_from_slice(&byted_size);
-                data.extend_from_slice(&[0x19, 0x06]);
-                data.extend_from_slice(d.as_bytes());
+                data.extend_from_slice(&byted_d);
             }
+            Tuctosin::AsciiString(d) => append_string(&mut data, [0x19, 0x06], d)?,
             Tuctosin::NodeType(d) => {
                 let mut byted_d = [0; 2];
                 BigEndian::write_i16(&mut byted_size, 2 + 4);
                 data.extend_from_slice(&byted_size);
                 data.extend_from_slice(&[0x2A, 0x02]);
                 BigEndian::write_i16(&mut byted_d, *d);
-                data.extend_from_slice(&byted_d.to_vec());
+                data.extend_from_slice(&byted_d);
             }
             Tuctosin::BoxType(d) => {
                 let mut byted_d = [0; 2];
@@ -153,9 +255,44 @@ impl Tuctosin {
                 data.extend_from_slice(&byted_size);
                 data.extend_from_slice(&[0x2E, 0x02]);
                 BigEndian::write_i16(&mut byted_d, *d);
-                data.extend_from_slice(&byted_d.to_vec());
+                data.extend_from_slice(&byted_d);
+            }
+            Tuctosin::BgnExtn(d) => {
+                let mut byted_d = [0; 4];
+                BigEndian::write_i16(&mut byted_size, 4 + 4);
+                data.extend_from_slice(&byted_size);
+                data.extend_from_slice(&[0x30, 0x03]);
+                BigEndian::write_i32(&mut byted_d, *d);
+                data.extend_from_slice(&byted_d);
+            }
+            Tuctosin::EndExtn(d) => {
+                let mut byted_d = [0; 4];
+                BigEndian::write_i16(&mut byted_size, 4 + 4);
+                data.extend_from_slice(&byted_size);
+                data.extend_from_slice(&[0x31, 0x03]);
+                BigEndian::write_i32(&mut byted_d, *d);
+                data.extend_from_slice(&byted_d);
+            }
+            Tuctosin::PropAttr(d) => {
+                let mut byted_d = [0; 2];
+                BigEndian::write_i16(&mut byted_size, 2 + 4);
+                data.extend_from_slice(&byted_size);
+                data.extend_from_slice(&[0x2B, 0x02]);
+                BigEndian::write_i16(&mut byted_d, *d);
+                data.extend_from_slice(&byted_d);
+            }
+            Tuctosin::PropValue(d) => append_string(&mut data, [0x2C, 0x06], d)?,
+            Tuctosin::Spacing(d) | Tuctosin::LinkType(d) => {
+                append_i16s(&mut data, self.record_type(), &[*d])?
+            }
+            Tuctosin::UInteger(d) | Tuctosin::ElKey(d) => {
+                append_i32s(&mut data, self.record_type(), &[*d])?
             }
+            Tuctosin::UString(d) => append_string(&mut data, [0x1E, 0x06], d)?,
+            Tuctosin::LinkKeys(d) => append_i32s(&mut data, [0x29, 0x03], d)?,
+            // the marker records carry no data
+            _ => append_i16s(&mut data, self.record_type(), &[])?,
         }
-        return data;
+        Ok(data)
     }
 }
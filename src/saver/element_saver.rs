@@ -0,0 +1,149 @@
+use crate::error::GDSIIErrorKind;
+use crate::model::{Element, Property, Strans, Tuctosin, TuctosinHeader};
+use byteorder::{BigEndian, ByteOrder};
+
+use super::basic_saver::{append_raw, append_tuctosin_end};
+
+impl TuctosinHeader {
+    pub fn byted(&self) -> Vec<u8> {
+        let mut data = vec![0u8; 2];
+        BigEndian::write_u16(&mut data, 4);
+        data.extend(&self.record_type());
+        data
+    }
+}
+
+// ELFLAGS and PLEX lead every element body, followed by the records kept
+// in `extra`
+fn push_common(
+    records: &mut Vec<Tuctosin>,
+    elflags: Option<i16>,
+    plex: Option<i32>,
+    extra: &[Tuctosin],
+) {
+    records.extend(elflags.map(Tuctosin::ElfFlags));
+    records.extend(plex.map(Tuctosin::Plex));
+    records.extend(extra.iter().cloned());
+}
+
+// optional STRANS, MAG and ANGLE of references and texts
+fn push_strans(records: &mut Vec<Tuctosin>, strans: Option<&Strans>) {
+    if let Some(strans) = strans {
+        records.push(Tuctosin::Strans(strans.to_bits()));
+        records.extend(strans.mag.map(Tuctosin::Mag));
+        records.extend(strans.angle.map(Tuctosin::Angle));
+    }
+}
+
+fn push_props(records: &mut Vec<Tuctosin>, props: &[Property]) {
+    for p in props {
+        records.push(Tuctosin::PropAttr(p.attr));
+        records.push(Tuctosin::PropValue(p.value.clone()));
+    }
+}
+
+impl Element {
+    /// Body records of the element without the header and ENDEL.
+    ///
+    /// They come in Stream Format order, or in [`Element::order`] when it is
+    /// set, with records missing from it after those it lists.
+    pub fn records(&self) -> Vec<Tuctosin> {
+        let records = self.stream_records();
+        if self.order().is_empty() {
+            return records;
+        }
+        let mut pending: Vec<Option<Tuctosin>> = records.into_iter().map(Some).collect();
+        let mut replayed = Vec::with_capacity(pending.len());
+        for rtype in self.order() {
+            let next = pending
+                .iter_mut()
+                .find(|r| r.as_ref().is_some_and(|r| r.record_type() == *rtype));
+            replayed.extend(next.and_then(Option::take));
+        }
+        replayed.extend(pending.into_iter().flatten());
+        replayed
+    }
+
+    // body records in Stream Format order
+    fn stream_records(&self) -> Vec<Tuctosin> {
+        let mut records = vec![];
+        match self {
+            Element::Boundary(e) => {
+                push_common(&mut records, e.elflags, e.plex, &e.extra);
+                records.push(Tuctosin::Layer(e.layer));
+                records.push(Tuctosin::DataType(e.datatype));
+                records.push(Tuctosin::Xy(e.points.clone()));
+                push_props(&mut records, &e.props);
+            }
+            Element::Path(e) => {
+                push_common(&mut records, e.elflags, e.plex, &e.extra);
+                records.push(Tuctosin::Layer(e.layer));
+                records.push(Tuctosin::DataType(e.datatype));
+                records.extend(e.pathtype.map(Tuctosin::PathType));
+                records.extend(e.width.map(Tuctosin::Width));
+                records.extend(e.bgnextn.map(Tuctosin::BgnExtn));
+                records.extend(e.endextn.map(Tuctosin::EndExtn));
+                records.push(Tuctosin::Xy(e.points.clone()));
+                push_props(&mut records, &e.props);
+            }
+            Element::SRef(e) => {
+                push_common(&mut records, e.elflags, e.plex, &e.extra);
+                records.push(Tuctosin::Sname(e.sname.clone()));
+                push_strans(&mut records, e.strans.as_ref());
+                records.push(Tuctosin::Xy(vec![e.origin]));
+                push_props(&mut records, &e.props);
+            }
+            Element::ARef(e) => {
+                push_common(&mut records, e.elflags, e.plex, &e.extra);
+                records.push(Tuctosin::Sname(e.sname.clone()));
+                push_strans(&mut records, e.strans.as_ref());
+                records.push(Tuctosin::ColRow((e.cols, e.rows)));
+                records.push(Tuctosin::Xy(vec![e.origin, e.col_point, e.row_point]));
+                push_props(&mut records, &e.props);
+            }
+            Element::Text(e) => {
+                push_common(&mut records, e.elflags, e.plex, &e.extra);
+                records.push(Tuctosin::Layer(e.layer));
+                records.push(Tuctosin::TextType(e.texttype));
+                records.extend(e.presentation.map(Tuctosin::Persentation));
+                records.extend(e.pathtype.map(Tuctosin::PathType));
+                records.extend(e.width.map(Tuctosin::Width));
+                push_strans(&mut records, e.strans.as_ref());
+                records.push(Tuctosin::Xy(vec![e.origin]));
+                records.push(Tuctosin::AsciiString(e.string.clone()));
+                push_props(&mut records, &e.props);
+            }
+            Element::Node(e) => {
+                push_common(&mut records, e.elflags, e.plex, &e.extra);
+                records.push(Tuctosin::Layer(e.layer));
+                records.push(Tuctosin::NodeType(e.nodetype));
+                records.push(Tuctosin::Xy(e.points.clone()));
+                push_props(&mut records, &e.props);
+            }
+            Element::Box(e) => {
+                push_common(&mut records, e.elflags, e.plex, &e.extra);
+                records.push(Tuctosin::Layer(e.layer));
+                records.push(Tuctosin::BoxType(e.boxtype));
+                records.push(Tuctosin::Xy(e.points.clone()));
+                push_props(&mut records, &e.props);
+            }
+        }
+        records
+    }
+
+    /// The whole element from its header record to ENDEL.
+    ///
+    /// Fails with [`GDSIIErrorKind::RecordTooLong`] when a record does not
+    /// fit its 2-byte size, as XY with more than 8191 points.
+    pub fn byted(&self) -> Result<Vec<u8>, GDSIIErrorKind> {
+        let mut data = self.header().byted();
+        let records = self.records();
+        for (i, toc) in records.iter().enumerate() {
+            append_raw(&mut data, self.raw(), i..=i)?;
+            data.extend(&toc.byted()?);
+        }
+        append_raw(&mut data, self.raw(), records.len()..)?;
+        append_tuctosin_end(&mut data);
+        Ok(data)
+    }
+}
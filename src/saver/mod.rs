@@ -1,2 +1,9 @@
 mod basic_saver;
+mod element_saver;
+#[allow(clippy::module_inception)]
 mod saver;
+mod verify;
+mod writer;
+
+pub(crate) use verify::first_difference;
+pub use writer::GdsWriter;
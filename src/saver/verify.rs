@@ -0,0 +1,48 @@
+use crate::error::GDSIIErrorKind;
+use crate::model::record_name;
+use byteorder::{BigEndian, ByteOrder};
+
+// the record starting at `offset`, clipped to the end of the stream
+fn record_at(s: &[u8], offset: usize) -> &[u8] {
+    let rest = s.get(offset..).unwrap_or_default();
+    if rest.len() < 4 {
+        return rest;
+    }
+    let size = (BigEndian::read_u16(rest) as usize).max(4);
+    &rest[..size.min(rest.len())]
+}
+
+/// Walk both streams record by record up to ENDLIB, then compare what
+/// follows as a whole, and report the first record that differs.
+pub(crate) fn first_difference(original: &[u8], saved: &[u8]) -> Option<GDSIIErrorKind> {
+    let mut offset = 0;
+    loop {
+        let (a, b) = (record_at(original, offset), record_at(saved, offset));
+        if a.is_empty() && b.is_empty() {
+            return None;
+        }
+        let first = if a.len() >= 4 { a } else { b };
+        if a != b {
+            return Some(GDSIIErrorKind::RoundtripMismatch {
+                offset,
+                record: first.get(2).map_or("UNKNOWN", |t| record_name(*t)),
+                original: a.to_vec(),
+                saved: b.to_vec(),
+            });
+        }
+        offset += a.len();
+        if a.len() < 4 || a[2..4] == [0x04, 0x00] {
+            break;
+        }
+    }
+    let (a, b) = (&original[offset..], &saved[offset..]);
+    if a != b {
+        return Some(GDSIIErrorKind::RoundtripMismatch {
+            offset,
+            record: "padding after ENDLIB",
+            original: a.to_vec(),
+            saved: b.to_vec(),
+        });
+    }
+    None
+}
@@ -0,0 +1,199 @@
+use super::basic_saver::append_i16s;
+use super::saver::append_structure_header;
+use crate::error::GDSIIErrorKind;
+use crate::model::*;
+use std::io::Write;
+
+/// where a [`GdsWriter`] is in the BGNLIB ... ENDLIB nesting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WriterState {
+    Start,
+    Library,
+    Structure,
+}
+
+impl WriterState {
+    fn describe(self) -> &'static str {
+        match self {
+            WriterState::Start => "before BGNLIB",
+            WriterState::Library => "outside a structure",
+            WriterState::Structure => "inside a structure",
+        }
+    }
+}
+
+/// Element-level writer emitting records as soon as they are given.
+///
+/// Calls must nest as the stream does: [`begin_library`], then any number of
+/// [`begin_structure`] ... elements ... [`end_structure`], then [`finish`].
+/// A call out of place fails with [`GDSIIErrorKind::InvalidNesting`] and
+/// writes nothing. Nothing is buffered, wrap `W` in a
+/// [`std::io::BufWriter`] when writing to a file or socket.
+///
+/// ```
+/// use gds2_io::{Boundary, GdsWriter};
+///
+/// let mut writer = GdsWriter::new(vec![]);
+/// writer.begin_library("LIB", (1e-3, 1e-9))?;
+/// writer.begin_structure("TOP")?;
+/// writer.write_boundary(Boundary {
+///     layer: 1,
+///     points: vec![(0, 0), (10, 0), (10, 10), (0, 0)],
+///     ..Default::default()
+/// })?;
+/// writer.end_structure()?;
+/// let bytes = writer.finish()?;
+/// # Ok::<(), gds2_io::GDSIIErrorKind>(())
+/// ```
+///
+/// [`begin_library`]: GdsWriter::begin_library
+/// [`begin_structure`]: GdsWriter::begin_structure
+/// [`end_structure`]: GdsWriter::end_structure
+/// [`finish`]: GdsWriter::finish
+pub struct GdsWriter<W: Write> {
+    w: W,
+    state: WriterState,
+}
+
+impl<W: Write> GdsWriter<W> {
+    pub fn new(w: W) -> Self {
+        GdsWriter {
+            w,
+            state: WriterState::Start,
+        }
+    }
+
+    // fail unless the writer is in `state`, `record` names the call
+    fn expect(
+        &self,
+        state: WriterState,
+        record: &'static str,
+    ) -> std::result::Result<(), GDSIIErrorKind> {
+        if self.state == state {
+            Ok(())
+        } else {
+            Err(GDSIIErrorKind::InvalidNesting {
+                record,
+                state: self.state.describe(),
+            })
+        }
+    }
+
+    /// HEADER, BGNLIB, LIBNAME and UNITS, units given as (user units per
+    /// database unit, meters per database unit)
+    pub fn begin_library(
+        &mut self,
+        name: &str,
+        units: (f64, f64),
+    ) -> std::result::Result<(), GDSIIErrorKind> {
+        self.begin_library_with(&[
+            FileHeader::Header(600),
+            FileHeader::BgnLib([0; 12]),
+            FileHeader::LibName(name.to_string()),
+            FileHeader::Units([units.0.into(), units.1.into()]),
+        ])
+    }
+
+    /// the given library records as they are, they must include HEADER,
+    /// BGNLIB and LIBNAME
+    pub fn begin_library_with(
+        &mut self,
+        header: &[FileHeader],
+    ) -> std::result::Result<(), GDSIIErrorKind> {
+        self.expect(WriterState::Start, "BGNLIB")?;
+        for tag in ["head", "bgn", "libname"] {
+            if !header.iter().any(|h| h.get_tag() == tag) {
+                return Err(GDSIIErrorKind::InvalidGDSII);
+            }
+        }
+        let mut data = vec![];
+        for h in header {
+            data.extend(h.byted()?);
+        }
+        self.w.write_all(&data)?;
+        self.state = WriterState::Library;
+        Ok(())
+    }
+
+    /// BGNSTR and STRNAME, both timestamps left zero
+    pub fn begin_structure(&mut self, name: &str) -> std::result::Result<(), GDSIIErrorKind> {
+        self.begin_structure_with(name, Timestamp::default(), Timestamp::default())
+    }
+
+    pub fn begin_structure_with(
+        &mut self,
+        name: &str,
+        created: Timestamp,
+        modified: Timestamp,
+    ) -> std::result::Result<(), GDSIIErrorKind> {
+        self.expect(WriterState::Library, "BGNSTR")?;
+        let structure = Structure {
+            name: name.to_string(),
+            created,
+            modified,
+            ..Default::default()
+        };
+        let mut data = vec![];
+        append_structure_header(&mut data, &structure)?;
+        self.w.write_all(&data)?;
+        self.state = WriterState::Structure;
+        Ok(())
+    }
+
+    pub fn end_structure(&mut self) -> std::result::Result<(), GDSIIErrorKind> {
+        self.expect(WriterState::Structure, "ENDSTR")?;
+        let mut data = vec![];
+        append_i16s(&mut data, [0x07, 0x00], &[])?;
+        self.w.write_all(&data)?;
+        self.state = WriterState::Library;
+        Ok(())
+    }
+
+    /// any element, from its header record to ENDEL
+    pub fn write_element(&mut self, element: &Element) -> std::result::Result<(), GDSIIErrorKind> {
+        self.expect(WriterState::Structure, element.header().name())?;
+        self.w.write_all(&element.byted()?)?;
+        Ok(())
+    }
+
+    pub fn write_boundary(
+        &mut self,
+        boundary: Boundary,
+    ) -> std::result::Result<(), GDSIIErrorKind> {
+        self.write_element(&Element::Boundary(boundary))
+    }
+
+    pub fn write_path(&mut self, path: Path) -> std::result::Result<(), GDSIIErrorKind> {
+        self.write_element(&Element::Path(path))
+    }
+
+    pub fn write_sref(&mut self, sref: SRef) -> std::result::Result<(), GDSIIErrorKind> {
+        self.write_element(&Element::SRef(sref))
+    }
+
+    pub fn write_aref(&mut self, aref: ARef) -> std::result::Result<(), GDSIIErrorKind> {
+        self.write_element(&Element::ARef(aref))
+    }
+
+    pub fn write_text(&mut self, text: Text) -> std::result::Result<(), GDSIIErrorKind> {
+        self.write_element(&Element::Text(text))
+    }
+
+    pub fn write_node(&mut self, node: Node) -> std::result::Result<(), GDSIIErrorKind> {
+        self.write_element(&Element::Node(node))
+    }
+
+    pub fn write_box(&mut self, gds_box: GdsBox) -> std::result::Result<(), GDSIIErrorKind> {
+        self.write_element(&Element::Box(gds_box))
+    }
+
+    /// ENDLIB, then flush and hand back the inner writer
+    pub fn finish(mut self) -> std::result::Result<W, GDSIIErrorKind> {
+        self.expect(WriterState::Library, "ENDLIB")?;
+        let mut data = vec![];
+        append_i16s(&mut data, [0x04, 0x00], &[])?;
+        self.w.write_all(&data)?;
+        self.w.flush()?;
+        Ok(self.w)
+    }
+}
@@ -0,0 +1,247 @@
+use crate::error::GDSIIErrorKind;
+use crate::geometry::{BBox, BBoxOptions};
+use crate::model::*;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// Number of elements of each kind, TEXT counting text nodes too.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ElementCounts {
+    pub boundary: usize,
+    pub path: usize,
+    pub sref: usize,
+    pub aref: usize,
+    pub text: usize,
+    pub node: usize,
+    pub gds_box: usize,
+}
+
+impl ElementCounts {
+    pub fn total(&self) -> usize {
+        self.boundary + self.path + self.sref + self.aref + self.text + self.node + self.gds_box
+    }
+
+    fn add(&mut self, element: &Element) {
+        match element {
+            Element::Boundary(_) => self.boundary += 1,
+            Element::Path(_) => self.path += 1,
+            Element::SRef(_) => self.sref += 1,
+            Element::ARef(_) => self.aref += 1,
+            Element::Text(_) => self.text += 1,
+            Element::Node(_) => self.node += 1,
+            Element::Box(_) => self.gds_box += 1,
+        }
+    }
+}
+
+/// Elements and XY vertices drawn on one layer/datatype pair.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LayerCounts {
+    pub elements: usize,
+    pub vertices: usize,
+}
+
+/// The BOUNDARY with the most vertices.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LargestPolygon {
+    pub structure: String,
+    /// position in the structure's elements
+    pub index: usize,
+    pub vertices: usize,
+}
+
+/// Statistics of a library, see [`Library::summerize`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+    pub name: Option<String>,
+    /// (user units per database unit, meters per database unit)
+    pub units: Option<(f64, f64)>,
+    pub structures: usize,
+    /// structures no other structure places
+    pub tops: Vec<String>,
+    pub elements: ElementCounts,
+    /// keyed by (layer, DATATYPE/TEXTTYPE/NODETYPE/BOXTYPE)
+    pub layers: BTreeMap<(i16, i16), LayerCounts>,
+    /// AREF instances, each array counting `cols * rows`
+    pub aref_instances: u64,
+    /// box of every top structure, TEXT origins left out, `None` as well
+    /// when there are dangling references or cycles
+    pub bbox: Option<BBox>,
+    /// most placements from a top down to a structure, 0 for a flat library
+    /// and `None` when structures place each other
+    pub depth: Option<usize>,
+    pub largest_polygon: Option<LargestPolygon>,
+    /// SREF/AREF targets no structure defines, as (referencing structure,
+    /// undefined name)
+    pub dangling: Vec<(String, String)>,
+    /// structures placing each other, each cycle ending on the name it
+    /// starts with; only the first one found is listed
+    pub cycles: Vec<Vec<String>>,
+}
+
+fn vertices(element: &Element) -> usize {
+    match element {
+        Element::Boundary(e) => e.points.len(),
+        Element::Path(e) => e.points.len(),
+        Element::Node(e) => e.points.len(),
+        Element::Box(e) => e.points.len(),
+        Element::Text(_) => 1,
+        // not drawn on a layer, never counted
+        Element::SRef(_) | Element::ARef(_) => 0,
+    }
+}
+
+impl Library {
+    /// Count what the library holds.
+    ///
+    /// Dangling references and cycles are reported in the summary, leaving
+    /// out only the bbox and depth they make impossible to compute.
+    pub fn summerize(&self) -> Summary {
+        let graph = self.hierarchy();
+        let (depth, cycles) = match graph.topological_order() {
+            Ok(order) => {
+                let mut depths: HashMap<&str, usize> = HashMap::new();
+                for name in order {
+                    let depth = depths.get(name).copied().unwrap_or(0);
+                    for child in graph.children(name) {
+                        let d = depths.entry(child).or_insert(0);
+                        *d = (*d).max(depth + 1);
+                    }
+                }
+                (Some(depths.into_values().max().unwrap_or(0)), vec![])
+            }
+            Err(GDSIIErrorKind::ReferenceCycle { cycle }) => (None, vec![cycle]),
+            Err(_) => (None, vec![]),
+        };
+        let mut elements = ElementCounts::default();
+        let mut layers: BTreeMap<(i16, i16), LayerCounts> = BTreeMap::new();
+        let mut aref_instances = 0u64;
+        let mut largest_polygon: Option<LargestPolygon> = None;
+        for structure in &self.structures {
+            for (index, element) in structure.elements.iter().enumerate() {
+                elements.add(element);
+                if let (Some(layer), Some(datatype)) = (element.layer(), element.datatype()) {
+                    let counts = layers.entry((layer, datatype)).or_default();
+                    counts.elements += 1;
+                    counts.vertices += vertices(element);
+                }
+                match element {
+                    Element::ARef(e) => {
+                        aref_instances += e.cols.max(0) as u64 * e.rows.max(0) as u64
+                    }
+                    Element::Boundary(e)
+                        if largest_polygon
+                            .as_ref()
+                            .is_none_or(|l| e.points.len() > l.vertices) =>
+                    {
+                        largest_polygon = Some(LargestPolygon {
+                            structure: structure.name.clone(),
+                            index,
+                            vertices: e.points.len(),
+                        })
+                    }
+                    _ => {}
+                }
+            }
+        }
+        let name = self.header.iter().find_map(|h| match h {
+            FileHeader::LibName(name) => Some(name.clone()),
+            _ => None,
+        });
+        Summary {
+            name,
+            units: self.units(),
+            structures: self.structures.len(),
+            tops: graph.tops().into_iter().map(|t| t.to_string()).collect(),
+            elements,
+            layers,
+            aref_instances,
+            bbox: self.bbox(&BBoxOptions::default()).ok().flatten(),
+            depth,
+            largest_polygon,
+            dangling: graph.dangling().to_vec(),
+            cycles,
+        }
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "library    {}",
+            self.name.as_deref().unwrap_or("(unnamed)")
+        )?;
+        match self.units {
+            Some((user, meters)) => writeln!(
+                f,
+                "units      {} user units, {:e} m per database unit",
+                user, meters
+            )?,
+            None => writeln!(f, "units      (missing)")?,
+        }
+        writeln!(
+            f,
+            "structures {}, top: {}",
+            self.structures,
+            self.tops.join(", ")
+        )?;
+        match self.depth {
+            Some(depth) => writeln!(f, "depth      {}", depth)?,
+            None => writeln!(f, "depth      (unknown)")?,
+        }
+        match self.bbox {
+            Some(b) => writeln!(
+                f,
+                "bbox       ({}, {}) to ({}, {})",
+                b.min.0, b.min.1, b.max.0, b.max.1
+            )?,
+            None if self.dangling.is_empty() && self.cycles.is_empty() => {
+                writeln!(f, "bbox       (empty)")?
+            }
+            None => writeln!(f, "bbox       (unknown)")?,
+        }
+        for (parent, name) in &self.dangling {
+            writeln!(f, "dangling   {} placed by {}", name, parent)?;
+        }
+        for cycle in &self.cycles {
+            writeln!(f, "cycle      {}", cycle.join(" -> "))?;
+        }
+        let e = &self.elements;
+        writeln!(f, "elements   {}", e.total())?;
+        for (kind, count) in [
+            ("BOUNDARY", e.boundary),
+            ("PATH", e.path),
+            ("SREF", e.sref),
+            ("AREF", e.aref),
+            ("TEXT", e.text),
+            ("NODE", e.node),
+            ("BOX", e.gds_box),
+        ] {
+            if count > 0 {
+                writeln!(f, "  {:<10} {}", kind, count)?;
+            }
+        }
+        if e.aref > 0 {
+            writeln!(f, "  AREF instances {}", self.aref_instances)?;
+        }
+        if let Some(p) = &self.largest_polygon {
+            writeln!(
+                f,
+                "largest polygon {} vertices, element {} of {}",
+                p.vertices, p.index, p.structure
+            )?;
+        }
+        writeln!(f, "layer/type   elements   vertices")?;
+        for ((layer, datatype), counts) in &self.layers {
+            writeln!(
+                f,
+                "  {:>9} {:>10} {:>10}",
+                format!("{}/{}", layer, datatype),
+                counts.elements,
+                counts.vertices
+            )?;
+        }
+        Ok(())
+    }
+}
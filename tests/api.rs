@@ -0,0 +1,50 @@
+use gds2_io::prelude::*;
+use gds2_io::{FileHeader, GDSIIModel, Tuctosin};
+
+fn testcase(name: &str) -> Vec<u8> {
+    std::fs::read(format!(
+        "{}/testcases/{}.gds",
+        std::env::var("CARGO_MANIFEST_DIR").unwrap(),
+        name
+    ))
+    .unwrap()
+}
+
+// downstream code can name the model types in its own signatures
+fn library_name(lib: &GDSIIModel) -> Option<&str> {
+    lib.header.iter().find_map(|h| match h {
+        FileHeader::LibName(name) => Some(name.as_str()),
+        _ => None,
+    })
+}
+
+fn first_layer(structure: &Structure) -> Option<Tuctosin> {
+    let element: &Element = structure.elements.first()?;
+    element.layer().map(Tuctosin::Layer)
+}
+
+#[test]
+fn test_entry_points() {
+    let data = testcase("sim_s1");
+    let from_bytes: Library = parse_bytes(&data).unwrap();
+    let from_stream = from_reader(std::io::BufReader::new(data.as_slice())).unwrap();
+    assert!(library_name(&from_bytes).is_some());
+    assert_eq!(
+        first_layer(&from_bytes.structures[0]),
+        first_layer(&from_stream.structures[0])
+    );
+
+    assert_eq!(from_bytes.to_bytes().unwrap(), data);
+    let mut written = vec![];
+    from_stream.to_writer(&mut written).unwrap();
+    assert_eq!(written, data);
+}
+
+#[test]
+fn test_parse_bytes_errors() {
+    let data = testcase("sim_c3");
+    match parse_bytes(&data[..100]) {
+        Err(GDSIIErrorKind::UnexpectedEof { .. }) => {}
+        other => panic!("unexpected result {:?}", other.map(|_| ())),
+    }
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use common::record;
+use gds2_io::{ascii2gds, gds2ascii, parse_ascii, parse_gds2, GDSIIErrorKind};
+
+#[test]
+fn test_testcases() {
+    for entry in std::fs::read_dir("testcases").unwrap() {
+        let path = entry.unwrap().path();
+        let data = std::fs::read(&path).unwrap();
+        let text = gds2ascii(&data).unwrap();
+        assert_eq!(ascii2gds(&text).unwrap(), data, "{}", path.display());
+        let lib = parse_gds2(&path).unwrap();
+        assert_eq!(lib.to_ascii().unwrap(), text);
+    }
+}
+
+#[test]
+fn test_dump() {
+    let mut data = record(0x00, 0x02, &[0x02, 0x58]);
+    data.extend(record(0x02, 0x06, b"A\"\\\x01"));
+    // 1e-3 reads back from its decimal, 0.2 with 54 mantissa bits does not
+    let mut units = 0x3E4189374BC6A7F0u64.to_be_bytes().to_vec();
+    units.extend(0x4033333333333333u64.to_be_bytes());
+    data.extend(record(0x03, 0x05, &units));
+    data.extend(record(0x05, 0x02, &[0; 24]));
+    data.extend(record(0x06, 0x06, b"TOP\0"));
+    data.extend(record(0x08, 0x00, &[]));
+    data.extend(record(0x26, 0x01, &[0x80, 0x01]));
+    data.extend(record(0x10, 0x03, &[0xFF, 0xFF, 0xFF, 0xFE, 0, 0, 0, 7]));
+    data.extend(record(0x70, 0x0A, &[1, 2]));
+    data.extend(record(0x0D, 0x03, &[0, 1]));
+    data.extend(record(0x11, 0x00, &[]));
+    data.extend(record(0x07, 0x00, &[]));
+    data.extend(record(0x04, 0x00, &[]));
+    data.extend([0; 6]);
+    let text = gds2ascii(&data).unwrap();
+    let expected = "\
+00000000: HEADER INT2 600
+00000006: LIBNAME ASCII \"A\\\"\\\\\\x01\"
+0000000E: UNITS REAL8 0.001 0x4033333333333333
+00000022: BGNSTR INT2 0 0 0 0 0 0 0 0 0 0 0 0
+0000003E:   STRNAME ASCII \"TOP\"
+00000046:   BOUNDARY NODATA
+0000004A:     ELFLAGS BITARRAY 0x8001
+00000050:     XY INT4 -2 7
+0000005C:     0x70 0x0A raw 0102
+00000062:     LAYER INT4 raw 0001
+00000068:   ENDEL NODATA
+";
+    assert!(text.starts_with(expected), "{}", text);
+    assert!(text.ends_with("ENDSTR NODATA\n00000070: ENDLIB NODATA\n00000074: TAIL zeros 6\n"));
+}
+
+#[test]
+fn test_edit() {
+    let text = "\
+# a hand written library
+HEADER INT2 600
+BGNLIB INT2 0 0 0 0 0 0 0 0 0 0 0 0
+LIBNAME ASCII \"LIB\"
+UNITS REAL8 0.001 1e-9
+
+BGNSTR INT2 0 0 0 0 0 0 0 0 0 0 0 0
+  STRNAME ASCII \"TOP\"
+  BOUNDARY NODATA
+    LAYER INT2 3
+    DATATYPE INT2 0
+    XY INT4 0 0 10 0 10 10 0 0
+  ENDEL NODATA
+ENDSTR NODATA
+ENDLIB NODATA
+";
+    let lib = parse_ascii(text).unwrap();
+    assert_eq!(lib.units(), Some((0.001, 1e-9)));
+    let top = lib.structure("TOP").unwrap();
+    assert_eq!(top.elements[0].layer(), Some(3));
+    // and the dump of the result reads the same records
+    let dumped = lib.to_ascii().unwrap();
+    assert_eq!(ascii2gds(&dumped).unwrap(), ascii2gds(text).unwrap());
+}
+
+#[test]
+fn test_errors() {
+    match ascii2gds("HEADER INT2 600\nLAYER INT2 70000\n") {
+        Err(GDSIIErrorKind::InvalidAscii { line: 2, .. }) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+    assert!(matches!(
+        ascii2gds("FOO NODATA"),
+        Err(GDSIIErrorKind::InvalidAscii { line: 1, .. })
+    ));
+    assert!(matches!(
+        gds2ascii(&record(0x00, 0x02, &[0x02, 0x58])),
+        Err(GDSIIErrorKind::UnexpectedEof { offset: 6, .. })
+    ));
+}
@@ -0,0 +1,145 @@
+use gds2_io::{
+    parse_gds2, ARef, BBox, BBoxOptions, Boundary, Element, GDSIIErrorKind, Library, Path, Real8,
+    SRef, Strans, Structure, Text,
+};
+
+fn path(pathtype: i16, ext: (i32, i32)) -> Path {
+    Path {
+        pathtype: Some(pathtype),
+        width: Some(10),
+        bgnextn: Some(ext.0),
+        endextn: Some(ext.1),
+        points: vec![(0, 0), (100, 0), (100, 50)],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_path_bbox() {
+    assert_eq!(path(0, (0, 0)).bbox(), Some(BBox::new((0, -5), (105, 50))));
+    assert_eq!(path(1, (0, 0)).bbox(), Some(BBox::new((-5, -5), (105, 55))));
+    assert_eq!(path(2, (0, 0)).bbox(), Some(BBox::new((-5, -5), (105, 55))));
+    assert_eq!(
+        path(4, (20, 7)).bbox(),
+        Some(BBox::new((-20, -5), (105, 57)))
+    );
+    let diagonal = Path {
+        width: Some(-2),
+        points: vec![(0, 0), (10, 10)],
+        ..Default::default()
+    };
+    // 1 / sqrt(2) either side, rounded outwards
+    assert_eq!(diagonal.bbox(), Some(BBox::new((-1, -1), (11, 11))));
+}
+
+fn library() -> Library {
+    let square = Element::Boundary(Boundary {
+        points: vec![(0, 0), (10, 0), (10, 20), (0, 20), (0, 0)],
+        ..Default::default()
+    });
+    let text = Element::Text(Text {
+        string: "far".to_string(),
+        origin: (-500, 0),
+        ..Default::default()
+    });
+    let sref = Element::SRef(SRef {
+        sname: "CELL".to_string(),
+        strans: Some(Strans {
+            angle: Some(Real8::from_f64(90.0)),
+            ..Default::default()
+        }),
+        origin: (0, 0),
+        ..Default::default()
+    });
+    let aref = Element::ARef(ARef {
+        sname: "CELL".to_string(),
+        cols: 4,
+        rows: 3,
+        origin: (1000, 0),
+        col_point: (1400, 0),
+        row_point: (1000, 300),
+        ..Default::default()
+    });
+    Library {
+        structures: vec![
+            Structure {
+                elements: vec![sref, aref],
+                ..Structure::new("TOP")
+            },
+            Structure {
+                elements: vec![square, text],
+                ..Structure::new("CELL")
+            },
+        ],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_hierarchical_bbox() {
+    let lib = library();
+    let options = BBoxOptions::default();
+    assert_eq!(
+        lib.structure_bbox("CELL", &options).unwrap(),
+        Some(BBox::new((0, 0), (10, 20)))
+    );
+    assert_eq!(
+        lib.bbox(&options).unwrap(),
+        Some(BBox::new((-20, 0), (1310, 220)))
+    );
+    let with_text = BBoxOptions { include_text: true };
+    let mut cache = lib.bboxes(&with_text);
+    assert_eq!(
+        cache.structure("CELL").unwrap(),
+        Some(BBox::new((-500, 0), (10, 20)))
+    );
+    assert_eq!(
+        cache.structure("TOP").unwrap(),
+        Some(BBox::new((-20, -500), (1310, 220)))
+    );
+}
+
+#[test]
+fn test_bbox_errors() {
+    let mut lib = library();
+    lib.structures[1].name = "OTHER".to_string();
+    match lib.bbox(&BBoxOptions::default()) {
+        Err(GDSIIErrorKind::UndefinedStructure {
+            name,
+            referenced_from: Some(parent),
+        }) => assert_eq!((name.as_str(), parent.as_str()), ("CELL", "TOP")),
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn test_testcase_bbox() {
+    let lib = parse_gds2("testcases/sim_c2.gds").unwrap();
+    let points: Vec<_> = lib.structures[0]
+        .elements
+        .iter()
+        .flat_map(|e| match e {
+            Element::Boundary(b) => b.points.clone(),
+            other => panic!("unexpected element {:?}", other),
+        })
+        .collect();
+    assert_eq!(
+        lib.bbox(&BBoxOptions::default()).unwrap(),
+        BBox::from_points(&points)
+    );
+}
+
+#[test]
+fn test_acute_path_bbox() {
+    // a 120 degree turn, mitred out to twice the half width past the corner
+    let acute = Path {
+        width: Some(10),
+        points: vec![(0, 0), (100, 0), (50, 87)],
+        ..Default::default()
+    };
+    let bbox = acute.bbox().unwrap();
+    assert_eq!(bbox.max.0, 109);
+    for (x, y) in acute.outline() {
+        assert!(bbox.contains((x.round() as i32, y.round() as i32)));
+    }
+}
@@ -0,0 +1,106 @@
+// helpers shared by the integration tests, every test file uses only some
+#![allow(dead_code)]
+
+use gds2_io::{Boundary, Element, Library, Point, SRef, Structure};
+
+// one stream record, its size taken from `data`
+pub fn record(rtype: u8, dtype: u8, data: &[u8]) -> Vec<u8> {
+    let mut r = ((data.len() + 4) as u16).to_be_bytes().to_vec();
+    r.extend([rtype, dtype]);
+    r.extend(data);
+    r
+}
+
+pub fn i16s(d: &[i16]) -> Vec<u8> {
+    d.iter().flat_map(|v| v.to_be_bytes()).collect()
+}
+
+pub fn i32s(d: &[i32]) -> Vec<u8> {
+    d.iter().flat_map(|v| v.to_be_bytes()).collect()
+}
+
+// the header record `rtype`, `records` and ENDEL
+pub fn element(rtype: u8, records: &[Vec<u8>]) -> Vec<u8> {
+    let mut data = record(rtype, 0x00, &[]);
+    for r in records {
+        data.extend(r);
+    }
+    data.extend(record(0x11, 0x00, &[]));
+    data
+}
+
+// HEADER ... UNITS, BGNSTR, STRNAME "TOP" followed by `body`, ENDSTR, ENDLIB
+pub fn stream(body: &[Vec<u8>]) -> Vec<u8> {
+    let mut data = record(0x00, 0x02, &i16s(&[600]));
+    data.extend(record(0x01, 0x02, &i16s(&[0; 12])));
+    data.extend(record(0x02, 0x06, b"LIB\0"));
+    data.extend(record(
+        0x03,
+        0x05,
+        &[
+            0x3E, 0x41, 0x89, 0x37, 0x4B, 0xC6, 0xA7, 0xF0, 0x39, 0x44, 0xB8, 0x2F, 0xA0, 0x9B,
+            0x5A, 0x54,
+        ],
+    ));
+    data.extend(record(0x05, 0x02, &i16s(&[0; 12])));
+    data.extend(record(0x06, 0x06, b"TOP\0"));
+    for r in body {
+        data.extend(r);
+    }
+    data.extend(record(0x07, 0x00, &[]));
+    data.extend(record(0x04, 0x00, &[]));
+    data
+}
+
+// write `data` to target/`name`.gds and return its path
+pub fn write(name: &str, data: &[u8]) -> String {
+    let path = format!(
+        "{}/target/{}.gds",
+        std::env::var("CARGO_MANIFEST_DIR").unwrap(),
+        name
+    );
+    std::fs::write(&path, data).unwrap();
+    path
+}
+
+// BOUNDARY square on `layer` with its lower left corner at `at`
+pub fn square(layer: i16, at: Point, size: i32) -> Element {
+    let (x, y) = at;
+    Element::Boundary(Boundary {
+        layer,
+        points: vec![
+            (x, y),
+            (x + size, y),
+            (x + size, y + size),
+            (x, y + size),
+            (x, y),
+        ],
+        ..Default::default()
+    })
+}
+
+pub fn sref(sname: &str, origin: Point) -> Element {
+    Element::SRef(SRef {
+        sname: sname.to_string(),
+        origin,
+        ..Default::default()
+    })
+}
+
+pub fn structure(name: &str, elements: Vec<Element>) -> Structure {
+    Structure {
+        elements,
+        ..Structure::new(name)
+    }
+}
+
+// library of the (name, elements) structures in order
+pub fn library(structures: &[(&str, Vec<Element>)]) -> Library {
+    Library {
+        structures: structures
+            .iter()
+            .map(|(name, elements)| structure(name, elements.clone()))
+            .collect(),
+        ..Default::default()
+    }
+}
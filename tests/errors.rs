@@ -0,0 +1,221 @@
+mod common;
+
+use common::{element, i16s, record, stream, write};
+use gds2_io::{parse_gds2, ExpectedLength, GDSIIErrorKind};
+
+fn xy() -> Vec<u8> {
+    let points: Vec<i32> = vec![0, 0, 10, 0, 10, 10, 0, 0];
+    record(
+        0x10,
+        0x03,
+        &points
+            .iter()
+            .flat_map(|v| v.to_be_bytes())
+            .collect::<Vec<_>>(),
+    )
+}
+
+fn parse(name: &str, data: &[u8]) -> Result<usize, GDSIIErrorKind> {
+    parse_gds2(write(&format!("errors_{}", name), data)).map(|lib| lib.structures[0].elements.len())
+}
+
+#[test]
+fn test_valid() {
+    let body = [element(
+        0x08,
+        &[
+            record(0x0D, 0x02, &i16s(&[1])),
+            record(0x0E, 0x02, &i16s(&[0])),
+            xy(),
+        ],
+    )];
+    assert_eq!(parse("valid", &stream(&body)).unwrap(), 1);
+}
+
+#[test]
+fn test_invalid_length() {
+    let body = [element(
+        0x08,
+        &[
+            record(0x0D, 0x02, &i16s(&[1, 2])),
+            record(0x0E, 0x02, &i16s(&[0])),
+            xy(),
+        ],
+    )];
+    let e = parse("length", &stream(&body)).unwrap_err();
+    match e {
+        GDSIIErrorKind::InvalidLength {
+            offset,
+            record,
+            expected,
+            actual,
+            ref structure,
+        } => {
+            assert_eq!(offset, 0x66);
+            assert_eq!(record, "LAYER");
+            assert_eq!(expected, ExpectedLength::Exact(2));
+            assert_eq!(actual, 4);
+            assert_eq!(structure.as_deref(), Some("TOP"));
+        }
+        e => panic!("unexpected error {:?}", e),
+    }
+    assert_eq!(
+        e.to_string(),
+        "LAYER record at offset 0x66 in structure `TOP`: expected 2 bytes of data, found 4"
+    );
+}
+
+#[test]
+fn test_unknown_record() {
+    let body = [record(0x70, 0x02, &i16s(&[1]))];
+    match parse("unknown", &stream(&body)).unwrap_err() {
+        GDSIIErrorKind::UnknownRecord {
+            rtype,
+            dtype,
+            structure,
+            ..
+        } => {
+            assert_eq!((rtype, dtype), (0x70, 0x02));
+            assert_eq!(structure.as_deref(), Some("TOP"));
+        }
+        e => panic!("unexpected error {:?}", e),
+    }
+}
+
+#[test]
+fn test_invalid_string() {
+    let body = [record(0x0C, 0x00, &[]), record(0x19, 0x06, &[0xFF, 0xFE])];
+    match parse("string", &stream(&body)).unwrap_err() {
+        GDSIIErrorKind::InvalidString { record, .. } => assert_eq!(record, "STRING"),
+        e => panic!("unexpected error {:?}", e),
+    }
+}
+
+#[test]
+fn test_grammar() {
+    // DATATYPE missing
+    let body = [element(0x08, &[record(0x0D, 0x02, &i16s(&[1])), xy()])];
+    match parse("missing", &stream(&body)).unwrap_err() {
+        GDSIIErrorKind::MissingRecord {
+            offset,
+            element,
+            missing,
+            ..
+        } => {
+            assert_eq!(offset, 0x62);
+            assert_eq!((element, missing), ("BOUNDARY", "DATATYPE"));
+        }
+        e => panic!("unexpected error {:?}", e),
+    }
+
+    // WIDTH is not part of a BOUNDARY
+    let body = [element(
+        0x08,
+        &[
+            record(0x0D, 0x02, &i16s(&[1])),
+            record(0x0E, 0x02, &i16s(&[0])),
+            record(0x0F, 0x03, &[0, 0, 0, 1]),
+            xy(),
+        ],
+    )];
+    match parse("unexpected", &stream(&body)).unwrap_err() {
+        GDSIIErrorKind::UnexpectedRecord { record, offset, .. } => {
+            assert_eq!(record, "WIDTH");
+            assert_eq!(offset, 0x72);
+        }
+        e => panic!("unexpected error {:?}", e),
+    }
+
+    // MAG without the STRANS it belongs to
+    let sref = element(
+        0x0A,
+        &[
+            record(0x12, 0x06, b"AB"),
+            record(0x1B, 0x05, &[0x41, 0x20, 0, 0, 0, 0, 0, 0]),
+            record(0x10, 0x03, &[0; 8]),
+        ],
+    );
+    match parse("mag", &stream(&[sref])).unwrap_err() {
+        GDSIIErrorKind::UnexpectedRecord { record, offset, .. } => {
+            assert_eq!((record, offset), ("MAG", 0x6C));
+        }
+        e => panic!("unexpected error {:?}", e),
+    }
+
+    // LAYER given twice
+    let body = [element(
+        0x08,
+        &[
+            record(0x0D, 0x02, &i16s(&[1])),
+            record(0x0D, 0x02, &i16s(&[2])),
+            record(0x0E, 0x02, &i16s(&[0])),
+            xy(),
+        ],
+    )];
+    match parse("twice", &stream(&body)).unwrap_err() {
+        GDSIIErrorKind::UnexpectedRecord { record, offset, .. } => {
+            assert_eq!((record, offset), ("LAYER", 0x6C));
+        }
+        e => panic!("unexpected error {:?}", e),
+    }
+
+    // ENDEL without an element
+    let body = [record(0x11, 0x00, &[])];
+    match parse("endel", &stream(&body)).unwrap_err() {
+        GDSIIErrorKind::UnexpectedRecord { record, .. } => assert_eq!(record, "ENDEL"),
+        e => panic!("unexpected error {:?}", e),
+    }
+
+    // library records after the first structure would be saved before it
+    let mut data = stream(&[]);
+    let at = data.len() - 4;
+    data.truncate(at);
+    data.extend(record(0x22, 0x02, &i16s(&[3])));
+    data.extend(record(0x04, 0x00, &[]));
+    match parse("late_header", &data).unwrap_err() {
+        GDSIIErrorKind::UnexpectedRecord {
+            record,
+            offset,
+            structure,
+        } => {
+            assert_eq!((record, offset), ("GENERATIONS", at));
+            assert_eq!(structure, None);
+        }
+        e => panic!("unexpected error {:?}", e),
+    }
+}
+
+#[test]
+fn test_truncated() {
+    let data = std::fs::read(format!(
+        "{}/testcases/sim_c1.gds",
+        std::env::var("CARGO_MANIFEST_DIR").unwrap(),
+    ))
+    .unwrap();
+    match parse("truncated", &data[..1001]).unwrap_err() {
+        GDSIIErrorKind::UnexpectedEof { structure, .. } => {
+            assert_eq!(structure.as_deref(), Some("debug"))
+        }
+        e => panic!("unexpected error {:?}", e),
+    }
+    // zero padding where a record should be
+    let mut data = data[..998].to_vec();
+    data.extend([0u8; 8]);
+    match parse("zero", &data).unwrap_err() {
+        GDSIIErrorKind::InvalidRecordSize { offset, size, .. } => {
+            assert_eq!((offset, size), (998, 0))
+        }
+        e => panic!("unexpected error {:?}", e),
+    }
+}
+
+#[test]
+fn test_odd_record_size() {
+    let body = [record(0x0C, 0x00, &[]), record(0x19, 0x06, b"odd")];
+    match parse("odd", &stream(&body)).unwrap_err() {
+        GDSIIErrorKind::InvalidRecordSize { offset, size, .. } => {
+            assert_eq!((offset, size), (0x66, 7))
+        }
+        e => panic!("unexpected error {:?}", e),
+    }
+}
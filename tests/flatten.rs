@@ -0,0 +1,141 @@
+mod common;
+
+use common::{square, sref, structure};
+use gds2_io::{
+    parse_gds2, ARef, Element, FlattenOptions, GDSIIErrorKind, Library, Real8, SRef, Strans, Text,
+};
+
+// TOP places MID rotated, MID holds a square and a 2x1 array of LEAF
+fn library() -> Library {
+    let aref = Element::ARef(ARef {
+        sname: "LEAF".to_string(),
+        cols: 2,
+        rows: 1,
+        origin: (0, 0),
+        col_point: (200, 0),
+        row_point: (0, 50),
+        ..Default::default()
+    });
+    let text = Element::Text(Text {
+        string: "pin".to_string(),
+        origin: (5, 5),
+        ..Default::default()
+    });
+    let mid = Element::SRef(SRef {
+        strans: Some(Strans {
+            angle: Some(Real8::from_f64(90.0)),
+            ..Default::default()
+        }),
+        sname: "MID".to_string(),
+        origin: (1000, 0),
+        ..Default::default()
+    });
+    common::library(&[
+        ("TOP", vec![mid, text]),
+        ("MID", vec![square(1, (0, 0), 10), aref]),
+        ("LEAF", vec![square(2, (0, 0), 10)]),
+    ])
+}
+
+fn first_points(e: &Element) -> Vec<(i32, i32)> {
+    match e {
+        Element::Boundary(b) => b.points.clone(),
+        other => panic!("unexpected element {:?}", other),
+    }
+}
+
+#[test]
+fn test_flatten_nested() {
+    let lib = library();
+    let flat: Vec<Element> = lib
+        .flat_elements("TOP", &FlattenOptions::default())
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(flat.len(), 3);
+    assert_eq!(first_points(&flat[0])[2], (990, 10));
+    // second LEAF instance sits 100 along MID's x axis, rotated onto y
+    assert_eq!(first_points(&flat[2])[0], (1000, 100));
+    assert_eq!(first_points(&flat[2])[2], (990, 110));
+    assert_eq!(flat[2].layer(), Some(2));
+
+    let flattened = lib.flatten("TOP", &FlattenOptions::default()).unwrap();
+    assert_eq!(flattened.structure_names().collect::<Vec<_>>(), ["TOP"]);
+    assert_eq!(flattened.structures[0].elements, flat);
+}
+
+#[test]
+fn test_flatten_options() {
+    let lib = library();
+    let options = FlattenOptions {
+        max_depth: Some(1),
+        keep_text: true,
+        ..Default::default()
+    };
+    let flat: Vec<Element> = lib
+        .flat_elements("TOP", &options)
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(flat.len(), 3);
+    match &flat[1] {
+        Element::ARef(a) => {
+            assert_eq!(a.origin, (1000, 0));
+            assert_eq!(a.col_point, (1000, 200));
+            assert_eq!(a.strans.unwrap().angle_degrees(), 90.0);
+        }
+        other => panic!("unexpected element {:?}", other),
+    }
+    assert!(matches!(flat[2], Element::Text(_)));
+}
+
+#[test]
+fn test_flatten_errors() {
+    let mut lib = library();
+    match lib.flatten("NONE", &FlattenOptions::default()) {
+        Err(GDSIIErrorKind::UndefinedStructure {
+            referenced_from: None,
+            ..
+        }) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+    lib.structures[2].elements.push(sref("MID", (0, 0)));
+    match lib.flatten("TOP", &FlattenOptions::default()) {
+        Err(GDSIIErrorKind::ReferenceCycle { cycle }) => {
+            assert_eq!(cycle, ["MID", "LEAF", "MID"])
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+    lib.structures[2].elements.pop();
+    lib.structures.pop();
+    match lib.flatten("TOP", &FlattenOptions::default()) {
+        Err(e @ GDSIIErrorKind::UndefinedStructure { .. }) => assert_eq!(
+            e.to_string(),
+            "structure `LEAF` is not defined, referenced from `MID`"
+        ),
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn test_flatten_duplicate_names() {
+    let mut lib = library();
+    lib.structures
+        .push(structure("LEAF", vec![square(3, (0, 0), 10)]));
+    lib.structures
+        .push(structure("TOP", vec![sref("LEAF", (0, 0))]));
+    let flat = lib.flatten("TOP", &FlattenOptions::default()).unwrap();
+    let layers: Vec<_> = flat.structures[0]
+        .elements
+        .iter()
+        .map(|e| e.layer())
+        .collect();
+    assert_eq!(layers, [Some(1), Some(2), Some(2)]);
+}
+
+#[test]
+fn test_flatten_testcase() {
+    let lib = parse_gds2("testcases/sim_c1.gds").unwrap();
+    let flat = lib.flatten("debug", &FlattenOptions::default()).unwrap();
+    assert_eq!(flat.structures[0].elements, lib.structures[0].elements);
+}
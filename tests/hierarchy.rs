@@ -0,0 +1,92 @@
+mod common;
+
+use common::{library, sref};
+use gds2_io::{parse_gds2, ARef, Element, GDSIIErrorKind};
+
+fn aref(sname: &str, cols: i16, rows: i16) -> Element {
+    Element::ARef(ARef {
+        sname: sname.to_string(),
+        cols,
+        rows,
+        ..Default::default()
+    })
+}
+
+#[test]
+fn test_graph() {
+    let lib = library(&[
+        ("LEAF", vec![]),
+        (
+            "TOP",
+            vec![sref("MID", (0, 0)), sref("MID", (0, 0)), aref("LEAF", 3, 2)],
+        ),
+        ("MID", vec![aref("LEAF", 2, 2), sref("VIA", (0, 0))]),
+        ("SPARE", vec![sref("LEAF", (0, 0))]),
+    ]);
+    let graph = lib.hierarchy();
+    assert_eq!(graph.tops(), ["TOP", "SPARE"]);
+    assert_eq!(graph.children("TOP"), ["MID", "LEAF"]);
+    assert_eq!(graph.parents("LEAF"), ["TOP", "MID", "SPARE"]);
+    assert_eq!(graph.placements("TOP", "MID"), 2);
+    assert_eq!(graph.placements("TOP", "LEAF"), 6);
+    assert_eq!(graph.dangling(), [("MID".to_string(), "VIA".to_string())]);
+    assert_eq!(
+        graph.topological_order().unwrap(),
+        ["TOP", "SPARE", "MID", "LEAF"]
+    );
+    let counts = graph.instance_counts().unwrap();
+    assert_eq!(counts["TOP"], 1);
+    assert_eq!(counts["MID"], 2);
+    // 6 in TOP, 2 * 4 through MID, 1 in SPARE
+    assert_eq!(counts["LEAF"], 15);
+}
+
+#[test]
+fn test_cycle() {
+    let lib = library(&[
+        ("TOP", vec![sref("A", (0, 0))]),
+        ("A", vec![sref("B", (0, 0))]),
+        ("B", vec![sref("C", (0, 0))]),
+        ("C", vec![sref("A", (0, 0))]),
+    ]);
+    let graph = lib.hierarchy();
+    assert_eq!(graph.tops(), ["TOP"]);
+    for result in [
+        graph.topological_order().map(|_| ()),
+        graph.instance_counts().map(|_| ()),
+    ] {
+        match result {
+            Err(GDSIIErrorKind::ReferenceCycle { cycle }) => {
+                assert_eq!(cycle, ["A", "B", "C", "A"])
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn test_testcase() {
+    let lib = parse_gds2("testcases/sim_c6.gds").unwrap();
+    let graph = lib.hierarchy();
+    assert_eq!(graph.tops(), ["debug"]);
+    assert!(graph.children("debug").is_empty());
+    assert!(graph.dangling().is_empty());
+}
+
+#[test]
+fn test_duplicate_names() {
+    // the second B is shadowed, its placement of D does not count
+    let lib = library(&[
+        ("A", vec![sref("B", (0, 0))]),
+        ("B", vec![]),
+        ("D", vec![]),
+        ("B", vec![sref("D", (0, 0))]),
+    ]);
+    let graph = lib.hierarchy();
+    assert_eq!(graph.tops(), ["A", "D"]);
+    assert_eq!(graph.topological_order().unwrap(), ["A", "D", "B"]);
+    let counts = graph.instance_counts().unwrap();
+    assert_eq!((counts["B"], counts["D"]), (1, 1));
+    let summary = lib.summerize();
+    assert_eq!(summary.tops, ["A", "D"]);
+}
@@ -0,0 +1,122 @@
+#![cfg(feature = "serde")]
+
+use gds2_io::{
+    parse_gds2_with, Boundary, Element, FileHeader, GDSIIErrorKind, Library, ParseOptions, Real8,
+    SRef, Strans, Structure,
+};
+use proptest::prelude::*;
+
+#[test]
+fn test_testcases() {
+    let options = ParseOptions { keep_unknown: true };
+    for entry in std::fs::read_dir("testcases").unwrap() {
+        let path = entry.unwrap().path();
+        let lib = parse_gds2_with(&path, &options).unwrap();
+        for json in [lib.to_json(), lib.to_json_pretty()] {
+            let read = Library::from_json(&json).unwrap();
+            assert_eq!(
+                read.to_bytes().unwrap(),
+                std::fs::read(&path).unwrap(),
+                "{}",
+                path.display()
+            );
+        }
+    }
+}
+
+#[test]
+fn test_schema() {
+    let mut top = Structure::new("TOP");
+    top.elements = vec![
+        Element::Boundary(Boundary {
+            layer: 1,
+            points: vec![(0, 0), (10, 0), (10, 10), (0, 0)],
+            ..Default::default()
+        }),
+        Element::SRef(SRef {
+            sname: "A".to_string(),
+            strans: Some(Strans {
+                angle: Some(Real8::from_f64(90.0)),
+                mag: Some(Real8::from_bits(0x4033333333333333)),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }),
+    ];
+    let lib = Library {
+        header: vec![FileHeader::Header(600), FileHeader::EndMask],
+        structures: vec![top],
+        ..Default::default()
+    };
+    let json: serde_json::Value = serde_json::from_str(&lib.to_json()).unwrap();
+    let expected = serde_json::json!({
+        "header": [{"record": "HEADER", "value": 600}, {"record": "ENDMASKS"}],
+        "structures": [{
+            "name": "TOP",
+            "created": {"year": 0, "month": 0, "day": 0, "hour": 0, "minute": 0, "second": 0},
+            "modified": {"year": 0, "month": 0, "day": 0, "hour": 0, "minute": 0, "second": 0},
+            "strclass": null,
+            "strtype": null,
+            "elements": [
+                {
+                    "type": "boundary",
+                    "elflags": null,
+                    "plex": null,
+                    "layer": 1,
+                    "datatype": 0,
+                    "points": [[0, 0], [10, 0], [10, 10], [0, 0]],
+                    "props": []
+                },
+                {
+                    "type": "sref",
+                    "elflags": null,
+                    "plex": null,
+                    "sname": "A",
+                    "strans": {
+                        "reflect_x": false,
+                        "abs_mag": false,
+                        "abs_angle": false,
+                        "mag": "0x4033333333333333",
+                        "angle": 90.0,
+                        "reserved": 0
+                    },
+                    "origin": [0, 0],
+                    "props": []
+                }
+            ]
+        }],
+        "tail": []
+    });
+    assert_eq!(json, expected);
+
+    // fields left out take their defaults
+    let read = Library::from_json(
+        r#"{"structures": [{"name": "A", "elements": [{"type": "text", "string": "x"}]}]}"#,
+    )
+    .unwrap();
+    assert_eq!(read.structures[0].elements[0].layer(), Some(0));
+}
+
+#[test]
+fn test_invalid() {
+    assert!(matches!(
+        Library::from_json(r#"{"structures": [{"elements": [{"type": "circle"}]}]}"#),
+        Err(GDSIIErrorKind::InvalidJson { .. })
+    ));
+}
+
+proptest! {
+    #[test]
+    fn prop_real8_round_trip(a in any::<u64>(), b in any::<u64>()) {
+        let units = [Real8::from_bits(a), Real8::from_bits(b)];
+        let lib = Library {
+            header: vec![FileHeader::Units(units)],
+            ..Default::default()
+        };
+        let read = Library::from_json(&lib.to_json()).unwrap();
+        let [FileHeader::Units(read)] = read.header[..] else {
+            panic!("{:?}", read.header);
+        };
+        prop_assert_eq!(read, units);
+    }
+}
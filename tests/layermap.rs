@@ -0,0 +1,184 @@
+use gds2_io::{
+    parse_gds2, Boundary, CadenceLayerMap, Element, GDSIIErrorKind, GdsBox, LayerMap, Library,
+    Node, Path, RawRecord, SRef, Structure, Text, Unmapped,
+};
+
+fn library() -> Library {
+    let mut top = Structure::new("TOP");
+    top.elements = vec![
+        Element::Boundary(Boundary {
+            layer: 31,
+            datatype: 0,
+            ..Default::default()
+        }),
+        Element::Path(Path {
+            layer: 40,
+            datatype: 3,
+            ..Default::default()
+        }),
+        Element::Text(Text {
+            layer: 31,
+            texttype: 2,
+            ..Default::default()
+        }),
+        Element::SRef(SRef {
+            sname: "X".to_string(),
+            ..Default::default()
+        }),
+        Element::Node(Node {
+            layer: 7,
+            nodetype: 1,
+            ..Default::default()
+        }),
+        Element::Box(GdsBox {
+            layer: 50,
+            boxtype: 9,
+            ..Default::default()
+        }),
+    ];
+    Library {
+        structures: vec![top],
+        ..Default::default()
+    }
+}
+
+fn numbers(lib: &Library) -> Vec<(i16, i16)> {
+    lib.structures[0]
+        .elements
+        .iter()
+        .filter_map(|e| Some((e.layer()?, e.datatype()?)))
+        .collect()
+}
+
+const MAP: &str = "
+# from -> to
+31 0 -> 12 5
+31/2 -> 12/6   # pins
+40 * -> 41 *
+50 * -> 51 0
+";
+
+#[test]
+fn test_text_map() {
+    let map = LayerMap::parse(MAP).unwrap();
+    assert_eq!(map.len(), 4);
+    assert_eq!(map.get(40, 7), Some((41, 7)));
+    assert_eq!(map.get(31, 1), None);
+
+    let mut lib = library();
+    lib.remap_layers(&map, Unmapped::Keep).unwrap();
+    assert_eq!(numbers(&lib), [(12, 5), (41, 3), (12, 6), (7, 1), (51, 0)]);
+
+    let mut lib = library();
+    lib.remap_layers(&map, Unmapped::Drop).unwrap();
+    assert_eq!(numbers(&lib), [(12, 5), (41, 3), (12, 6), (51, 0)]);
+    assert_eq!(lib.structures[0].elements.len(), 5);
+
+    let mut lib = library();
+    match lib.remap_layers(&map, Unmapped::Error) {
+        Err(GDSIIErrorKind::UnmappedLayer {
+            layer: 7,
+            datatype: 1,
+            structure,
+        }) => assert_eq!(structure, "TOP"),
+        other => panic!("unexpected result {:?}", other),
+    }
+    assert_eq!(numbers(&lib), numbers(&library()));
+}
+
+#[test]
+fn test_drop_keeps_raw_records() {
+    let raw = |rtype: u8| RawRecord {
+        rtype,
+        dtype: 0,
+        bytes: vec![],
+    };
+    let mut lib = library();
+    // before the path, the node, the box and at the end
+    lib.structures[0].raw = vec![
+        (1, raw(0x60)),
+        (4, raw(0x61)),
+        (5, raw(0x62)),
+        (6, raw(0x63)),
+    ];
+    let mut expected = lib.structures[0].clone();
+    expected.remove_element(4);
+    let map = LayerMap::parse(MAP).unwrap();
+    lib.remap_layers(&map, Unmapped::Drop).unwrap();
+    let anchors = |s: &Structure| {
+        s.raw
+            .iter()
+            .map(|(at, r)| (*at, r.rtype))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(anchors(&lib.structures[0]), anchors(&expected));
+    assert_eq!(
+        anchors(&lib.structures[0]),
+        [(1, 0x60), (4, 0x61), (4, 0x62), (5, 0x63)]
+    );
+
+    // dropping most of a large cell takes one pass
+    let mut top = Structure::new("TOP");
+    top.elements = (0..200_000)
+        .map(|i| {
+            Element::Boundary(Boundary {
+                layer: if i % 1000 == 0 { 31 } else { 1 },
+                ..Default::default()
+            })
+        })
+        .collect();
+    top.raw = vec![(200_000, raw(0x60))];
+    let mut lib = Library {
+        structures: vec![top],
+        ..Default::default()
+    };
+    lib.remap_layers(&map, Unmapped::Drop).unwrap();
+    assert_eq!(lib.structures[0].elements.len(), 200);
+    assert_eq!(lib.structures[0].raw[0].0, 200);
+}
+
+#[test]
+fn test_invalid_maps() {
+    for (text, line) in [
+        ("31 0 -> 12 5\n31 0 12 5", 2),
+        ("31 x -> 1 0", 1),
+        ("\n\n31 0 -> 12 *", 3),
+        ("31 -> 12 0", 1),
+    ] {
+        match LayerMap::parse(text) {
+            Err(GDSIIErrorKind::InvalidLayerMap { line: l, .. }) => assert_eq!(l, line, "{}", text),
+            other => panic!("unexpected result {:?} for {}", other, text),
+        }
+    }
+    assert!(CadenceLayerMap::parse("M1 drawing 31").is_err());
+}
+
+#[test]
+fn test_cadence_maps() {
+    let old = CadenceLayerMap::parse(
+        "# name purpose layer datatype
+         M1 drawing 31 0
+         M1 pin     31 2  mask1
+         VIA1 drawing 32 0",
+    )
+    .unwrap();
+    let new = CadenceLayerMap::parse("M1 drawing 12 5\nM1 pin 12 6").unwrap();
+    assert_eq!(old.get("M1", "pin").unwrap().datatype, 2);
+    let map = LayerMap::between(&old, &new);
+    assert_eq!(map.get(31, 0), Some((12, 5)));
+    assert_eq!(map.get(31, 2), Some((12, 6)));
+    assert_eq!(map.get(32, 0), None);
+}
+
+#[test]
+fn test_testcase_remap() {
+    let mut lib = parse_gds2("testcases/sim_c5.gds").unwrap();
+    let before = numbers(&lib);
+    let mut map = LayerMap::new();
+    for (layer, datatype) in &before {
+        map.insert((*layer, *datatype), (layer + 100, *datatype));
+    }
+    lib.remap_layers(&map, Unmapped::Error).unwrap();
+    let shifted: Vec<_> = before.iter().map(|(l, d)| (l + 100, *d)).collect();
+    assert_eq!(numbers(&lib), shifted);
+}
@@ -0,0 +1,87 @@
+mod common;
+
+use common::{square, sref};
+use gds2_io::{parse_gds2, ARef, Element, GDSIIErrorKind, Library, Text};
+
+fn library() -> Library {
+    let top = vec![
+        square(31, (-100, 0), 10),
+        Element::ARef(ARef {
+            sname: "VIA".to_string(),
+            cols: 2,
+            rows: 1,
+            origin: (0, 0),
+            col_point: (100, 0),
+            row_point: (0, 10),
+            ..Default::default()
+        }),
+        sref("PAD", (0, 500)),
+        Element::Text(Text {
+            layer: 31,
+            texttype: 5,
+            string: "net".to_string(),
+            ..Default::default()
+        }),
+    ];
+    common::library(&[
+        ("TOP", top),
+        ("VIA", vec![square(31, (0, 0), 10), square(32, (0, 0), 10)]),
+        ("PAD", vec![square(1, (0, 0), 10)]),
+    ])
+}
+
+fn first_x(e: &Element) -> i32 {
+    match e {
+        Element::Boundary(b) => b.points[0].0,
+        other => panic!("unexpected element {:?}", other),
+    }
+}
+
+#[test]
+fn test_layer_queries() {
+    let lib = library();
+    let index = lib.layer_index();
+    let layers: Vec<_> = index.layers().into_iter().collect();
+    assert_eq!(
+        layers,
+        [((1, 0), 1), ((31, 0), 2), ((31, 5), 1), ((32, 0), 1)]
+    );
+    assert_eq!(index.structure_layers("VIA").len(), 2);
+    assert_eq!(index.shapes_on("TOP", 31, 0).count(), 1);
+    assert_eq!(index.shapes_on("TOP", 31, 5).count(), 1);
+    assert_eq!(index.shapes_on("NONE", 31, 0).count(), 0);
+
+    let flat = index.flat_shapes_on("TOP", 31, 0).unwrap();
+    let xs: Vec<i32> = flat.iter().map(first_x).collect();
+    assert_eq!(xs, [-100, 0, 50]);
+    assert!(index.flat_shapes_on("TOP", 2, 0).unwrap().is_empty());
+}
+
+#[test]
+fn test_layer_query_errors() {
+    let mut lib = library();
+    lib.structures[2].name = "GONE".to_string();
+    // PAD draws nothing on 31/0, but is still checked
+    match lib.layer_index().flat_shapes_on("TOP", 31, 0) {
+        Err(GDSIIErrorKind::UndefinedStructure { name, .. }) => assert_eq!(name, "PAD"),
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn test_testcase_layers() {
+    let lib = parse_gds2("testcases/sim_c3.gds").unwrap();
+    let index = lib.layer_index();
+    let total: usize = index.layers().values().sum();
+    assert_eq!(total, lib.structures[0].elements.len());
+    for (&(layer, datatype), &count) in &index.layers() {
+        assert_eq!(index.shapes_on("debug", layer, datatype).count(), count);
+        assert_eq!(
+            index
+                .flat_shapes_on("debug", layer, datatype)
+                .unwrap()
+                .len(),
+            count
+        );
+    }
+}
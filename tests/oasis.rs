@@ -0,0 +1,436 @@
+use gds2_io::{
+    parse_bytes, parse_gds2, parse_oasis, parse_oasis_bytes, ARef, BBoxOptions, Boundary, Element,
+    FileHeader, GDSIIErrorKind, Library, OasisOptions, Path, Property, Real8, SRef, Strans,
+    Structure, Text,
+};
+
+fn rect(x: i32) -> Element {
+    Element::Boundary(Boundary {
+        layer: 1,
+        points: vec![(x, 0), (x + 10, 0), (x + 10, 20), (x, 20), (x, 0)],
+        ..Default::default()
+    })
+}
+
+fn library() -> Library {
+    let mut a = Structure::new("A");
+    a.elements = vec![rect(0), rect(100)];
+    let mut top = Structure::new("TOP");
+    top.elements = vec![
+        Element::SRef(SRef {
+            sname: "A".to_string(),
+            strans: Some(Strans {
+                angle: Some(Real8::from_f64(90.0)),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }),
+        Element::ARef(ARef {
+            sname: "A".to_string(),
+            cols: 3,
+            rows: 2,
+            origin: (5, 0),
+            col_point: (95, 0),
+            row_point: (5, 80),
+            ..Default::default()
+        }),
+    ];
+    Library {
+        header: vec![
+            FileHeader::Header(600),
+            FileHeader::BgnLib([0; 12]),
+            FileHeader::LibName("LIB".to_string()),
+            FileHeader::Units([Real8::from_f64(1e-3), Real8::from_f64(1e-9)]),
+        ],
+        structures: vec![a, top],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_oasis_bytes() {
+    let data = library().to_oasis(&OasisOptions::default());
+    let mut expected = b"%SEMI-OASIS\r\n".to_vec();
+    // START: version, 1000 grid steps per micron, offsets in END
+    expected.extend([0x01, 0x03, b'1', b'.', b'0', 0x00, 0xE8, 0x07, 0x01]);
+    // CELLNAME A, CELLNAME TOP
+    expected.extend([0x03, 0x01, b'A', 0x03, 0x03, b'T', b'O', b'P']);
+    // CELL 0, RECTANGLE with layer, datatype, width and height, then a
+    // RECTANGLE with only its x
+    expected.extend([0x0D, 0x00, 0x14, 0x63, 0x01, 0x00, 0x0A, 0x14]);
+    expected.extend([0x14, 0x10, 0xC8, 0x01]);
+    // CELL 1, PLACEMENT of cell 0 turned 90 degrees, PLACEMENT at x 5
+    // repeated as a 3 x 2 grid spaced 30 by 40
+    expected.extend([0x0D, 0x01, 0x11, 0xC2, 0x00]);
+    expected.extend([0x11, 0x28, 0x0A, 0x01, 0x01, 0x00, 0x1E, 0x28]);
+    assert_eq!(&data[..data.len() - 256], expected.as_slice());
+
+    let end = &data[data.len() - 256..];
+    // END, CELLNAME table at 22, no other tables
+    assert_eq!(&end[..3], [0x02, 0x01, 0x16]);
+    assert!(end[3..13].iter().all(|b| *b == 0));
+    // padding of 240 bytes, then no validation
+    assert_eq!(&end[13..15], [0xF0, 0x01]);
+    assert_eq!(end[255], 0);
+}
+
+#[test]
+fn test_cblock() {
+    let lib = parse_gds2("testcases/sim_c1.gds").unwrap();
+    let plain = lib.to_oasis(&OasisOptions::default());
+    let packed = lib.to_oasis(&OasisOptions { compress: true });
+    assert!(packed.len() < plain.len());
+    // CELLNAME debug, then CELL 0
+    let cell = b"%SEMI-OASIS\r\n".len() + 9 + 7 + 2;
+    assert_eq!(plain[..cell], packed[..cell]);
+    let body = &plain[cell..plain.len() - 256];
+    let mut at = cell;
+    let mut unsigned = || {
+        let (mut v, mut shift) = (0u64, 0);
+        loop {
+            let b = packed[at];
+            at += 1;
+            v |= ((b & 0x7F) as u64) << shift;
+            shift += 7;
+            if b & 0x80 == 0 {
+                return v as usize;
+            }
+        }
+    };
+    assert_eq!(unsigned(), 34);
+    assert_eq!(unsigned(), 0);
+    assert_eq!(unsigned(), body.len());
+    let compressed = unsigned();
+    let inflated = miniz_oxide::inflate::decompress_to_vec(&packed[at..at + compressed]).unwrap();
+    assert_eq!(inflated, body);
+    assert_eq!(packed.len() - at - compressed, 256);
+}
+
+#[test]
+fn test_save_oasis() {
+    let path = format!(
+        "{}/target/oasis_saved.oas",
+        std::env::var("CARGO_MANIFEST_DIR").unwrap()
+    );
+    let lib = library();
+    lib.save_oasis(&path, &OasisOptions::default()).unwrap();
+    assert_eq!(
+        std::fs::read(&path).unwrap(),
+        lib.to_oasis(&OasisOptions::default())
+    );
+}
+
+#[test]
+fn test_oasis_roundtrip() {
+    let lib = library();
+    let read = parse_oasis_bytes(&lib.to_oasis(&OasisOptions::default())).unwrap();
+    for (a, b) in lib.structures.iter().zip(&read.structures) {
+        assert_eq!(a.name, b.name);
+        assert_eq!(a.elements, b.elements);
+    }
+    assert_eq!(read.structures.len(), 2);
+    let units = read.units().unwrap();
+    assert!((units.0 - 1e-3).abs() < 1e-15 && (units.1 - 1e-9).abs() < 1e-21);
+}
+
+#[test]
+fn test_oasis_testcases() {
+    for entry in std::fs::read_dir("testcases").unwrap() {
+        let path = entry.unwrap().path();
+        let lib = parse_gds2(&path).unwrap();
+        let plain = parse_oasis_bytes(&lib.to_oasis(&OasisOptions::default())).unwrap();
+        let packed = parse_oasis_bytes(&lib.to_oasis(&OasisOptions { compress: true })).unwrap();
+        let opts = BBoxOptions::default();
+        for s in &lib.structures {
+            let a = plain.structure(&s.name).unwrap();
+            assert_eq!(a.elements, packed.structure(&s.name).unwrap().elements);
+            assert_eq!(
+                lib.structure_bbox(&s.name, &opts).unwrap(),
+                plain.structure_bbox(&s.name, &opts).unwrap(),
+                "{:?} {}",
+                path,
+                s.name
+            );
+        }
+        // and on to GDSII
+        let gds = parse_bytes(&plain.to_bytes().unwrap()).unwrap();
+        assert_eq!(gds.structures.len(), lib.structures.len());
+    }
+}
+
+fn unsigned(data: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        data.push((v & 0x7F) as u8 | 0x80);
+        v >>= 7;
+    }
+    data.push(v as u8);
+}
+
+fn signed(data: &mut Vec<u8>, v: i64) {
+    unsigned(data, (v.unsigned_abs() << 1) | (v < 0) as u64);
+}
+
+fn string(data: &mut Vec<u8>, s: &str) {
+    unsigned(data, s.len() as u64);
+    data.extend(s.as_bytes());
+}
+
+// records the writer never produces: relative positions, trapezoids, a
+// manhattan point list, extensions, irregular repetitions and a cell
+// named after its use
+fn handwritten() -> Vec<u8> {
+    let mut d = b"%SEMI-OASIS\r\n".to_vec();
+    d.extend([0x01, 0x03, b'1', b'.', b'0', 0x00, 0xE8, 0x07, 0x01]);
+    d.push(0x0E);
+    string(&mut d, "TOP");
+    d.push(0x10);
+    // TRAPEZOID on 2/0, 100 x 20, delta-a 10, delta-b -10
+    d.extend([0x17, 0x7B, 0x02, 0x00, 0x64, 0x14]);
+    signed(&mut d, 10);
+    signed(&mut d, -10);
+    d.extend([0x00, 0x00]);
+    // RECTANGLE of the same size 200 to the right
+    d.extend([0x14, 0x10]);
+    signed(&mut d, 200);
+    // POLYGON 50 x 30, 100 up
+    d.extend([0x15, 0x38, 0x00, 0x02]);
+    signed(&mut d, 50);
+    signed(&mut d, 30);
+    signed(&mut d, 0);
+    signed(&mut d, 100);
+    // PATH 10 wide, start extension 3, flush end, one g-delta (30, 40)
+    d.extend([0x16, 0xF8, 0x05, 0x0D]);
+    signed(&mut d, 3);
+    d.extend([0x04, 0x01, (30 << 2) | 1]);
+    signed(&mut d, 40);
+    signed(&mut d, 0);
+    signed(&mut d, 100);
+    // PLACEMENT of cell 7 at x 0, 10 and 35, with a GDSII property
+    d.extend([0x11, 0xF8, 0x07, 0x00, 0x00, 0x04, 0x01, 0x0A, 0x19]);
+    d.extend([0x1C, 0x25]);
+    string(&mut d, "S_GDS_PROPERTY");
+    d.extend([0x08, 0x01, 0x0A]);
+    string(&mut d, "hi");
+    // TEXT pin on 5/1 at (7, -3)
+    d.extend([0x13, 0x5B]);
+    string(&mut d, "pin");
+    d.extend([0x05, 0x01]);
+    signed(&mut d, 7);
+    signed(&mut d, -3);
+    // CTRAPEZOID type 0, 40 x 10, back down to y 0
+    d.extend([0x1A, 0xF8, 0x00, 0x28, 0x0A]);
+    signed(&mut d, 0);
+    signed(&mut d, -200);
+    // cell 7 is empty and named at the end
+    d.extend([0x0D, 0x07, 0x04]);
+    string(&mut d, "B");
+    d.push(0x07);
+    let mut end = vec![0x02];
+    end.resize(256, 0);
+    d.extend(end);
+    d
+}
+
+fn boundary(layer: i16, points: &[(i32, i32)]) -> Element {
+    let mut points = points.to_vec();
+    points.push(points[0]);
+    Element::Boundary(Boundary {
+        layer,
+        points,
+        ..Default::default()
+    })
+}
+
+#[test]
+fn test_oasis_records() {
+    let lib = parse_oasis_bytes(&handwritten()).unwrap();
+    assert_eq!(lib.structure("B").unwrap().elements, []);
+    let top = &lib.structure("TOP").unwrap().elements;
+    assert_eq!(top.len(), 9);
+    assert_eq!(top[0], boundary(2, &[(10, 20), (90, 20), (100, 0), (0, 0)]));
+    assert_eq!(
+        top[1],
+        boundary(2, &[(200, 0), (300, 0), (300, 20), (200, 20)])
+    );
+    assert_eq!(
+        top[2],
+        boundary(2, &[(200, 100), (250, 100), (250, 130), (200, 130)])
+    );
+    assert_eq!(
+        top[3],
+        Element::Path(Path {
+            layer: 2,
+            pathtype: Some(4),
+            width: Some(10),
+            bgnextn: Some(3),
+            endextn: Some(0),
+            points: vec![(200, 200), (230, 240)],
+            ..Default::default()
+        })
+    );
+    for (i, x) in [0, 10, 35].into_iter().enumerate() {
+        assert_eq!(
+            top[4 + i],
+            Element::SRef(SRef {
+                sname: "B".to_string(),
+                origin: (x, 0),
+                props: vec![Property {
+                    attr: 1,
+                    value: "hi".to_string()
+                }],
+                ..Default::default()
+            })
+        );
+    }
+    assert_eq!(
+        top[7],
+        Element::Text(Text {
+            layer: 5,
+            texttype: 1,
+            origin: (7, -3),
+            string: "pin".to_string(),
+            ..Default::default()
+        })
+    );
+    assert_eq!(
+        top[8],
+        boundary(2, &[(200, 0), (200, 10), (230, 10), (240, 0)])
+    );
+
+    let path = format!(
+        "{}/target/oasis_records.oas",
+        std::env::var("CARGO_MANIFEST_DIR").unwrap()
+    );
+    std::fs::write(&path, handwritten()).unwrap();
+    assert_eq!(parse_oasis(&path).unwrap().structures.len(), 2);
+}
+
+#[test]
+fn test_oasis_errors() {
+    let data = handwritten();
+    match parse_oasis_bytes(&data[..40]) {
+        Err(GDSIIErrorKind::InvalidOasis { offset: 38, .. }) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+    assert!(matches!(
+        parse_oasis_bytes(b"%SEMI-OASIS\r"),
+        Err(GDSIIErrorKind::InvalidOasis { offset: 0, .. })
+    ));
+}
+
+// a cell TOP holding `body`
+fn cell(body: &[u8]) -> Vec<u8> {
+    let mut d = b"%SEMI-OASIS\r\n".to_vec();
+    d.extend([0x01, 0x03, b'1', b'.', b'0', 0x00, 0xE8, 0x07, 0x01]);
+    d.push(0x0E);
+    string(&mut d, "TOP");
+    d.extend(body);
+    let mut end = vec![0x02];
+    end.resize(256, 0);
+    d.extend(end);
+    d
+}
+
+fn reason(data: &[u8]) -> String {
+    match parse_oasis_bytes(data) {
+        Err(GDSIIErrorKind::InvalidOasis { reason, .. }) => reason,
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn test_oasis_reference_offsets() {
+    // names resolved after the last record report the record using them
+    let at = cell(&[]).len() - 256;
+    let mut d = rectangle(1, 10, 10, 0, 0, &[]);
+    d.extend([0x11, 0xC0, 0x07]);
+    match parse_oasis_bytes(&cell(&d)) {
+        Err(GDSIIErrorKind::InvalidOasis { offset, reason }) => {
+            assert_eq!(offset, at + d.len() - 3);
+            assert_eq!(reason, "CELLNAME reference number 7 is not defined");
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+    let mut d = rectangle(1, 10, 10, 0, 0, &[]);
+    let property = at + d.len();
+    d.extend([0x1C, 0x25]);
+    string(&mut d, "S_GDS_PROPERTY");
+    d.extend([0x08, 0x01, 0x0D, 0x03]);
+    match parse_oasis_bytes(&cell(&d)) {
+        Err(GDSIIErrorKind::InvalidOasis { offset, reason }) => {
+            assert_eq!(offset, property);
+            assert_eq!(reason, "PROPSTRING reference number 3 is not defined");
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+// RECTANGLE with every field, `w` x `h` at (`x`, `y`), then `rest`
+fn rectangle(layer: u64, w: u64, h: u64, x: i64, y: i64, rest: &[u8]) -> Vec<u8> {
+    let mut d = vec![0x14, 0x7B];
+    unsigned(&mut d, layer);
+    unsigned(&mut d, 0);
+    unsigned(&mut d, w);
+    unsigned(&mut d, h);
+    signed(&mut d, x);
+    signed(&mut d, y);
+    d.extend(rest);
+    d
+}
+
+#[test]
+fn test_oasis_limits() {
+    // a 100000 x 100000 grid of rectangles is refused, not allocated
+    let mut grid = vec![0x01];
+    for v in [99_998, 99_998, 10, 10] {
+        unsigned(&mut grid, v);
+    }
+    let mut d = rectangle(1, 10, 10, 0, 0, &[]);
+    d[1] |= 0x04;
+    d.extend(&grid);
+    assert!(reason(&cell(&d)).contains("too large"));
+
+    // counts and steps that overflow
+    let mut count = vec![0x02];
+    unsigned(&mut count, u64::MAX);
+    unsigned(&mut count, 1);
+    let mut d = rectangle(1, 10, 10, 0, 0, &[]);
+    d[1] |= 0x04;
+    d.extend(&count);
+    assert_eq!(reason(&cell(&d)), "repetition count overflows");
+    let mut steps = vec![0x02];
+    unsigned(&mut steps, 1);
+    unsigned(&mut steps, i64::MAX as u64);
+    let mut d = rectangle(1, 10, 10, 0, 0, &[]);
+    d[1] |= 0x04;
+    d.extend(&steps);
+    assert_eq!(reason(&cell(&d)), "coordinate overflows");
+
+    // relative XGEOMETRY positions adding up past i64
+    let mut d = vec![0x10];
+    for _ in 0..3 {
+        d.extend([0x21, 0x10, 0x00, 0x00]);
+        signed(&mut d, i64::MAX / 2);
+    }
+    assert_eq!(reason(&cell(&d)), "coordinate overflows");
+
+    // a CTRAPEZOID twice as wide as a huge height
+    let mut d = vec![0x1A, 0xA3, 0x01, 0x00, 0x14];
+    unsigned(&mut d, u64::MAX / 2 + 1);
+    assert_eq!(reason(&cell(&d)), "coordinate overflows");
+
+    // a PATH whose width does not fit GDSII
+    let mut d = vec![0x16, 0xE3, 0x01, 0x00];
+    unsigned(&mut d, u64::MAX);
+    // flush ends, one g-delta of (10, 0)
+    d.extend([0x05, 0x04, 0x01]);
+    unsigned(&mut d, 10 << 4);
+    assert_eq!(reason(&cell(&d)), "path width is beyond the GDSII range");
+
+    // layers beyond i16 are refused, not truncated
+    let d = rectangle(40_000, 10, 10, 0, 0, &[]);
+    assert_eq!(reason(&cell(&d)), "layer 40000 is beyond the GDSII range");
+    let d = rectangle(32_767, 10, 10, 0, 0, &[]);
+    let lib = parse_oasis_bytes(&cell(&d)).unwrap();
+    assert_eq!(lib.structures[0].elements[0].layer(), Some(32_767));
+}
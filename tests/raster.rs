@@ -0,0 +1,225 @@
+mod common;
+
+use common::{square, sref};
+use gds2_io::{
+    parse_gds2, BBox, Boundary, Color, Element, Hatch, Image, LayerStyle, LayerStyles, Library,
+    Path, RasterOptions, Viewport,
+};
+
+fn library() -> Library {
+    let top = vec![
+        sref("CHILD", (20, 0)),
+        Element::Path(Path {
+            layer: 2,
+            width: Some(10),
+            points: vec![(0, 50), (100, 50)],
+            ..Default::default()
+        }),
+        // overlapping squares of one layer, the second one clockwise
+        square(5, (60, 0), 20),
+        Element::Boundary(Boundary {
+            layer: 5,
+            points: vec![(70, 0), (70, 20), (90, 20), (90, 0), (70, 0)],
+            ..Default::default()
+        }),
+    ];
+    common::library(&[("CHILD", vec![square(1, (0, 0), 10)]), ("TOP", top)])
+}
+
+fn options() -> RasterOptions {
+    RasterOptions {
+        viewport: Viewport {
+            window: Some(BBox::new((0, 0), (100, 100))),
+            width: 200,
+            height: 200,
+        },
+        background: Some(Color::new(255, 255, 255)),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_rasterize() {
+    let lib = library();
+    let mut styles = LayerStyles::new();
+    styles.insert(
+        1,
+        0,
+        LayerStyle::new(Color::new(255, 0, 0)).with_opacity(0.25),
+    );
+    styles.insert_layer(
+        2,
+        LayerStyle::new(Color::new(0, 0, 255))
+            .with_hatch(Hatch::Vertical)
+            .with_opacity(1.0),
+    );
+    styles.insert_layer(5, LayerStyle::new(Color::new(0, 0, 0)));
+    let image = lib.rasterize("TOP", &styles, &options()).unwrap();
+    assert_eq!((image.width, image.height), (200, 200));
+    assert_eq!(image.pixels.len(), 200 * 200 * 4);
+    // the child square covers pixels 40..60 x 180..200
+    assert_eq!(image.pixel(50, 190), [255, 191, 191, 255]);
+    // the outline is centred on the edge, half in pixel 39, half in 40
+    assert_eq!(image.pixel(40, 190), [255, 96, 96, 255]);
+    assert_eq!(image.pixel(39, 190), [255, 128, 128, 255]);
+    assert_eq!(image.pixel(30, 190), [255, 255, 255, 255]);
+    // the path's band is 90..110, hatched every 8th column
+    assert_eq!(image.pixel(100, 100), [0, 0, 255, 255]);
+    assert_eq!(image.pixel(101, 100), [255, 255, 255, 255]);
+    assert_eq!(image.pixel(101, 150), [255, 255, 255, 255]);
+    // layer 5 is one shade where its squares overlap
+    assert_eq!(image.pixel(150, 170), image.pixel(130, 170));
+    assert_eq!(image.pixel(150, 170), [128, 128, 128, 255]);
+    let mut lib = common::library(&[("TOP", vec![square(1, (0, 0), 10)])]);
+    let mut styles = LayerStyles::new();
+    styles.insert_layer(
+        1,
+        LayerStyle {
+            stroke: None,
+            ..LayerStyle::new(Color::new(0, 0, 0)).with_opacity(1.0)
+        },
+    );
+    let options = RasterOptions {
+        viewport: Viewport {
+            window: Some(BBox::new((0, 0), (20, 20))),
+            width: 40,
+            height: 40,
+        },
+        background: None,
+        ..Default::default()
+    };
+    let image = lib.rasterize("TOP", &styles, &options).unwrap();
+    assert_eq!(image.pixel(10, 30), [0, 0, 0, 255]);
+    assert_eq!(image.pixel(25, 30), [0, 0, 0, 0]);
+    // at half a pixel per unit the right edge of a 11 unit square falls
+    // in the middle of pixel 5
+    let half = RasterOptions {
+        viewport: Viewport {
+            window: Some(BBox::new((0, 0), (20, 20))),
+            width: 10,
+            height: 10,
+        },
+        ..options
+    };
+    lib.structures[0].elements = vec![square(1, (0, 0), 11)];
+    let image = lib.rasterize("TOP", &styles, &half).unwrap();
+    assert_eq!(image.pixel(4, 7), [0, 0, 0, 255]);
+    assert_eq!(image.pixel(5, 7), [0, 0, 0, 128]);
+    assert_eq!(image.pixel(6, 7), [0, 0, 0, 0]);
+    assert!(lib
+        .rasterize("NONE", &styles, &RasterOptions::default())
+        .is_err());
+}
+
+#[test]
+fn test_rasterize_frames() {
+    let lib = library();
+    let options = RasterOptions {
+        max_depth: Some(0),
+        ..options()
+    };
+    let mut styles = LayerStyles::new();
+    styles.hide(2, None);
+    styles.hide(5, None);
+    let image = lib.rasterize("TOP", &styles, &options).unwrap();
+    // only the grey box of CHILD, 40..60 x 180..200
+    assert_eq!(image.pixel(50, 190), [255, 255, 255, 255]);
+    assert_eq!(image.pixel(40, 190), [192, 192, 192, 255]);
+    assert_eq!(image.pixel(39, 190), [192, 192, 192, 255]);
+    assert_eq!(image.pixel(50, 180), [192, 192, 192, 255]);
+    assert_eq!(image.pixel(100, 100), [255, 255, 255, 255]);
+}
+
+// the RGBA rows stored in a PNG with unfiltered scanlines
+fn decode(png: &[u8]) -> (u32, u32, Vec<u8>) {
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    let (mut pos, mut size, mut idat) = (8, (0, 0), vec![]);
+    let mut kinds = vec![];
+    while pos < png.len() {
+        let length = u32::from_be_bytes(png[pos..pos + 4].try_into().unwrap()) as usize;
+        let kind = &png[pos + 4..pos + 8];
+        let data = &png[pos + 8..pos + 8 + length];
+        kinds.push(String::from_utf8_lossy(kind).to_string());
+        match kind {
+            b"IHDR" => {
+                size = (
+                    u32::from_be_bytes(data[0..4].try_into().unwrap()),
+                    u32::from_be_bytes(data[4..8].try_into().unwrap()),
+                );
+                assert_eq!(&data[8..], &[8, 6, 0, 0, 0]);
+            }
+            b"IDAT" => idat.extend(data),
+            _ => {}
+        }
+        pos += 12 + length;
+    }
+    assert_eq!(kinds, ["IHDR", "IDAT", "IEND"]);
+    let raw = miniz_oxide::inflate::decompress_to_vec_zlib(&idat).unwrap();
+    let row = size.0 as usize * 4 + 1;
+    assert_eq!(raw.len(), row * size.1 as usize);
+    let pixels = raw.chunks(row).flat_map(|r| r[1..].to_vec()).collect();
+    (size.0, size.1, pixels)
+}
+
+#[test]
+fn test_png() {
+    let mut image = Image::new(3, 2);
+    image.fill(Color::new(1, 2, 3));
+    let png = image.to_png();
+    assert_eq!(decode(&png), (3, 2, image.pixels.clone()));
+    // IEND's CRC is fixed
+    assert_eq!(&png[png.len() - 4..], &[0xAE, 0x42, 0x60, 0x82]);
+    assert_eq!(decode(&Image::new(0, 0).to_png()), (0, 0, vec![]));
+}
+
+#[test]
+fn test_png_testcases() {
+    let dir = format!("{}/target", std::env::var("CARGO_MANIFEST_DIR").unwrap());
+    let options = RasterOptions {
+        viewport: Viewport {
+            width: 128,
+            height: 96,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    for entry in std::fs::read_dir("testcases").unwrap() {
+        let path = entry.unwrap().path();
+        let lib = parse_gds2(&path).unwrap();
+        let graph = lib.hierarchy();
+        for top in graph.tops() {
+            let image = lib.rasterize(top, &LayerStyles::new(), &options).unwrap();
+            assert!(
+                image.pixels.chunks(4).any(|p| p[3] != 0),
+                "{} {}",
+                path.display(),
+                top
+            );
+        }
+    }
+    let lib = parse_gds2("testcases/sim_c1.gds").unwrap();
+    let top = lib.hierarchy().tops()[0].to_string();
+    let image = lib.rasterize(&top, &LayerStyles::new(), &options).unwrap();
+    let out = format!("{}/raster_sim_c1.png", dir);
+    image.save_png(&out).unwrap();
+    assert_eq!(
+        decode(&std::fs::read(out).unwrap()),
+        (128, 96, image.pixels)
+    );
+}
+
+#[test]
+fn test_rasterize_range_ends() {
+    // the default window's margin cannot go past the coordinate range
+    let lib = common::library(&[("TOP", vec![square(1, (i32::MIN, i32::MIN), 1 << 30)])]);
+    let options = RasterOptions {
+        viewport: Viewport {
+            width: 10,
+            height: 10,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let image = lib.rasterize("TOP", &LayerStyles::new(), &options).unwrap();
+    assert_ne!(image.pixel(5, 5)[3], 0);
+}
@@ -0,0 +1,89 @@
+mod common;
+
+use common::{i16s, record, write};
+use gds2_io::{parse_gds2, parse_gds2_with, GDSIIErrorKind, ParseOptions, RawRecord};
+
+fn boundary(vendor: &[u8]) -> Vec<u8> {
+    let mut data = record(0x08, 0x00, &[]);
+    data.extend(record(0x0D, 0x02, &i16s(&[1])));
+    data.extend(vendor);
+    data.extend(record(0x0E, 0x02, &i16s(&[0])));
+    let points: Vec<u8> = [0i32, 0, 10, 0, 10, 10, 0, 0]
+        .iter()
+        .flat_map(|v| v.to_be_bytes())
+        .collect();
+    data.extend(record(0x10, 0x03, &points));
+    data.extend(vendor);
+    data.extend(record(0x11, 0x00, &[]));
+    data
+}
+
+// a library with the vendor record `0x70` in every position it may take
+fn vendor_library() -> Vec<u8> {
+    let vendor = |n: u8| record(0x70, 0x02, &[0x00, n]);
+    let mut data = record(0x00, 0x02, &i16s(&[600]));
+    data.extend(vendor(1));
+    data.extend(record(0x01, 0x02, &i16s(&[0; 12])));
+    data.extend(record(0x02, 0x06, b"LIB\0"));
+    data.extend(record(
+        0x03,
+        0x05,
+        &[
+            0x3E, 0x41, 0x89, 0x37, 0x4B, 0xC6, 0xA7, 0xF0, 0x39, 0x44, 0xB8, 0x2F, 0xA0, 0x9B,
+            0x5A, 0x54,
+        ],
+    ));
+    data.extend(vendor(2));
+    for name in [b"A\0", b"B\0"] {
+        data.extend(record(0x05, 0x02, &i16s(&[0; 12])));
+        data.extend(record(0x06, 0x06, name));
+        data.extend(vendor(3));
+        data.extend(boundary(&vendor(4)));
+        data.extend(boundary(&[]));
+        data.extend(vendor(5));
+        data.extend(record(0x07, 0x00, &[]));
+    }
+    data.extend(record(0x7F, 0x06, b"tail"));
+    data.extend(record(0x04, 0x00, &[]));
+    data
+}
+
+#[test]
+fn test_unknown_rejected_by_default() {
+    let path = write("raw_rejected", &vendor_library());
+    match parse_gds2(path) {
+        Err(GDSIIErrorKind::UnknownRecord {
+            offset: 6,
+            rtype: 0x70,
+            dtype: 0x02,
+            structure: None,
+        }) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn test_unknown_kept() {
+    let data = vendor_library();
+    let options = ParseOptions { keep_unknown: true };
+    let lib = parse_gds2_with(write("raw_kept", &data), &options).unwrap();
+    let vendor = |n: u8| RawRecord {
+        rtype: 0x70,
+        dtype: 0x02,
+        bytes: vec![0, n],
+    };
+    let tail = RawRecord {
+        rtype: 0x7F,
+        dtype: 0x06,
+        bytes: b"tail".to_vec(),
+    };
+    assert_eq!(lib.raw, [(1, vendor(1)), (4, vendor(2)), (6, tail)]);
+    let a = lib.structure("A").unwrap();
+    assert_eq!(a.raw, [(0, vendor(3)), (2, vendor(5))]);
+    assert_eq!(a.elements[0].raw(), [(1, vendor(4)), (3, vendor(4))]);
+    assert!(a.elements[1].raw().is_empty());
+
+    let saved = write("raw_kept_saved", &[]);
+    lib.save_gds2(&saved).unwrap();
+    assert_eq!(std::fs::read(saved).unwrap(), data);
+}
@@ -0,0 +1,68 @@
+use gds2_io::{FileHeader, GDSIIErrorKind, GDSIIVariant, RecordReader, TuctosinHeader};
+use std::io::Read;
+
+// hands out at most three bytes per call, like a pipe would
+struct Trickle<R>(R);
+
+impl<R: Read> Read for Trickle<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = buf.len().min(3);
+        self.0.read(&mut buf[..n])
+    }
+}
+
+fn testcase(name: &str) -> Vec<u8> {
+    std::fs::read(format!(
+        "{}/testcases/{}.gds",
+        std::env::var("CARGO_MANIFEST_DIR").unwrap(),
+        name
+    ))
+    .unwrap()
+}
+
+#[test]
+fn test_records() {
+    let data = testcase("sim_c1");
+    let mut reader = RecordReader::new(Trickle(data.as_slice()));
+    let mut boxes = 0;
+    let mut last = None;
+    let mut expected_offset = 0;
+    for record in reader.by_ref() {
+        let (offset, variant) = record.unwrap();
+        assert_eq!(offset, expected_offset);
+        if offset == 0 {
+            assert!(matches!(
+                variant,
+                GDSIIVariant::FileHeader(FileHeader::Header(_))
+            ));
+        }
+        if let GDSIIVariant::TuctosinHeader(TuctosinHeader::Box) = variant {
+            boxes += 1;
+        }
+        let size = u16::from_be_bytes([data[offset], data[offset + 1]]) as usize;
+        expected_offset = offset + size;
+        last = Some(variant);
+    }
+    assert_eq!(boxes, 2034);
+    assert!(matches!(last, Some(GDSIIVariant::FileEnd)));
+    assert_eq!(reader.offset(), expected_offset);
+
+    let mut tail = vec![];
+    reader.into_inner().read_to_end(&mut tail).unwrap();
+    assert_eq!(tail.len(), data.len() - expected_offset);
+    assert!(tail.iter().all(|b| *b == 0));
+}
+
+#[test]
+fn test_truncated() {
+    let data = testcase("sim_c2");
+    let mut reader = RecordReader::new(&data[..1001]);
+    let e = reader.find_map(|r| r.err()).unwrap();
+    match e {
+        GDSIIErrorKind::UnexpectedEof { structure, .. } => {
+            assert_eq!(structure.as_deref(), Some("debug"))
+        }
+        e => panic!("unexpected error {:?}", e),
+    }
+    assert!(reader.next().is_none());
+}
@@ -0,0 +1,110 @@
+use gds2_io::{parse_gds2, Real8};
+use proptest::prelude::*;
+
+// values and their Stream Format encoding
+const KNOWN: &[(f64, u64)] = &[
+    (0.0, 0x0000_0000_0000_0000),
+    (1.0, 0x4110_0000_0000_0000),
+    (-1.0, 0xC110_0000_0000_0000),
+    (0.5, 0x4080_0000_0000_0000),
+    (90.0, 0x425A_0000_0000_0000),
+    (180.0, 0x42B4_0000_0000_0000),
+    (270.0, 0x4310_E000_0000_0000),
+    (-90.0, 0xC25A_0000_0000_0000),
+    (1e-3, 0x3E41_8937_4BC6_A7F0),
+    (1e-9, 0x3944_B82F_A09B_5A54),
+    (0.0625, 0x4010_0000_0000_0000),
+];
+
+#[test]
+fn test_known_patterns() {
+    for (value, bits) in KNOWN {
+        assert_eq!(Real8::from_f64(*value).to_bits(), *bits, "encode {}", value);
+        assert_eq!(
+            Real8::from_bits(*bits).to_f64(),
+            *value,
+            "decode {:#x}",
+            bits
+        );
+    }
+}
+
+#[test]
+fn test_unnormalized_decode() {
+    // leading zero hex digits in the mantissa are legal on input
+    assert_eq!(Real8::from_bits(0x4201_0000_0000_0000).to_f64(), 1.0);
+    assert_eq!(Real8::from_bits(0x4000_0000_0000_0000).to_f64(), 0.0);
+    // Cadence writes the correctly rounded decimal, which differs from the
+    // f64 encoding in the last mantissa bits
+    assert_eq!(Real8::from_bits(0x3E41_8937_4BC6_A7EF).to_f64(), 1e-3);
+}
+
+#[test]
+fn test_limits() {
+    assert_eq!(Real8::from_f64(f64::NAN), Real8::ZERO);
+    assert_eq!(Real8::from_f64(1e300).to_bits(), 0x7FFF_FFFF_FFFF_FFFF);
+    assert_eq!(
+        Real8::from_f64(-f64::INFINITY).to_bits(),
+        0xFFFF_FFFF_FFFF_FFFF
+    );
+    assert_eq!(Real8::from_f64(1e-300), Real8::ZERO);
+    // smallest exponent, the mantissa is shifted and rounded
+    let tiny = 16f64.powi(-66);
+    assert_eq!(Real8::from_f64(tiny).to_bits(), 0x0001_0000_0000_0000);
+    assert_eq!(Real8::from_bits(0x0001_0000_0000_0000).to_f64(), tiny);
+    // ties round to the even mantissa
+    let ulp = 16f64.powi(-64) * 2f64.powi(-56);
+    let bits = |v: f64| Real8::from_f64(v).to_bits();
+    assert_eq!(bits(tiny + 0.5 * ulp), 0x0001_0000_0000_0000);
+    assert_eq!(bits(tiny + 1.5 * ulp), 0x0001_0000_0000_0002);
+    assert_eq!(bits(tiny + 0.75 * ulp), 0x0001_0000_0000_0001);
+}
+
+#[test]
+fn test_units_from_file() {
+    let gds2_data = parse_gds2(format!(
+        "{}/testcases/sim_c1.gds",
+        std::env::var("CARGO_MANIFEST_DIR").unwrap(),
+    ))
+    .unwrap();
+    assert_eq!(gds2_data.units(), Some((1e-3, 1e-9)));
+}
+
+proptest! {
+    #[test]
+    fn prop_f64_round_trip(mantissa in 1u64..(1 << 53), exp in -200i32..200, negative: bool) {
+        // every f64 inside the exponent range is representable exactly
+        let value = mantissa as f64 * 2f64.powi(exp);
+        let value = if negative { -value } else { value };
+        prop_assert_eq!(Real8::from_f64(value).to_f64(), value);
+    }
+
+    #[test]
+    fn prop_encoding_is_normalized(mantissa in 1u64..(1 << 53), exp in -232i32..179, negative: bool) {
+        // 2^-232 to 2^232, about 1e-70 to 1e70
+        let value = mantissa as f64 * 2f64.powi(exp);
+        let value = if negative { -value } else { value };
+        let bits = Real8::from_f64(value).to_bits();
+        // the leading hex digit of the mantissa is never zero
+        prop_assert_ne!(bits & 0x00F0_0000_0000_0000, 0);
+        prop_assert_eq!(bits >> 63 == 1, value < 0.0);
+    }
+
+    #[test]
+    fn prop_decode_is_nearest(bits in any::<u64>()) {
+        // decoding rounds once: the result is within half an ulp of the
+        // exact mantissa value
+        let real = Real8::from_bits(bits);
+        let mantissa = bits & 0x00FF_FFFF_FFFF_FFFF;
+        let decoded = real.to_f64().abs();
+        let exp = ((bits >> 56) & 0x7F) as i32 - 64;
+        let scaled = decoded / 2f64.powi(4 * exp - 56);
+        prop_assert_eq!(scaled, mantissa as f64);
+    }
+
+    #[test]
+    fn prop_bytes_round_trip(bits in any::<u64>()) {
+        let real = Real8::from_bits(bits);
+        prop_assert_eq!(Real8::from_be_bytes(real.to_be_bytes()), real);
+    }
+}
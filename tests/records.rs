@@ -0,0 +1,123 @@
+mod common;
+
+use common::{element, i16s, i32s, record, write};
+use gds2_io::parse_gds2;
+
+fn name44(names: &[&str]) -> Vec<u8> {
+    let mut data = vec![];
+    for n in names {
+        data.extend(n.as_bytes());
+        data.extend(std::iter::repeat_n(0u8, 44 - n.len()));
+    }
+    data
+}
+
+// every library record in Stream Format order
+fn full_header() -> Vec<u8> {
+    let mut data = record(0x00, 0x02, &i16s(&[600]));
+    data.extend(record(0x01, 0x02, &i16s(&[1; 12])));
+    data.extend(record(0x39, 0x02, &i16s(&[2])));
+    data.extend(record(0x3A, 0x06, b"spacing.srf\0"));
+    data.extend(record(0x3B, 0x02, &i16s(&[1, 2, 3, 4, 5, 6])));
+    data.extend(record(0x02, 0x06, b"LIB\0"));
+    data.extend(record(0x1F, 0x06, &name44(&["lib_a", "lib_b"])));
+    data.extend(record(0x20, 0x06, &name44(&["f0", "", "f2", ""])));
+    data.extend(record(0x23, 0x06, b"attrs.txt\0"));
+    data.extend(record(0x24, 0x06, b"styp"));
+    data.extend(record(0x22, 0x02, &i16s(&[3])));
+    data.extend(record(0x36, 0x02, &i16s(&[1])));
+    data.extend(record(0x37, 0x06, b"1 2-5\0"));
+    data.extend(record(0x38, 0x00, &[]));
+    data.extend(record(
+        0x03,
+        0x05,
+        &[
+            0x3E, 0x41, 0x89, 0x37, 0x4B, 0xC6, 0xA7, 0xF0, 0x39, 0x44, 0xB8, 0x2F, 0xA0, 0x9B,
+            0x5A, 0x54,
+        ],
+    ));
+    data.extend(record(0x32, 0x02, &i16s(&[1])));
+    data.extend(record(0x33, 0x02, &i16s(&[1, 2, 3, 4, 5, 6])));
+    data.extend(record(0x35, 0x03, &i32s(&[7, 8])));
+    data
+}
+
+fn full_structure() -> Vec<u8> {
+    let mut data = record(0x05, 0x02, &i16s(&[2; 12]));
+    data.extend(record(0x06, 0x06, b"TOP\0"));
+    data.extend(record(0x34, 0x01, &i16s(&[4])));
+    data.extend(record(0x25, 0x02, &i16s(&[5])));
+    data.extend(element(
+        0x08,
+        &[
+            record(0x26, 0x01, &i16s(&[1])),
+            record(0x2F, 0x03, &i32s(&[9])),
+            record(0x1D, 0x03, &i32s(&[11])),
+            record(0x1E, 0x06, b"user"),
+            record(0x27, 0x03, &i32s(&[12])),
+            record(0x28, 0x02, &i16s(&[13])),
+            record(0x29, 0x03, &i32s(&[14, 15])),
+            record(0x3C, 0x00, &[]),
+            record(0x45, 0x00, &[]),
+            record(0x0D, 0x02, &i16s(&[1])),
+            record(0x0E, 0x02, &i16s(&[0])),
+            record(0x10, 0x03, &i32s(&[0, 0, 10, 0, 10, 10, 0, 0])),
+        ],
+    ));
+    data.extend(element(
+        0x14,
+        &[
+            record(0x18, 0x02, &i16s(&[2])),
+            record(0x0D, 0x02, &i16s(&[3])),
+            record(0x16, 0x02, &i16s(&[4])),
+            record(0x10, 0x03, &i32s(&[5, 6])),
+            record(0x19, 0x06, b"pin\0"),
+        ],
+    ));
+    data.extend(record(0x07, 0x00, &[]));
+    data
+}
+
+// write `data`, check that it saves back byte for byte, return its path
+fn roundtrip(name: &str, data: &[u8]) -> String {
+    let input = write(&format!("records_{}", name), data);
+    let output = write(&format!("records_{}_saved", name), &[]);
+    let lib = parse_gds2(&input).unwrap();
+    lib.clone().save_gds2(&output).unwrap();
+    assert_eq!(std::fs::read(&output).unwrap(), data);
+    input
+}
+
+#[test]
+fn test_library_records() {
+    let mut data = full_header();
+    data.extend(record(0x04, 0x00, &[]));
+    let lib = parse_gds2(roundtrip("header", &data)).unwrap();
+    let header = |tag: &str| format!("{:?}", lib.header_record(tag).unwrap());
+    assert_eq!(header("libdirsize"), "LibDirSize(2)");
+    assert_eq!(header("srfname"), "SrfName(\"spacing.srf\")");
+    assert_eq!(header("libsecur"), "LibSecur([(1, 2, 3), (4, 5, 6)])");
+    assert_eq!(header("reflib"), "RefLibs([\"lib_a\", \"lib_b\"])");
+    assert_eq!(header("font"), "Fonts([\"f0\", \"\", \"f2\", \"\"])");
+    assert_eq!(header("styptable"), "StypTable(\"styp\")");
+    assert_eq!(header("endmask"), "EndMask");
+    assert_eq!(header("tapecode"), "TapeCode([1, 2, 3, 4, 5, 6])");
+    assert_eq!(header("reserved"), "Reserved([7, 8])");
+}
+
+#[test]
+fn test_structure_records() {
+    let mut data = full_header();
+    data.extend(full_structure());
+    data.extend(record(0x04, 0x00, &[]));
+    let lib = parse_gds2(roundtrip("structure", &data)).unwrap();
+    let top = lib.structure("TOP").unwrap();
+    assert_eq!((top.strclass, top.strtype), (Some(4), Some(5)));
+    assert_eq!(top.elements.len(), 2);
+    assert_eq!(
+        format!("{:?}", top.elements[0].extra()),
+        "[UInteger(11), UString(\"user\"), ElKey(12), LinkType(13), LinkKeys([14, 15]), Border, Contact]"
+    );
+    assert_eq!(format!("{:?}", top.elements[1].header()), "TextNode");
+    assert_eq!(format!("{:?}", top.elements[1].extra()), "[Spacing(2)]");
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use common::{element, i16s, i32s, record, stream, write};
+use gds2_io::{parse_bytes, parse_gds2, verify_roundtrip, Element};
+
+#[test]
+fn test_testcases() {
+    let dir = format!("{}/testcases", std::env::var("CARGO_MANIFEST_DIR").unwrap());
+    let mut checked = 0;
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_some_and(|e| e == "gds") {
+            if let Err(e) = verify_roundtrip(&path) {
+                panic!("{}: {}", path.display(), e);
+            }
+            checked += 1;
+        }
+    }
+    assert_eq!(checked, 7);
+}
+
+#[test]
+fn test_header_order_and_tail() {
+    // UNITS ahead of LIBNAME, an empty structure and a short odd tail
+    let mut data = record(0x00, 0x02, &[0x02, 0x58]);
+    data.extend(record(0x01, 0x02, &[0; 24]));
+    data.extend(record(
+        0x03,
+        0x05,
+        &[0x41, 0x10, 0, 0, 0, 0, 0, 0, 0x41, 0x10, 0, 0, 0, 0, 0, 0],
+    ));
+    data.extend(record(0x02, 0x06, b"L\0"));
+    data.extend(record(0x05, 0x02, &[0; 24]));
+    data.extend(record(0x06, 0x06, b"EMPTY\0"));
+    data.extend(record(0x07, 0x00, &[]));
+    data.extend(record(0x04, 0x00, &[]));
+    data.extend([0, 0, 0xAB]);
+    let path = write("roundtrip_order", &data);
+    verify_roundtrip(&path).unwrap();
+    let lib = parse_gds2(&path).unwrap();
+    assert_eq!(lib.header[2].get_tag(), "unit");
+    assert_eq!(lib.tail, [0, 0, 0xAB]);
+}
+
+#[test]
+fn test_element_record_order() {
+    // a BOUNDARY with DATATYPE ahead of LAYER, an ELKEY after them and its
+    // property ahead of XY, followed by one in Stream Format order
+    let xy = record(0x10, 0x03, &i32s(&[0, 0, 10, 0, 10, 10, 0, 0]));
+    let data = stream(&[
+        element(
+            0x08,
+            &[
+                record(0x0E, 0x02, &i16s(&[2])),
+                record(0x0D, 0x02, &i16s(&[1])),
+                record(0x27, 0x03, &i32s(&[7])),
+                record(0x2B, 0x02, &i16s(&[1])),
+                record(0x2C, 0x06, b"pin\0"),
+                xy.clone(),
+            ],
+        ),
+        element(
+            0x08,
+            &[
+                record(0x0D, 0x02, &i16s(&[1])),
+                record(0x0E, 0x02, &i16s(&[2])),
+                xy,
+            ],
+        ),
+    ]);
+
+    let lib = parse_bytes(&data).unwrap();
+    let [Element::Boundary(read), Element::Boundary(plain)] = &lib.structures[0].elements[..]
+    else {
+        panic!("unexpected elements {:?}", lib.structures[0].elements);
+    };
+    assert_eq!((read.layer, read.datatype), (1, 2));
+    assert_eq!(read.props[0].value, "pin");
+    assert_eq!(
+        read.order,
+        [
+            [0x0E, 0x02],
+            [0x0D, 0x02],
+            [0x27, 0x03],
+            [0x2B, 0x02],
+            [0x2C, 0x06],
+            [0x10, 0x03]
+        ]
+    );
+    assert!(plain.order.is_empty());
+    assert_eq!(lib.to_bytes().unwrap(), data);
+}
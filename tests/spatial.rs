@@ -0,0 +1,123 @@
+use gds2_io::{
+    parse_gds2, polygon_contains, BBox, Boundary, Element, Library, RTree, SRef, SpatialOptions,
+    Structure,
+};
+use proptest::prelude::*;
+
+fn triangle(x: i32) -> Element {
+    Element::Boundary(Boundary {
+        points: vec![(x, 0), (x + 10, 0), (x, 10), (x, 0)],
+        ..Default::default()
+    })
+}
+
+fn library() -> Library {
+    let mut top = Structure::new("TOP");
+    top.elements = (0..100).map(|i| triangle(i * 20)).collect();
+    top.elements.push(Element::SRef(SRef {
+        sname: "CELL".to_string(),
+        origin: (0, 1000),
+        ..Default::default()
+    }));
+    let mut cell = Structure::new("CELL");
+    cell.elements.push(triangle(0));
+    Library {
+        structures: vec![top, cell],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_queries() {
+    let lib = library();
+    let index = lib
+        .spatial_index("TOP", &SpatialOptions::default())
+        .unwrap();
+    assert_eq!(index.len(), 100);
+    assert_eq!(index.window(&BBox::new((15, 5), (45, 5))), [1, 2]);
+    assert_eq!(index.at_point((48, 8)), [2]);
+    let top = lib.structure("TOP").unwrap();
+    // inside the box of triangle 2 but beyond its hypotenuse
+    assert!(index.containing(top, (48, 8)).is_empty());
+    assert_eq!(index.containing(top, (41, 1)), [2]);
+    assert_eq!(index.nearest((72, 3)), Some((3, 2.0)));
+    assert_eq!(index.nearest((5, 500)).map(|n| n.0), Some(0));
+
+    let options = SpatialOptions {
+        references: true,
+        ..Default::default()
+    };
+    let index = lib.spatial_index("TOP", &options).unwrap();
+    assert_eq!(index.at_point((3, 1003)), [100]);
+    assert_eq!(index.nearest((5, 900)).map(|n| n.0), Some(100));
+}
+
+#[test]
+fn test_refresh() {
+    let mut lib = library();
+    let mut index = lib
+        .spatial_index("TOP", &SpatialOptions::default())
+        .unwrap();
+    let top = lib.structure_mut("TOP").unwrap();
+    top.elements[5] = triangle(5000);
+    top.elements.truncate(90);
+    assert_eq!(index.refresh(&lib).unwrap(), 11);
+    assert_eq!(index.len(), 90);
+    assert_eq!(index.at_point((5001, 1)), [5]);
+    assert!(index.at_point((101, 1)).is_empty());
+    assert!(index.at_point((1901, 1)).is_empty());
+    assert_eq!(index.refresh(&lib).unwrap(), 0);
+}
+
+#[test]
+fn test_testcase_window() {
+    let lib = parse_gds2("testcases/sim_c4.gds").unwrap();
+    let index = lib
+        .spatial_index("debug", &SpatialOptions::default())
+        .unwrap();
+    let elements = &lib.structures[0].elements;
+    let window = BBox::new((-2000, -1000), (1500, 3000));
+    let expected: Vec<usize> = (0..elements.len())
+        .filter(|i| index.element_bbox(*i).unwrap().intersects(&window))
+        .collect();
+    assert!(!expected.is_empty());
+    assert_eq!(index.window(&window), expected);
+}
+
+fn bbox() -> impl Strategy<Value = BBox> {
+    (-1000..1000i32, -1000..1000i32, 0..100i32, 0..100i32)
+        .prop_map(|(x, y, w, h)| BBox::new((x, y), (x + w, y + h)))
+}
+
+proptest! {
+    #[test]
+    fn prop_rtree_edits(boxes in prop::collection::vec(bbox(), 0..300), window in bbox()) {
+        let mut tree = RTree::new();
+        for (i, b) in boxes.iter().enumerate() {
+            tree.insert(*b, i);
+        }
+        for (i, b) in boxes.iter().enumerate().step_by(3) {
+            prop_assert_eq!(tree.remove(b, &i), Some(i));
+        }
+        let kept = |i: &usize| !i.is_multiple_of(3);
+        prop_assert_eq!(tree.len(), boxes.iter().enumerate().filter(|(i, _)| kept(i)).count());
+        let mut found: Vec<usize> = tree.search(&window).into_iter().copied().collect();
+        found.sort_unstable();
+        let expected: Vec<usize> = (0..boxes.len())
+            .filter(|i| kept(i) && boxes[*i].intersects(&window))
+            .collect();
+        prop_assert_eq!(found, expected);
+
+        let packed = RTree::bulk_load(boxes.iter().copied().zip(0..).collect());
+        let mut found: Vec<usize> = packed.search(&window).into_iter().copied().collect();
+        found.sort_unstable();
+        let expected: Vec<usize> = (0..boxes.len()).filter(|i| boxes[*i].intersects(&window)).collect();
+        prop_assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn prop_polygon_contains_box(b in bbox(), p in (-1100..1100i32, -1100..1100i32)) {
+        let corners = b.corners();
+        prop_assert_eq!(polygon_contains(&corners, p), b.contains(p));
+    }
+}
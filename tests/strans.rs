@@ -0,0 +1,74 @@
+mod common;
+
+use common::{element, record, stream};
+use gds2_io::{parse_bytes, Element, GDSIIErrorKind, Real8, Strans};
+use proptest::prelude::*;
+
+// library with a single SREF made of `body` between SNAME and XY
+fn sref_library(body: &[Vec<u8>]) -> Vec<u8> {
+    let mut records = vec![record(0x12, 0x06, b"CELL")];
+    records.extend(body.iter().cloned());
+    records.push(record(0x10, 0x03, &[0, 0, 0, 5, 0, 0, 0, 7]));
+    stream(&[element(0x0A, &records)])
+}
+
+#[test]
+fn test_bits() {
+    let s = Strans::from_bits(0x8006u16 as i16);
+    assert!(s.reflect_x && s.abs_mag && s.abs_angle);
+    assert_eq!(s.reserved, 0);
+    assert_eq!((s.magnification(), s.angle_degrees()), (1.0, 0.0));
+
+    let s = Strans::from_bits(0x0002);
+    assert!(!s.reflect_x && !s.abs_mag && s.abs_angle);
+
+    let s = Strans {
+        reflect_x: true,
+        ..Default::default()
+    };
+    assert_eq!(s.to_bits() as u16, 0x8000);
+}
+
+#[test]
+fn test_sref_strans() {
+    let data = sref_library(&[
+        record(0x1A, 0x01, &0x8004u16.to_be_bytes()),
+        record(0x1B, 0x05, &Real8::from_f64(2.0).to_be_bytes()),
+        record(0x1C, 0x05, &Real8::from_f64(90.0).to_be_bytes()),
+    ]);
+    let lib = parse_bytes(&data).unwrap();
+    let strans = match &lib.structures[0].elements[0] {
+        Element::SRef(e) => e.strans.unwrap(),
+        e => panic!("unexpected element {:?}", e),
+    };
+    assert!(strans.reflect_x && strans.abs_mag && !strans.abs_angle);
+    assert_eq!(strans.magnification(), 2.0);
+    assert_eq!(strans.angle_degrees(), 90.0);
+    assert_eq!(lib.to_bytes().unwrap(), data);
+
+    // ANGLE alone
+    let data = sref_library(&[
+        record(0x1A, 0x01, &[0, 0]),
+        record(0x1C, 0x05, &Real8::from_f64(45.0).to_be_bytes()),
+    ]);
+    let lib = parse_bytes(&data).unwrap();
+    let strans = lib.structures[0].elements[0].strans().unwrap();
+    assert_eq!((strans.mag, strans.angle_degrees()), (None, 45.0));
+    assert_eq!(lib.to_bytes().unwrap(), data);
+}
+
+#[test]
+fn test_mag_without_strans() {
+    let data = sref_library(&[record(0x1B, 0x05, &Real8::from_f64(2.0).to_be_bytes())]);
+    match parse_bytes(&data) {
+        Err(GDSIIErrorKind::UnexpectedRecord { record, .. }) => assert_eq!(record, "MAG"),
+        other => panic!("unexpected result {:?}", other.map(|_| ())),
+    }
+}
+
+proptest! {
+    #[test]
+    fn prop_bits_roundtrip(bits: i16) {
+        prop_assert_eq!(Strans::from_bits(bits).to_bits(), bits);
+    }
+}
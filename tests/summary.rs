@@ -0,0 +1,132 @@
+mod common;
+
+use common::{library, square, sref};
+use gds2_io::{parse_gds2, ARef, BBox, Boundary, Element, Library, Path, SRef, Structure};
+
+#[test]
+fn test_summary() {
+    let mut lib = parse_gds2("testcases/sim_s1.gds").unwrap();
+    let debug = lib.structures[0].elements.clone();
+    let boundaries = debug.len();
+    let mut cell = Structure::new("CELL");
+    cell.elements.push(Element::Path(Path {
+        layer: 7,
+        datatype: 2,
+        width: Some(4),
+        points: vec![(0, 0), (0, 100)],
+        ..Default::default()
+    }));
+    cell.elements.push(Element::Boundary(Boundary {
+        layer: 7,
+        points: vec![(0, 0), (5, 0), (5, 5), (3, 7), (0, 5), (0, 0)],
+        ..Default::default()
+    }));
+    lib.structures[0].elements.push(Element::ARef(ARef {
+        sname: "CELL".to_string(),
+        cols: 3,
+        rows: 2,
+        origin: (0, 0),
+        col_point: (30, 0),
+        row_point: (0, 400),
+        ..Default::default()
+    }));
+    lib.structures[0].elements.push(Element::SRef(SRef {
+        sname: "CELL".to_string(),
+        ..Default::default()
+    }));
+    lib.structures.push(cell);
+
+    let summary = lib.summerize();
+    assert_eq!(summary.structures, 2);
+    assert_eq!(summary.tops, ["debug"]);
+    assert_eq!(summary.depth, Some(1));
+    assert_eq!(summary.units, Some((1e-3, 1e-9)));
+    assert_eq!(summary.elements.boundary, boundaries + 1);
+    assert_eq!(summary.elements.total(), boundaries + 4);
+    assert_eq!(summary.aref_instances, 6);
+    assert_eq!(summary.layers[&(7, 2)].vertices, 2);
+    assert_eq!(summary.layers[&(7, 0)].elements, 1);
+    let largest = summary.largest_polygon.as_ref().unwrap();
+    assert_eq!((largest.structure.as_str(), largest.index), ("CELL", 1));
+    let flat = BBox::from_points(
+        &lib.structures[0].elements[..boundaries]
+            .iter()
+            .flat_map(|e| match e {
+                Element::Boundary(b) => b.points.clone(),
+                _ => vec![],
+            })
+            .collect::<Vec<_>>(),
+    )
+    .unwrap();
+    assert_eq!(
+        summary.bbox,
+        Some(flat.union(BBox::new((-2, 0), (62, 500))))
+    );
+
+    let text = summary.to_string();
+    assert!(text.contains("structures 2, top: debug"), "{}", text);
+    assert!(text.contains("AREF instances 6"), "{}", text);
+    assert!(
+        text.lines()
+            .any(|l| l.split_whitespace().eq(["7/2", "1", "2"])),
+        "{}",
+        text
+    );
+}
+
+#[test]
+fn test_empty_summary() {
+    let summary = Library::default().summerize();
+    assert_eq!(summary.elements.total(), 0);
+    assert_eq!(summary.bbox, None);
+    assert!(summary.to_string().contains("bbox       (empty)"));
+}
+
+#[test]
+fn test_broken_summary() {
+    let square = square(1, (0, 0), 10);
+    let lib = library(&[
+        ("TOP", vec![square.clone(), sref("MISSING", (0, 0))]),
+        ("A", vec![sref("B", (0, 0)), square]),
+        ("B", vec![sref("A", (0, 0))]),
+    ]);
+
+    let summary = lib.summerize();
+    assert_eq!(summary.structures, 3);
+    assert_eq!(summary.tops, ["TOP"]);
+    assert_eq!(summary.elements.boundary, 2);
+    assert_eq!(summary.elements.sref, 3);
+    assert_eq!(summary.layers[&(1, 0)].vertices, 10);
+    assert_eq!(
+        summary.dangling,
+        [("TOP".to_string(), "MISSING".to_string())]
+    );
+    assert_eq!(summary.cycles.len(), 1);
+    let cycle = &summary.cycles[0];
+    assert_eq!(cycle.first(), cycle.last());
+    assert_eq!(cycle.len(), 3);
+    assert_eq!(summary.depth, None);
+    assert_eq!(summary.bbox, None);
+
+    let text = summary.to_string();
+    assert!(text.contains("depth      (unknown)"), "{}", text);
+    assert!(text.contains("bbox       (unknown)"), "{}", text);
+    assert!(
+        text.contains("dangling   MISSING placed by TOP"),
+        "{}",
+        text
+    );
+    assert!(
+        text.contains(&format!("cycle      {}", cycle.join(" -> "))),
+        "{}",
+        text
+    );
+
+    // a dangling reference alone still leaves the depth
+    let mut lib = lib;
+    lib.structures.truncate(1);
+    let summary = lib.summerize();
+    assert!(summary.cycles.is_empty());
+    assert_eq!(summary.depth, Some(0));
+    assert_eq!(summary.bbox, None);
+}
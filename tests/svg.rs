@@ -0,0 +1,210 @@
+use gds2_io::{
+    parse_gds2, BBox, Boundary, Color, Element, Hatch, LayerStyle, LayerStyles, Library, Path,
+    Real8, SRef, Strans, Structure, SvgOptions, Text, Viewport,
+};
+
+fn close(a: &[(f64, f64)], b: &[(f64, f64)]) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .zip(b)
+            .all(|(p, q)| (p.0 - q.0).abs() < 1e-9 && (p.1 - q.1).abs() < 1e-9)
+}
+
+fn path(pathtype: i16, points: &[(i32, i32)]) -> Path {
+    Path {
+        layer: 2,
+        pathtype: Some(pathtype),
+        width: Some(10),
+        points: points.to_vec(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_path_outline() {
+    let straight = path(0, &[(0, 0), (100, 0)]);
+    let expected = [(0.0, 5.0), (100.0, 5.0), (100.0, -5.0), (0.0, -5.0)];
+    assert!(close(&straight.outline(), &expected));
+    let extended = path(2, &[(0, 0), (100, 0)]);
+    let expected = [(-5.0, 5.0), (105.0, 5.0), (105.0, -5.0), (-5.0, -5.0)];
+    assert!(close(&extended.outline(), &expected));
+
+    let corner = path(0, &[(0, 0), (100, 0), (100, 100)]);
+    let expected = [
+        (0.0, 5.0),
+        (95.0, 5.0),
+        (95.0, 100.0),
+        (105.0, 100.0),
+        (105.0, -5.0),
+        (0.0, -5.0),
+    ];
+    assert!(
+        close(&corner.outline(), &expected),
+        "{:?}",
+        corner.outline()
+    );
+
+    // a turn back on itself is bevelled
+    let back = path(0, &[(0, 0), (100, 0), (0, 1)]);
+    assert_eq!(back.outline().len(), 8);
+    // round ends
+    let round = path(1, &[(0, 0), (100, 0)]);
+    let outline = round.outline();
+    assert_eq!(outline.len(), 34);
+    assert!(outline
+        .iter()
+        .any(|p| (p.0 - 105.0).abs() < 1e-9 && p.1.abs() < 1e-9));
+    assert!(outline
+        .iter()
+        .any(|p| (p.0 + 5.0).abs() < 1e-9 && p.1.abs() < 1e-9));
+    assert!(path(0, &[(0, 0)]).outline().is_empty());
+}
+
+fn library() -> Library {
+    let mut child = Structure::new("CHILD");
+    child.elements = vec![Element::Boundary(Boundary {
+        layer: 1,
+        points: vec![(0, 0), (10, 0), (10, 10), (0, 10), (0, 0)],
+        ..Default::default()
+    })];
+    let mut top = Structure::new("TOP");
+    top.elements = vec![
+        Element::SRef(SRef {
+            sname: "CHILD".to_string(),
+            origin: (20, 0),
+            ..Default::default()
+        }),
+        Element::Path(path(0, &[(0, 50), (100, 50)])),
+        Element::Text(Text {
+            layer: 3,
+            origin: (50, 80),
+            string: "a<b".to_string(),
+            presentation: Some(0b0101),
+            strans: Some(Strans {
+                angle: Some(Real8::from_f64(90.0)),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }),
+        Element::Boundary(Boundary {
+            layer: 4,
+            points: vec![(500, 500), (510, 500), (510, 510), (500, 500)],
+            ..Default::default()
+        }),
+    ];
+    Library {
+        structures: vec![child, top],
+        ..Default::default()
+    }
+}
+
+fn options() -> SvgOptions {
+    SvgOptions {
+        viewport: Viewport {
+            window: Some(BBox::new((0, 0), (100, 100))),
+            width: 200,
+            height: 200,
+        },
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_svg() {
+    let lib = library();
+    let mut styles = LayerStyles::new();
+    let red = Color::parse("#f00").unwrap();
+    styles.insert(1, 0, LayerStyle::new(red).with_opacity(0.25));
+    styles.insert_layer(
+        2,
+        LayerStyle::new(Color::new(0, 0, 255)).with_hatch(Hatch::Diagonal),
+    );
+    let svg = lib.to_svg("TOP", &styles, &options()).unwrap();
+    assert!(svg.starts_with(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="200" viewBox="0 0 200 200">"#
+    ));
+    assert!(svg.contains(r##"<pattern id="hatch-2-0" width="8" height="8" patternUnits="userSpaceOnUse"><path d="M0 8L8 0M-2 2L2 -2M6 10L10 6" stroke="#0000ff" stroke-width="1"/></pattern>"##));
+    assert!(svg.contains(
+        "<g id=\"layer-1-0\" fill=\"#ff0000\" fill-opacity=\"0.25\" stroke=\"#ff0000\" stroke-width=\"1\" stroke-linejoin=\"miter\">\n\
+         <polygon points=\"40,200 60,200 60,180 40,180\"/>\n</g>\n"
+    ));
+    assert!(svg.contains(r#"<g id="layer-2-0" fill="url(#hatch-2-0)""#));
+    assert!(svg.contains(r#"<polygon points="0,90 200,90 200,110 0,110"/>"#));
+    assert!(svg.contains(
+        r#"<text x="100" y="40" font-size="12" text-anchor="middle" dominant-baseline="central" transform="rotate(-90 100 40)">a&lt;b</text>"#
+    ));
+    // layer 4 is outside the window, no frames when everything is expanded
+    assert!(!svg.contains("layer-4-0") && !svg.contains("references"));
+    let order: Vec<usize> = ["layer-1-0", "layer-2-0", "layer-3-0"]
+        .iter()
+        .map(|id| svg.find(id).unwrap())
+        .collect();
+    assert!(order.windows(2).all(|w| w[0] < w[1]));
+    assert!(svg.ends_with("</svg>\n"));
+}
+
+#[test]
+fn test_svg_depth_and_hidden() {
+    let lib = library();
+    let mut styles = LayerStyles::new();
+    styles.hide(3, None);
+    let options = SvgOptions {
+        max_depth: Some(0),
+        background: Some(Color::new(255, 255, 255)),
+        texts: false,
+        ..options()
+    };
+    let svg = lib.to_svg("TOP", &styles, &options).unwrap();
+    assert!(!svg.contains("layer-1-0") && !svg.contains("layer-3-0"));
+    assert!(svg.contains(r##"<rect width="100%" height="100%" fill="#ffffff"/>"##));
+    assert!(
+        svg.contains(r#"<rect x="40" y="180" width="20" height="20"><title>CHILD</title></rect>"#)
+    );
+}
+
+#[test]
+fn test_svg_testcases() {
+    let dir = format!("{}/target", std::env::var("CARGO_MANIFEST_DIR").unwrap());
+    for entry in std::fs::read_dir("testcases").unwrap() {
+        let path = entry.unwrap().path();
+        let lib = parse_gds2(&path).unwrap();
+        let graph = lib.hierarchy();
+        for top in graph.tops() {
+            let svg = lib
+                .to_svg(top, &LayerStyles::new(), &SvgOptions::default())
+                .unwrap();
+            assert_eq!(svg.matches("<g ").count(), svg.matches("</g>").count());
+            assert!(svg.contains("<polygon"), "{} {}", path.display(), top);
+        }
+    }
+    let lib = parse_gds2("testcases/sim_c1.gds").unwrap();
+    let top = lib.hierarchy().tops()[0].to_string();
+    let out = format!("{}/svg_sim_c1.svg", dir);
+    lib.save_svg(&out, &top, &LayerStyles::new(), &SvgOptions::default())
+        .unwrap();
+    assert!(std::fs::read_to_string(out).unwrap().ends_with("</svg>\n"));
+}
+
+#[test]
+fn test_svg_range_ends() {
+    // the default window's margin cannot go past the coordinate range
+    let mut top = Structure::new("TOP");
+    top.elements = vec![Element::Boundary(Boundary {
+        layer: 1,
+        points: vec![
+            (i32::MIN, i32::MIN),
+            (i32::MIN + 10, i32::MIN),
+            (i32::MAX, i32::MAX),
+            (i32::MIN, i32::MIN),
+        ],
+        ..Default::default()
+    })];
+    let lib = Library {
+        structures: vec![top],
+        ..Default::default()
+    };
+    let svg = lib
+        .to_svg("TOP", &LayerStyles::new(), &SvgOptions::default())
+        .unwrap();
+    assert!(svg.contains("<polygon"));
+}
@@ -1,75 +1,127 @@
-use gds2_io::parse_gds2;
+use gds2_io::{parse_gds2, TuctosinHeader};
 
 #[test]
 fn test_1() {
-    let gds2_data = parse_gds2(&format!(
+    let original = format!(
         "{}/testcases/sim_c1.gds",
         std::env::var("CARGO_MANIFEST_DIR").unwrap(),
-    ))
-    .unwrap();
-    println!(
-        "header: {}",
-        gds2_data
-            .header
-            .iter()
-            .map(|(k, v)| format!("{}:{:?} \n", k, v))
-            .collect::<String>()
     );
-    println!("struct name: {:?}", gds2_data.structure_name);
-    let _ = gds2_data.save_gds2(&format!(
+    let out = format!(
         "{}/target/sim_c1_out.gds",
         std::env::var("CARGO_MANIFEST_DIR").unwrap(),
-    ));
-    println!("write done");
-    let gds2_data = parse_gds2(&format!(
-        "{}/target/sim_c1_out.gds",
-        std::env::var("CARGO_MANIFEST_DIR").unwrap(),
-    ))
-    .unwrap();
-    println!(
-        "header: {}",
-        gds2_data
-            .header
-            .iter()
-            .map(|(k, v)| format!("{}:{:?} \n", k, v))
-            .collect::<String>()
     );
-    println!("struct name: {:?}", gds2_data.structure_name);
+    let gds2_data = parse_gds2(&original).unwrap();
+    let names: Vec<String> = gds2_data.structure_names().map(String::from).collect();
+    gds2_data.save_gds2(&out).unwrap();
+    assert_eq!(
+        std::fs::read(&out).unwrap(),
+        std::fs::read(original).unwrap()
+    );
+    let gds2_data = parse_gds2(out).unwrap();
+    assert!(gds2_data
+        .structure_names()
+        .eq(names.iter().map(String::as_str)));
 }
 
 #[test]
 fn test_2() {
-    let gds2_data = parse_gds2(&format!(
+    let original = format!(
         "{}/testcases/sim_c6.gds",
         std::env::var("CARGO_MANIFEST_DIR").unwrap(),
-    ))
-    .unwrap();
-    println!(
-        "header: {}",
-        gds2_data
-            .header
-            .iter()
-            .map(|(k, v)| format!("{}:{:?} \n", k, v))
-            .collect::<String>()
     );
-    println!("struct name: {:?}", gds2_data.structure_name);
-    let _ = gds2_data.save_gds2(&format!(
+    let out = format!(
         "{}/target/sim_c6_out.gds",
         std::env::var("CARGO_MANIFEST_DIR").unwrap(),
-    ));
-    println!("write done");
-    let gds2_data = parse_gds2(&format!(
-        "{}/target/sim_c6_out.gds",
+    );
+    let gds2_data = parse_gds2(&original).unwrap();
+    let names: Vec<String> = gds2_data.structure_names().map(String::from).collect();
+    gds2_data.save_gds2(&out).unwrap();
+    assert_eq!(
+        std::fs::read(&out).unwrap(),
+        std::fs::read(original).unwrap()
+    );
+    let gds2_data = parse_gds2(out).unwrap();
+    assert!(gds2_data
+        .structure_names()
+        .eq(names.iter().map(String::as_str)));
+}
+
+#[test]
+fn test_multi_structure() {
+    let mut gds2_data = parse_gds2(format!(
+        "{}/testcases/sim_c2.gds",
+        std::env::var("CARGO_MANIFEST_DIR").unwrap(),
+    ))
+    .unwrap();
+    assert_eq!(gds2_data.structures.len(), 1);
+    let mut copy = gds2_data.structures[0].clone();
+    copy.name = "copy".to_string();
+    copy.elements.truncate(3);
+    gds2_data.structures.push(copy);
+
+    gds2_data
+        .save_gds2(format!(
+            "{}/target/sim_c2_multi.gds",
+            std::env::var("CARGO_MANIFEST_DIR").unwrap(),
+        ))
+        .unwrap();
+    let gds2_data = parse_gds2(format!(
+        "{}/target/sim_c2_multi.gds",
         std::env::var("CARGO_MANIFEST_DIR").unwrap(),
     ))
     .unwrap();
-    println!(
-        "header: {}",
-        gds2_data
-            .header
-            .iter()
-            .map(|(k, v)| format!("{}:{:?} \n", k, v))
-            .collect::<String>()
+    assert_eq!(
+        gds2_data.structure_names().collect::<Vec<_>>(),
+        vec!["debug", "copy"]
     );
-    println!("struct name: {:?}", gds2_data.structure_name);
+    let first = gds2_data.structure("debug").unwrap();
+    let copy = gds2_data.structure("copy").unwrap();
+    assert_eq!(first.elements.len(), 3917);
+    assert_eq!(copy.elements.len(), 3);
+    assert_eq!(first.created, copy.created);
+    assert!(gds2_data.structure("missing").is_none());
+}
+
+#[test]
+fn test_elements() {
+    let boxes = parse_gds2(format!(
+        "{}/testcases/sim_c1.gds",
+        std::env::var("CARGO_MANIFEST_DIR").unwrap(),
+    ))
+    .unwrap();
+    let elements = &boxes.structures[0].elements;
+    assert_eq!(elements.len(), 2034);
+    assert!(elements
+        .iter()
+        .all(|e| e.header() == TuctosinHeader::Box && e.layer() == Some(1)));
+
+    let mut gds2_data = parse_gds2(format!(
+        "{}/testcases/sim_c2.gds",
+        std::env::var("CARGO_MANIFEST_DIR").unwrap(),
+    ))
+    .unwrap();
+    let boundaries = gds2_data.structures[0].elements.clone();
+    assert!(boundaries
+        .iter()
+        .all(|e| format!("{:?}", e.header()) == "Boundary" && e.sname().is_none()));
+
+    // interleave both kinds, file order has to survive the round trip
+    let mixed: Vec<_> = boundaries
+        .iter()
+        .zip(elements.iter())
+        .flat_map(|(b, x)| [b.clone(), x.clone()])
+        .collect();
+    gds2_data.structures[0].elements = mixed.clone();
+    gds2_data
+        .save_gds2(format!(
+            "{}/target/sim_c2_mixed.gds",
+            std::env::var("CARGO_MANIFEST_DIR").unwrap(),
+        ))
+        .unwrap();
+    let reparsed = parse_gds2(format!(
+        "{}/target/sim_c2_mixed.gds",
+        std::env::var("CARGO_MANIFEST_DIR").unwrap(),
+    ))
+    .unwrap();
+    assert_eq!(reparsed.structures[0].elements, mixed);
 }
@@ -0,0 +1,86 @@
+use gds2_io::{ARef, BBox, Real8, SRef, Strans, Transform};
+use proptest::prelude::*;
+
+#[test]
+fn test_quarter_turns() {
+    let p = (3, 1);
+    assert_eq!(Transform::rotate(90.0).apply(p), (-1, 3));
+    assert_eq!(Transform::rotate(180.0).apply(p), (-3, -1));
+    assert_eq!(Transform::rotate(-90.0).apply(p), (1, -3));
+    assert_eq!(Transform::rotate(450.0).apply(p), (-1, 3));
+    assert_eq!(Transform::reflect_x().apply(p), (3, -1));
+    assert_eq!(Transform::rotate(90.0).quarter_turns(), Some(1));
+    assert!(!Transform::rotate(45.0).is_manhattan());
+
+    // exact far beyond f64 trigonometry precision
+    let far = (i32::MAX - 7, -1_000_000_000);
+    let t = Transform::rotate(270.0).then(&Transform::translate(5.0, -2.0));
+    assert_eq!(t.apply(far), (-1_000_000_000 + 5, -(i32::MAX - 7) - 2));
+}
+
+#[test]
+fn test_sref_placement() {
+    let sref = SRef {
+        sname: "CELL".to_string(),
+        strans: Some(Strans {
+            reflect_x: true,
+            mag: Some(Real8::from_f64(2.0)),
+            angle: Some(Real8::from_f64(90.0)),
+            ..Default::default()
+        }),
+        origin: (100, 200),
+        ..Default::default()
+    };
+    let t = sref.transform();
+    // (10, 5) -> reflect (10, -5) -> mag (20, -10) -> rotate (10, 20) -> move
+    assert_eq!(t.apply((10, 5)), (110, 220));
+    assert_eq!(t.inverse().apply((110, 220)), (10, 5));
+    assert_eq!(
+        t.apply_box(&BBox::new((0, 0), (10, 5))),
+        BBox::new((100, 200), (110, 220))
+    );
+}
+
+#[test]
+fn test_aref_instances() {
+    let aref = ARef::new("CELL", (10, 20), 3, 2, (100, 0), (0, 50));
+    let origins: Vec<_> = aref.transforms().map(|t| t.apply((0, 0))).collect();
+    assert_eq!(
+        origins,
+        [
+            (10, 20),
+            (110, 20),
+            (210, 20),
+            (10, 70),
+            (110, 70),
+            (210, 70)
+        ]
+    );
+}
+
+fn transform() -> impl Strategy<Value = Transform> {
+    (
+        any::<bool>(),
+        prop_oneof![(0..4).prop_map(|q| q as f64 * 90.0), -360.0..360.0f64],
+        0.1..10.0f64,
+        -1e6..1e6f64,
+        -1e6..1e6f64,
+    )
+        .prop_map(|(r, a, m, x, y)| Transform::new(r, a, m, (x, y)))
+}
+
+proptest! {
+    #[test]
+    fn prop_then_matches_sequence(a in transform(), b in transform(), x in -1e5..1e5f64, y in -1e5..1e5f64) {
+        let (sx, sy) = b.apply_f64(a.apply_f64((x, y)));
+        let (cx, cy) = a.then(&b).apply_f64((x, y));
+        let tol = 1e-6 * (1.0 + sx.abs() + sy.abs());
+        prop_assert!((sx - cx).abs() < tol && (sy - cy).abs() < tol);
+    }
+
+    #[test]
+    fn prop_inverse(t in transform(), x in -1e5..1e5f64, y in -1e5..1e5f64) {
+        let (ix, iy) = t.inverse().apply_f64(t.apply_f64((x, y)));
+        prop_assert!((ix - x).abs() < 1e-6 && (iy - y).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,134 @@
+use gds2_io::{parse_bytes, parse_gds2, Boundary, Element, GDSIIErrorKind, GdsWriter, SRef};
+
+#[test]
+fn test_stream_testcase() {
+    let path = format!(
+        "{}/testcases/sim_c2.gds",
+        std::env::var("CARGO_MANIFEST_DIR").unwrap()
+    );
+    let lib = parse_gds2(&path).unwrap();
+    let mut writer = GdsWriter::new(vec![]);
+    writer.begin_library_with(&lib.header).unwrap();
+    for s in &lib.structures {
+        writer
+            .begin_structure_with(&s.name, s.created, s.modified)
+            .unwrap();
+        for e in &s.elements {
+            writer.write_element(e).unwrap();
+        }
+        writer.end_structure().unwrap();
+    }
+    let mut data = writer.finish().unwrap();
+    data.extend(&lib.tail);
+    assert_eq!(data, std::fs::read(path).unwrap());
+}
+
+#[test]
+fn test_generated() {
+    let mut writer = GdsWriter::new(vec![]);
+    writer.begin_library("GEN", (1e-3, 1e-9)).unwrap();
+    writer.begin_structure("CELL").unwrap();
+    for i in 0..100 {
+        writer
+            .write_boundary(Boundary {
+                layer: 1,
+                points: vec![(i, 0), (i + 1, 0), (i + 1, 1), (i, 0)],
+                ..Default::default()
+            })
+            .unwrap();
+    }
+    writer.end_structure().unwrap();
+    writer.begin_structure("TOP").unwrap();
+    writer
+        .write_sref(SRef {
+            sname: "CELL".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+    writer.end_structure().unwrap();
+    let data = writer.finish().unwrap();
+
+    let path = format!(
+        "{}/target/writer_generated.gds",
+        std::env::var("CARGO_MANIFEST_DIR").unwrap()
+    );
+    std::fs::write(&path, data).unwrap();
+    let lib = parse_gds2(&path).unwrap();
+    assert_eq!(lib.structure_names().collect::<Vec<_>>(), ["CELL", "TOP"]);
+    assert_eq!(lib.structures[0].elements.len(), 100);
+    assert_eq!(lib.structures[1].elements[0].sname(), Some("CELL"));
+    assert_eq!(lib.units(), Some((1e-3, 1e-9)));
+}
+
+#[test]
+fn test_nesting() {
+    let mut writer = GdsWriter::new(vec![]);
+    match writer.write_boundary(Boundary::default()) {
+        Err(e @ GDSIIErrorKind::InvalidNesting { .. }) => {
+            assert_eq!(e.to_string(), "cannot write BOUNDARY before BGNLIB")
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+    writer.begin_library("LIB", (1e-3, 1e-9)).unwrap();
+    assert!(writer.end_structure().is_err());
+    writer.begin_structure("A").unwrap();
+    assert!(writer.begin_structure("B").is_err());
+    // the failed calls wrote nothing
+    writer.end_structure().unwrap();
+    let data = writer.finish().unwrap();
+    let path = format!(
+        "{}/target/writer_nesting.gds",
+        std::env::var("CARGO_MANIFEST_DIR").unwrap()
+    );
+    std::fs::write(&path, data).unwrap();
+    assert_eq!(parse_gds2(&path).unwrap().structures.len(), 1);
+
+    let mut writer = GdsWriter::new(vec![]);
+    writer.begin_library("LIB", (1e-3, 1e-9)).unwrap();
+    writer.begin_structure("A").unwrap();
+    match writer.finish() {
+        Err(e) => assert_eq!(e.to_string(), "cannot write ENDLIB inside a structure"),
+        Ok(_) => panic!("finished with an open structure"),
+    }
+}
+
+#[test]
+fn test_record_too_long() {
+    let boundary = |n: i32| Boundary {
+        layer: 1,
+        points: (0..n).map(|i| (i, 0)).collect(),
+        ..Default::default()
+    };
+    let mut writer = GdsWriter::new(vec![]);
+    writer.begin_library("LIB", (1e-3, 1e-9)).unwrap();
+    writer.begin_structure("TOP").unwrap();
+    // 8191 points fill XY to 65532 bytes, one more does not fit
+    writer.write_boundary(boundary(8191)).unwrap();
+    match writer.write_boundary(boundary(8192)) {
+        Err(e @ GDSIIErrorKind::RecordTooLong { .. }) => assert_eq!(
+            e.to_string(),
+            "XY record holds 65536 bytes of data, a record holds at most 65530"
+        ),
+        other => panic!("unexpected result {:?}", other),
+    }
+    writer.end_structure().unwrap();
+    let mut lib = parse_bytes(&writer.finish().unwrap()).unwrap();
+    assert_eq!(lib.structures[0].elements.len(), 1);
+
+    lib.structures[0]
+        .elements
+        .push(Element::Boundary(boundary(8192)));
+    assert!(matches!(
+        lib.to_bytes(),
+        Err(GDSIIErrorKind::RecordTooLong { record: "XY", .. })
+    ));
+    lib.structures[0].elements.clear();
+    lib.structures[0].name = "N".repeat(65531);
+    assert!(matches!(
+        lib.to_bytes(),
+        Err(GDSIIErrorKind::RecordTooLong {
+            record: "STRNAME",
+            length: 65532
+        })
+    ));
+}